@@ -0,0 +1,68 @@
+//! Compares the byte-per-bit `pattern_data` store with `PackedPattern` on a
+//! large generated pattern: heap size, decode and encode, column scans and
+//! per-bit counts.
+//!
+//! Run with `cargo bench --features packed --bench pattern_store [cycles]`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use pcf_parser::generate::{generate, GenOptions};
+use pcf_parser::layout::{PcfLayout, NUM_BITS};
+use pcf_parser::{decode_packed_with, decode_with, encode_packed_with, encode_to_vec, DecodeOptions, PackedPatternFile};
+
+fn time<T>(label: &str, mut f: impl FnMut() -> T) -> Duration {
+    const ROUNDS: u32 = 5;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+    let per_round = start.elapsed() / ROUNDS;
+    println!("  {:<28} {:>10.2?}", label, per_round);
+    per_round
+}
+
+fn main() {
+    let cycles = std::env::args()
+        .skip(1)
+        .find_map(|a| a.parse().ok())
+        .unwrap_or(2_000_000);
+    let data = generate(&GenOptions { cycles, seed: 1, density: 0.5 }).expect("generate pattern");
+    let packed = data.to_packed().expect("generated patterns hold only 0 and 1");
+    let packed_file = PackedPatternFile::from_unpacked(&data).expect("generated patterns hold only 0 and 1");
+    let bytes = encode_to_vec(&data).expect("generated patterns encode");
+    let opts = DecodeOptions { total_len: Some(bytes.len()), ..Default::default() };
+
+    let bytes_store: usize = data.pattern_data.iter().map(Vec::capacity).sum();
+    println!("{} cycles x {} bits", cycles, NUM_BITS);
+    println!("memory");
+    println!("  {:<28} {:>10} bytes", "Vec<Vec<u8>>", bytes_store);
+    println!("  {:<28} {:>10} bytes ({:.1}x smaller)", "PackedPattern", packed.heap_bytes(), bytes_store as f64 / packed.heap_bytes() as f64);
+
+    println!("decode");
+    time("Vec<Vec<u8>>", || decode_with(&mut &bytes[..], &opts).unwrap());
+    time("PackedPattern", || decode_packed_with(&mut &bytes[..], &opts).unwrap());
+
+    println!("encode");
+    time("Vec<Vec<u8>>", || encode_to_vec(&data));
+    time("PackedPattern", || {
+        let mut out = Vec::with_capacity(bytes.len());
+        encode_packed_with(&mut out, &packed_file, &PcfLayout::default()).unwrap();
+        out
+    });
+
+    println!("column scan (xor of every column)");
+    let fold = |acc: [u8; NUM_BITS], col: [u8; NUM_BITS]| core::array::from_fn(|i| acc[i] ^ col[i]);
+    time("Vec<Vec<u8>>", || data.columns().fold([0; NUM_BITS], fold));
+    time("PackedPattern", || packed.columns().fold([0; NUM_BITS], fold));
+
+    println!("set bits per bit");
+    time("Vec<Vec<u8>>", || {
+        (0..NUM_BITS).map(|bit| data.pattern_data[bit].iter().filter(|&&v| v != 0).count()).sum::<usize>()
+    });
+    time("PackedPattern", || (0..NUM_BITS).filter_map(|bit| packed.count_ones(bit)).sum::<usize>());
+
+    println!("conversion");
+    time("pack", || data.to_packed());
+    time("unpack", || packed.to_rows());
+}
@@ -0,0 +1,405 @@
+//! Operations over many files at once.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use serde::Serialize;
+
+use crate::inspect::header_looks_like_pcf;
+use crate::layout::header_size;
+use crate::pattern::{parse_pcf_file, parse_pcf_file_strict, PatternFileData};
+use crate::validate::ValidationIssue;
+
+/// Validation result for one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub path: PathBuf,
+    pub issues: Vec<ValidationIssue>,
+    /// Set when the file could not be parsed at all.
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// A parse failure counts as one issue.
+    pub fn issue_count(&self) -> usize {
+        self.issues.len() + usize::from(self.error.is_some())
+    }
+
+    /// Human-readable first problem, if any.
+    pub fn first_issue(&self) -> Option<String> {
+        self.error.clone().or_else(|| self.issues.first().map(ToString::to_string))
+    }
+}
+
+/// `.pcf` files (case-insensitive) directly inside `dir`, sorted by path.
+pub fn pcf_files_in<P: AsRef<Path>>(dir: P) -> io::Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && has_pcf_extension(&path) {
+            out.push(path);
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+fn has_pcf_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e.eq_ignore_ascii_case("pcf"))
+}
+
+/// How [`expand_paths`] turns command-line arguments into files.
+#[derive(Debug, Clone)]
+pub struct ExpandOptions {
+    /// Descend into subdirectories of directory arguments.
+    pub recursive: bool,
+    /// Walk through symlinked directories (each real directory is visited once).
+    pub follow_symlinks: bool,
+    /// Keep only files with this extension (case-insensitive); `None` keeps all.
+    pub extension: Option<String>,
+    /// Also keep only files whose header passes [`looks_like_pcf`](crate::looks_like_pcf).
+    pub sniff: bool,
+}
+
+impl Default for ExpandOptions {
+    fn default() -> Self {
+        ExpandOptions { recursive: false, follow_symlinks: false, extension: Some("pcf".into()), sniff: false }
+    }
+}
+
+/// Characters that make a path component a pattern.
+const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+
+/// Component matching for patterns: case-insensitive, like the extension
+/// filter, and `*` matches a leading dot.
+const GLOB_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// One component of a path pattern.
+enum Segment {
+    /// `**`: any number of directories, including none.
+    Recursive,
+    Pattern(glob::Pattern),
+}
+
+/// Expands files, directories and glob patterns into a sorted, de-duplicated
+/// file list without relying on the shell.
+///
+/// Patterns use [`glob::Pattern`] syntax within a path component (`*`, `?`,
+/// `[abc]`, `[!a-z]`), matched case-insensitively, and `**` for any number of
+/// directories. Explicitly named files are always kept; files found through
+/// a directory or pattern are filtered by [`ExpandOptions::extension`] and
+/// [`ExpandOptions::sniff`]. An input that yields no files is a `NotFound`
+/// error, so a mistyped path cannot pass as an empty, clean batch.
+pub fn expand_paths<S: AsRef<str>>(inputs: &[S], opts: &ExpandOptions) -> io::Result<Vec<PathBuf>> {
+    let mut walker = Walker { opts, visited: HashSet::new(), out: Vec::new() };
+
+    for input in inputs {
+        let input = input.as_ref();
+        let path = Path::new(input);
+        let before = walker.out.len();
+        if input.contains(GLOB_CHARS) {
+            let mut base = PathBuf::new();
+            let mut rest = Vec::new();
+            for comp in path.components() {
+                let text = comp.as_os_str().to_string_lossy();
+                if rest.is_empty() && !text.contains(GLOB_CHARS) {
+                    base.push(comp);
+                } else if text == "**" {
+                    rest.push(Segment::Recursive);
+                } else {
+                    let pattern = glob::Pattern::new(&text).map_err(|e| {
+                        io::Error::new(io::ErrorKind::InvalidInput, format!("{}: bad pattern: {}", input, e))
+                    })?;
+                    rest.push(Segment::Pattern(pattern));
+                }
+            }
+            walker.glob(&base, &rest)?;
+        } else if path.is_dir() {
+            walker.dir(path)?;
+        } else if path.is_file() {
+            walker.out.push(path.to_path_buf());
+        } else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{}: no such file or directory", input)));
+        }
+        if walker.out.len() == before {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{}: no matching files", input)));
+        }
+    }
+
+    let mut out = walker.out;
+    out.sort();
+    out.dedup();
+    Ok(out)
+}
+
+struct Walker<'a> {
+    opts: &'a ExpandOptions,
+    visited: HashSet<PathBuf>,
+    out: Vec<PathBuf>,
+}
+
+impl Walker<'_> {
+    fn keep(&self, path: &Path) -> bool {
+        let ext_ok = match &self.opts.extension {
+            Some(ext) => path.extension().is_some_and(|e| e.eq_ignore_ascii_case(ext)),
+            None => true,
+        };
+        ext_ok && (!self.opts.sniff || sniff_pcf(path))
+    }
+
+    /// Entries of `dir`, split into files and walkable subdirectories. An
+    /// empty `dir` means the current directory, keeping results relative.
+    fn entries(&mut self, dir: &Path) -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+        let (mut files, mut dirs) = (Vec::new(), Vec::new());
+        let read_from = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        if !read_from.is_dir() {
+            return Ok((files, dirs));
+        }
+        for entry in fs::read_dir(read_from)? {
+            let entry = entry?;
+            let path = dir.join(entry.file_name());
+            let kind = entry.file_type()?;
+            if kind.is_symlink() {
+                if !self.opts.follow_symlinks {
+                    continue;
+                }
+                if path.is_dir() {
+                    if self.visited.insert(fs::canonicalize(&path)?) {
+                        dirs.push(path);
+                    }
+                } else if path.is_file() {
+                    files.push(path);
+                }
+            } else if kind.is_dir() {
+                dirs.push(path);
+            } else if kind.is_file() {
+                files.push(path);
+            }
+        }
+        Ok((files, dirs))
+    }
+
+    fn dir(&mut self, dir: &Path) -> io::Result<()> {
+        let (files, dirs) = self.entries(dir)?;
+        let kept: Vec<_> = files.into_iter().filter(|f| self.keep(f)).collect();
+        self.out.extend(kept);
+        if self.opts.recursive {
+            for sub in dirs {
+                self.dir(&sub)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn glob(&mut self, base: &Path, rest: &[Segment]) -> io::Result<()> {
+        let Some((seg, tail)) = rest.split_first() else {
+            if base.is_file() && self.keep(base) {
+                self.out.push(base.to_path_buf());
+            }
+            return Ok(());
+        };
+
+        let (files, dirs) = self.entries(base)?;
+        let pattern = match seg {
+            Segment::Recursive => {
+                self.glob(base, tail)?;
+                for sub in dirs {
+                    self.glob(&sub, rest)?;
+                }
+                return Ok(());
+            }
+            Segment::Pattern(pattern) => pattern,
+        };
+
+        for path in files.into_iter().chain(dirs) {
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            if pattern.matches_with(&name, GLOB_OPTIONS) {
+                self.glob(&path, tail)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads just the header of `path` for [`header_looks_like_pcf`]; unreadable
+/// files do not look like PCF.
+fn sniff_pcf(path: &Path) -> bool {
+    let Ok(mut file) = File::open(path) else { return false };
+    let Ok(total) = file.metadata().map(|m| m.len() as usize) else { return false };
+    let mut header = vec![0u8; header_size()];
+    file.read_exact(&mut header).is_ok() && header_looks_like_pcf(&header, total)
+}
+
+/// Parses and validates every path, spreading the work over the available
+/// cores. Results are returned in the order of `paths`.
+pub fn audit_paths(paths: &[PathBuf], strict: bool) -> Vec<AuditEntry> {
+    let audit_one = |path: &PathBuf| {
+        let parsed = if strict { parse_pcf_file_strict(path) } else { parse_pcf_file(path) };
+        match parsed {
+            Ok(data) => AuditEntry { path: path.clone(), issues: data.validate(), error: None },
+            Err(e) => AuditEntry { path: path.clone(), issues: Vec::new(), error: Some(e.to_string()) },
+        }
+    };
+
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = paths.len().div_ceil(workers).max(1);
+
+    thread::scope(|s| {
+        let handles: Vec<_> = paths
+            .chunks(chunk)
+            .map(|part| s.spawn(move || part.iter().map(audit_one).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("audit worker panicked"))
+            .collect()
+    })
+}
+
+/// Files grouped by [`group_by_content`].
+#[derive(Debug, Clone, Default)]
+pub struct ContentGroups {
+    /// Paths per distinct record, each list in the order they were given.
+    pub groups: HashMap<PatternFileData, Vec<PathBuf>>,
+    /// Files that could not be parsed, with the error.
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl ContentGroups {
+    /// Groups of two or more files, sorted by their paths.
+    pub fn duplicates(&self) -> Vec<&[PathBuf]> {
+        let mut out: Vec<&[PathBuf]> = self.groups.values().filter(|g| g.len() > 1).map(Vec::as_slice).collect();
+        out.sort();
+        out
+    }
+}
+
+/// Groups `paths` by decoded content, so files holding the same record share
+/// one key whatever they are called.
+pub fn group_by_content(paths: &[PathBuf], strict: bool) -> ContentGroups {
+    let mut out = ContentGroups::default();
+    for path in paths {
+        let parsed = if strict { parse_pcf_file_strict(path) } else { parse_pcf_file(path) };
+        match parsed {
+            Ok(data) => out.groups.entry(data).or_default().push(path.clone()),
+            Err(e) => out.failed.push((path.clone(), e.to_string())),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::write_pcf_file;
+
+    #[test]
+    fn audit_directory_reports_per_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let sample = || PatternFileData::builder().pattern_file_length(2).build().unwrap();
+
+        let mut clean = sample();
+        clean.fill_pattern(1);
+        write_pcf_file(dir.path().join("a.pcf"), &clean).unwrap();
+
+        let mut blank = sample();
+        blank.clear_pattern();
+        write_pcf_file(dir.path().join("b.PCF"), &blank).unwrap();
+
+        fs::write(dir.path().join("c.pcf"), b"short").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+
+        let files = pcf_files_in(dir.path()).unwrap();
+        assert_eq!(files.len(), 3);
+
+        let report = audit_paths(&files, false);
+        let counts: Vec<usize> = report.iter().map(AuditEntry::issue_count).collect();
+        assert_eq!(counts, vec![0, 1, 1]);
+        assert_eq!(report[1].issues, vec![ValidationIssue::BlankPattern]);
+        assert!(report[2].error.is_some());
+    }
+
+    #[test]
+    fn group_by_content_ignores_file_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut data = PatternFileData::builder().pattern_file_length(3).build().unwrap();
+        write_pcf_file(dir.path().join("a.pcf"), &data).unwrap();
+        write_pcf_file(dir.path().join("copy of a.pcf"), &data).unwrap();
+        data.pattern_data[4][1] = 1;
+        write_pcf_file(dir.path().join("b.pcf"), &data).unwrap();
+        fs::write(dir.path().join("c.pcf"), b"short").unwrap();
+
+        let files = pcf_files_in(dir.path()).unwrap();
+        let grouped = group_by_content(&files, false);
+        assert_eq!(grouped.groups.len(), 2);
+        assert_eq!(grouped.groups[&data], vec![dir.path().join("b.pcf")]);
+        data.pattern_data[4][1] = 0;
+        let copies = vec![dir.path().join("a.pcf"), dir.path().join("copy of a.pcf")];
+        assert_eq!(grouped.groups[&data], copies);
+        assert_eq!(grouped.duplicates(), vec![copies.as_slice()]);
+        assert_eq!(grouped.failed.len(), 1);
+        assert_eq!(grouped.failed[0].0, dir.path().join("c.pcf"));
+    }
+
+    #[test]
+    fn component_patterns_follow_glob_syntax() {
+        let matches = |p: &str, name: &str| glob::Pattern::new(p).unwrap().matches_with(name, GLOB_OPTIONS);
+        assert!(matches("*.pcf", "a.pcf"));
+        assert!(matches("*.pcf", "B.PCF"));
+        assert!(matches("T?ST*", "test1.pcf"));
+        assert!(matches("run[0-9].pcf", "run7.pcf"));
+        assert!(!matches("run[!0-9].pcf", "run7.pcf"));
+        assert!(!matches("*.pcf", "a.pcf.bak"));
+        assert!(!matches("a?c", "ac"));
+    }
+
+    #[test]
+    fn expand_paths_walks_globs_and_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("x/y")).unwrap();
+        for f in ["top.pcf", "x/mid.PCF", "x/y/deep.pcf", "x/y/skip.txt"] {
+            fs::write(root.join(f), b"").unwrap();
+        }
+
+        let flat = expand_paths(&[root.to_str().unwrap()], &ExpandOptions::default()).unwrap();
+        assert_eq!(flat, vec![root.join("top.pcf")]);
+
+        let opts = ExpandOptions { recursive: true, ..Default::default() };
+        let deep = expand_paths(&[root.to_str().unwrap()], &opts).unwrap();
+        assert_eq!(deep.len(), 3);
+
+        let pattern = format!("{}/**/*.pcf", root.display());
+        let globbed = expand_paths(&[pattern], &ExpandOptions::default()).unwrap();
+        assert_eq!(globbed, vec![root.join("top.pcf"), root.join("x/mid.PCF"), root.join("x/y/deep.pcf")]);
+
+        let classes = format!("{}/[xz]/*.pcf", root.display());
+        assert_eq!(expand_paths(&[classes], &ExpandOptions::default()).unwrap(), vec![root.join("x/mid.PCF")]);
+
+        let any = ExpandOptions { recursive: true, extension: None, ..Default::default() };
+        assert_eq!(expand_paths(&[root.to_str().unwrap()], &any).unwrap().len(), 4);
+
+        write_pcf_file(root.join("x/real.bin"), &PatternFileData::new()).unwrap();
+        let sniffed = ExpandOptions { sniff: true, ..any };
+        assert_eq!(expand_paths(&[root.to_str().unwrap()], &sniffed).unwrap(), vec![root.join("x/real.bin")]);
+
+        assert!(expand_paths(&[root.join("missing").to_str().unwrap()], &opts).is_err());
+
+        let typo = format!("{}/**/*.pfc", root.display());
+        let err = expand_paths(&[typo], &opts).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let empty = root.join("x/y/empty");
+        fs::create_dir(&empty).unwrap();
+        assert!(expand_paths(&[empty.to_str().unwrap()], &ExpandOptions::default()).is_err());
+        let bad = format!("{}/[x", root.display());
+        assert_eq!(expand_paths(&[bad], &opts).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
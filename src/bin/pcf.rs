@@ -1,17 +1,44 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use owo_colors::OwoColorize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 
 use pcf_parser::{
-    parse_pcf_file, write_pcf_file,
-    hex_dump_file, diff_files, diff_blocks, PatternFileData,
+    parse_pcf_file_with, parse_pcf_multi_with, write_pcf_file_with,
+    hex_dump_file_with, hex_dump_html, lcs_diff, DiffOp, diff_files_streaming_with, diff_buffers_with, diff_buffers_lines, dump_merged, diff_side_by_side_with, SideBySideOptions, diff_blocks_with, diff_blocks_report, diff_pattern_blocks_report, diff_summary, BlockDiffOptions, DumpColumns, DumpOptions, IntFormat, OffsetBase, PatternFileData,
+    active_bits_diff, bit_diff, parse_pattern_region, first_diff_cycle, looks_like_pcf, render_waveform, loop_config_diff, decode_with, encode_with, guess_header_size, inspect_field, DecodeOptions, HeaderField, HeaderOnly, ValidationIssue,
+    batch::{audit_paths, expand_paths, group_by_content, ExpandOptions},
+    codec::DEFAULT_MAX_PATTERN_BYTES,
+    generate::{generate, GenOptions},
+    hash::fnv1a64,
+    layout::{header_size, PcfLayout, FIELD_WIDTH, NUM_BITS, PATTERN_PAD_COLS},
+    utils::{equate_header_fields, TextNormalize},
+    bitgrid::{read_bitgrid, write_bitgrid},
+    vcd::{read_vcd_limited, DEFAULT_MAX_VCD_CYCLES},
+    timing::format_nanos,
 };
 
 /// PCF – pattern-file command-line toolkit
 #[derive(Parser)]
 #[command(name = "pcf", version, about = "Read, diff and write .pcf files", arg_required_else_help = true)]
 struct Cli {
+    /// Reject malformed header fields instead of defaulting them
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Keep any bytes after the pattern region (a footer or checksum) and write them back
+    #[arg(long, global = true)]
+    tail_bytes: bool,
+
+    /// Width in bytes of each numeric header field, for files that do not use the standard 10
+    #[arg(long, global = true, default_value_t = FIELD_WIDTH)]
+    numeric_width: usize,
+
+    /// Width in bytes of each text header field, for files that do not use the standard 10
+    #[arg(long, global = true, default_value_t = FIELD_WIDTH)]
+    string_width: usize,
+
     #[command(subcommand)]
     cmd: Command,
 }
@@ -26,6 +53,54 @@ enum Command {
         /// Emit as JSON
         #[arg(long)]
         json: bool,
+
+        /// Emit as single-line JSON without whitespace
+        #[arg(long, conflicts_with = "json")]
+        json_compact: bool,
+
+        /// Leave `pattern_data` out of the JSON output
+        #[arg(long)]
+        no_pattern: bool,
+
+        /// Emit JSON with only these comma-separated fields, e.g. version,loop_counts
+        #[arg(long, value_delimiter = ',', conflicts_with = "no_pattern")]
+        fields: Option<Vec<String>>,
+
+        /// Truncate summary lines to N characters (default: terminal width)
+        #[arg(long, visible_alias = "max-width")]
+        width: Option<usize>,
+
+        /// Print every field, including the pattern, instead of the summary
+        #[arg(long, conflicts_with_all = ["json", "json_compact", "fields"])]
+        debug: bool,
+
+        /// After the summary, print the first N cycles as bit strings (bit 0 first)
+        #[arg(long, value_name = "N", conflicts_with_all = ["json", "json_compact", "fields", "debug"])]
+        show_cycles: Option<usize>,
+    },
+
+    /// Lists every record in a file of concatenated PCF records
+    ParseMulti {
+        /// Path to the bundled .pcf file
+        file: PathBuf,
+
+        /// Emit the records as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Writes each record of a concatenated bundle to its own file
+    Split {
+        /// Path to the bundled .pcf file
+        file: PathBuf,
+
+        /// Directory for the records (created if missing)
+        #[arg(long)]
+        out_dir: PathBuf,
+
+        /// File name per record; `{n}` becomes the zero-padded record number
+        #[arg(long, default_value = "{n}.pcf", value_parser = parse_name_template)]
+        template: String,
     },
 
     /// Hex-dumps the entire file
@@ -33,9 +108,53 @@ enum Command {
         /// Path to the .pcf file
         file: PathBuf,
 
-        /// Bytes per line
-        #[arg(long, default_value_t = 16, value_parser = parse_byte_range)]
-        bytes: usize,
+        /// Bytes per line [default: 16, or 64 with --ascii-only]
+        #[arg(long, value_parser = parse_byte_range)]
+        bytes: Option<usize>,
+
+        /// Print only every Nth line (0 or 1 = every line)
+        #[arg(long, default_value_t = 1)]
+        every: usize,
+
+        /// Write a styled HTML dump to this path instead of the terminal
+        #[arg(long)]
+        html: Option<PathBuf>,
+
+        /// Highlight bytes that differ from this file (HTML output only)
+        #[arg(long, requires = "html")]
+        compare: Option<PathBuf>,
+
+        /// Radix for printed offsets: hex or dec
+        #[arg(long, default_value = "hex", value_parser = parse_offset_base)]
+        offset_base: OffsetBase,
+
+        /// Append a one-byte XOR checksum to each line
+        #[arg(long)]
+        rowsum: bool,
+
+        /// Add an extra space after every N bytes
+        #[arg(long, default_value_t = 0)]
+        group: usize,
+
+        /// Print hex digits in lower case (upper case is the default)
+        #[arg(long, overrides_with = "uppercase")]
+        lowercase: bool,
+
+        /// Print hex digits in upper case
+        #[arg(long, overrides_with = "lowercase")]
+        uppercase: bool,
+
+        /// Reprint the column ruler every N lines (0 = never)
+        #[arg(long, default_value_t = 0, conflicts_with = "html")]
+        repeat_header: usize,
+
+        /// Print only the hex bytes, without the ASCII column
+        #[arg(long, conflicts_with_all = ["html", "ascii_only"])]
+        hex_only: bool,
+
+        /// Print only the ASCII column, to read embedded text such as clock names
+        #[arg(long, conflicts_with = "html")]
+        ascii_only: bool,
     },
 
     /// Byte-by-byte diff
@@ -46,179 +165,2286 @@ enum Command {
         /// Show N bytes before/after mismatch
         #[arg(long, default_value_t = 8)]
         context: usize,
+
+        /// Instead, show every difference as hex-dump lines with N lines of context
+        #[arg(long, conflicts_with = "stream", group = "line_mode")]
+        context_lines: Option<usize>,
+
+        /// Instead, dump every line of A, each followed by B's line when they
+        /// differ, with the changed bytes marked underneath
+        #[arg(long, conflicts_with_all = ["stream", "offset_base"], group = "line_mode")]
+        merged: bool,
+
+        /// Instead, dump A and B side by side, one line of each per row, with
+        /// `|` between lines that differ
+        #[arg(long, conflicts_with = "stream", group = "line_mode")]
+        side_by_side: bool,
+
+        /// With --side-by-side, fold each run of identical lines into one
+        #[arg(long, requires = "side_by_side")]
+        collapse: bool,
+
+        /// Highlight differing bytes in --side-by-side output: auto (when
+        /// writing to a terminal and NO_COLOR is unset), always or never
+        #[arg(long, default_value = "auto", value_parser = ["auto", "always", "never"], requires = "side_by_side")]
+        color: String,
+
+        /// Bytes per line for --context-lines, --merged and --side-by-side
+        #[arg(long, default_value_t = 16, value_parser = parse_byte_range, requires = "line_mode")]
+        bytes: usize,
+
+        /// Radix for printed offsets: hex or dec
+        #[arg(long, default_value = "hex", value_parser = parse_offset_base)]
+        offset_base: OffsetBase,
+
+        /// Compare through fixed-size buffers instead of loading both files
+        #[arg(long)]
+        stream: bool,
+
+        /// Compare only the pattern region; offsets are relative to its start
+        #[arg(long, conflicts_with_all = ["header", "stream"])]
+        pattern: bool,
+
+        /// Compare only the header region
+        #[arg(long, conflicts_with = "stream")]
+        header: bool,
+
+        /// Align the header regions with a longest-common-subsequence diff and
+        /// list inserted and deleted runs, for files shifted by a few bytes
+        #[arg(long, conflicts_with_all = ["stream", "pattern", "line_mode"])]
+        lcs: bool,
+
+        /// Treat header fields that differ only in padding or whitespace as equal
+        #[arg(long, conflicts_with_all = ["stream", "pattern"])]
+        ignore_ascii_ws: bool,
+
+        /// Treat header fields that differ only in ASCII case as equal
+        #[arg(long, conflicts_with_all = ["stream", "pattern"])]
+        ignore_ascii_case: bool,
+
+        /// Finish with a count of every differing byte and 18-byte block
+        #[arg(long, conflicts_with = "stream")]
+        summary: bool,
+
+        /// Print only the summary, as JSON
+        #[arg(long, requires = "summary")]
+        json: bool,
+
+        /// Treat file B as the base: report as if the arguments were given in reverse order
+        #[arg(long)]
+        swap: bool,
+    },
+
+    /// Block diff (18-byte rows)
+    DiffBlocks {
+        file_a: PathBuf,
+        file_b: PathBuf,
+
+        /// Bytes per block (default 18 for PCF pattern row)
+        #[arg(long, default_value_t = 18)]
+        block: usize,
+
+        /// Max mismatched blocks to show
+        #[arg(long, default_value_t = 10)]
+        max: usize,
+
+        /// Show each block's one-byte XOR checksum for both files
+        #[arg(long)]
+        rowsum: bool,
+
+        /// Show the blocks with the most differing bytes first
+        #[arg(long)]
+        sort_by_changes: bool,
+
+        /// Skip the header and compare only the pattern region, labelling
+        /// blocks by cycle
+        #[arg(long = "diff-only-pattern", visible_alias = "pattern")]
+        pattern_only: bool,
+
+        /// Finish with a count of every differing byte and block
+        #[arg(long)]
+        summary: bool,
+
+        /// Emit every differing block (and the summary, if requested) as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Treat file B as the base: report as if the arguments were given in reverse order
+        #[arg(long)]
+        swap: bool,
+    },
+
+    /// Prints one bit's value across all cycles
+    Bit {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Bit index (0–17)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..18))]
+        bit: u8,
+
+        /// Print a plain 0/1 string instead of run lengths
+        #[arg(long)]
+        raw: bool,
+    },
+
+    /// Counts cycles matching a column value or a single bit; prints only the count
+    #[command(group(clap::ArgGroup::new("query").required(true).args(["equals", "bit"])))]
+    Count {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// 18-character 0/1 column, bit 0 first
+        #[arg(long, value_parser = parse_column_bits)]
+        equals: Option<[u8; 18]>,
+
+        /// Bit index (0–17) to test
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..18))]
+        bit: Option<u8>,
+
+        /// Value the bit must have
+        #[arg(long, requires = "bit", default_value_t = 1, value_parser = clap::value_parser!(u8).range(0..2))]
+        value: u8,
+
+        /// Restrict to cycles FROM:TO (end exclusive, either side may be omitted)
+        #[arg(long, value_parser = parse_cycle_range)]
+        range: Option<(usize, usize)>,
+    },
+
+    /// Draws the chosen bits as a text waveform over a window of cycles
+    Wave {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Comma-separated bit indices (0–17)
+        #[arg(long, required = true, value_delimiter = ',', value_parser = clap::value_parser!(u8).range(0..18))]
+        bits: Vec<u8>,
+
+        /// First cycle to draw
+        #[arg(long, default_value_t = 0)]
+        from: usize,
+
+        /// Cycle to stop before (default: as many as fit the terminal, or 64)
+        #[arg(long)]
+        to: Option<usize>,
+    },
+
+    /// Lists the most frequent column values (bit 0 first) with their counts
+    Histogram {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Number of entries to show
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// Reports the assumed layout, header and cycle-count checks, and whether the file round-trips
+    Info {
+        /// Path to the .pcf file
+        file: PathBuf,
+    },
+
+    /// Prints per-bit set and transition counts, used clock slots and loops
+    Stats {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Emit as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Lists the bits that toggle inside each used loop region, with counts
+    LoopActivity {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Emit as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Runs semantic checks; exits non-zero if any issue is found
+    Validate {
+        /// Path to the .pcf file
+        file: PathBuf,
+    },
+
+    /// Writes a loop-free copy with every loop body repeated in place
+    Expand {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Path to output .pcf file
+        pcf_out: PathBuf,
+
+        /// Refuse to expand beyond this many cycles
+        #[arg(long, default_value_t = 10_000_000)]
+        max_cycles: u64,
+    },
+
+    /// Prints when each executed cycle starts, from loop expansion and cycle_time
+    Timeline {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Print only the executed-cycle count and total run time
+        #[arg(long)]
+        summary: bool,
+
+        /// Refuse to list more than this many executed cycles
+        #[arg(long, default_value_t = 100_000)]
+        max_cycles: u64,
+
+        /// Emit as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Writes one loop's body as a pattern of its own, looped the same number of times
+    ExtractLoop {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Loop index (0–7)
+        #[arg(long = "loop", value_parser = clap::value_parser!(u8).range(0..8))]
+        index: u8,
+
+        /// Path to output .pcf file
+        pcf_out: PathBuf,
+    },
+
+    /// Compares only the loop configuration (start/end/count per loop)
+    DiffLoops {
+        file_a: PathBuf,
+        file_b: PathBuf,
+
+        /// Emit as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compares pattern content: bits gained/lost and the number of changed cycles
+    DiffPattern {
+        file_a: PathBuf,
+        file_b: PathBuf,
+
+        /// Emit the active-bit changes as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Also count differing bits, in total and per bit (needs equal cycle counts)
+        #[arg(long)]
+        bits: bool,
+    },
+
+    /// Reports the first cycle at which two patterns differ, with both bit strings
+    FirstDiffCycle {
+        file_a: PathBuf,
+        file_b: PathBuf,
+    },
+
+    /// Writes a reproducible pseudo-random pattern, for benchmarks and demos
+    Gen {
+        /// Output .pcf path
+        pcf_out: PathBuf,
+
+        /// Pattern columns, including the 20 trailing pad columns
+        #[arg(long, default_value_t = 1000)]
+        cycles: usize,
+
+        /// RNG seed; the same seed always gives the same file
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// Probability (0–1) that each bit is set
+        #[arg(long, default_value_t = 0.5)]
+        density: f64,
+    },
+
+    /// Applies a JSON Patch (or a field → value object) and writes the result
+    Patch {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// JSON file holding the patch
+        patch_json: PathBuf,
+
+        /// Output .pcf path
+        pcf_out: PathBuf,
+    },
+
+    /// Forces bits low or high in every cycle
+    #[command(group(clap::ArgGroup::new("edits").required(true).multiple(true).args(["clear_bit", "set_bit"])))]
+    Edit {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Bit to force to 0 everywhere (repeatable)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..18))]
+        clear_bit: Vec<u8>,
+
+        /// Bit to force to 1 everywhere (repeatable)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..18))]
+        set_bit: Vec<u8>,
+
+        /// Write to this path instead of overwriting the input
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Renames a clock source in place (slot indices are unchanged)
+    #[command(group(clap::ArgGroup::new("target").required(true).args(["slot", "from"])))]
+    ReplaceClk {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Clock slot to rename (1–64)
+        #[arg(long, requires = "name")]
+        slot: Option<usize>,
+
+        /// New name for --slot
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Existing clock name to rename
+        #[arg(long, requires = "to", conflicts_with = "slot")]
+        from: Option<String>,
+
+        /// New name for --from
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Write to this path instead of overwriting the input
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Validates every .pcf in a directory; exits non-zero if any has issues
+    Audit {
+        /// Files, directories or glob patterns (e.g. "patterns/**/*.pcf")
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        /// Emit the report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Descend into subdirectories of directory arguments
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Walk through symlinked directories
+        #[arg(long)]
+        follow_symlinks: bool,
+
+        /// Extension that directory and pattern matches must have
+        #[arg(long, default_value = "pcf")]
+        ext: String,
+
+        /// Keep matches regardless of extension
+        #[arg(long, conflicts_with = "ext")]
+        any_ext: bool,
+
+        /// Skip directory and pattern matches whose header does not look like a PCF
+        #[arg(long)]
+        sniff: bool,
+    },
+
+    /// Builds a PCF from a VCD waveform (signals `bit0`..`bit17`, one time unit per cycle)
+    FromVcd {
+        /// Path to the .vcd input
+        vcd_in: PathBuf,
+
+        /// PCF whose header (timing, loops, clocks) is reused
+        #[arg(long)]
+        header: PathBuf,
+
+        /// Path to output .pcf file
+        pcf_out: PathBuf,
+
+        /// Refuse waveforms whose last timestamp needs more than this many cycles
+        #[arg(long, default_value_t = DEFAULT_MAX_VCD_CYCLES)]
+        max_cycles: usize,
+    },
+
+    /// Builds a PCF from a text bit grid (key=value header lines, one 0/1 row per cycle)
+    FromText {
+        /// Path to the .txt input
+        text_in: PathBuf,
+
+        /// Path to output .pcf file
+        pcf_out: PathBuf,
+    },
+
+    /// Writes a PCF as a text bit grid (the format `from-text` reads)
+    ToText {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Path to the .txt output
+        text_out: PathBuf,
+    },
+
+    /// Reads the bytes at an offset as a multi-byte integer
+    Peek {
+        /// Path to the file
+        file: PathBuf,
+
+        /// File offset, decimal or 0x-prefixed hex
+        #[arg(value_parser = parse_offset)]
+        offset: usize,
+
+        /// Interpretation: u16le, u16be, u32le or u32be
+        #[arg(long = "as", default_value = "u32le", value_parser = parse_int_format)]
+        format: IntFormat,
     },
 
-    /// Block diff (18-byte rows)
-    DiffBlocks {
-        file_a: PathBuf,
-        file_b: PathBuf,
+    /// Prints a stable 64-bit hash per file, for finding duplicates
+    Hash {
+        /// Paths to .pcf files
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Hash only the pattern region, ignoring version/timing/clock metadata
+        #[arg(long)]
+        pattern_only: bool,
+
+        /// With --pattern-only, also hash the loop configuration
+        #[arg(long, requires = "pattern_only")]
+        with_loops: bool,
+    },
+
+    /// Checks that a .pcf and its JSON twin describe the same record; exits non-zero if not
+    Check {
+        /// Path to the .pcf file
+        file: PathBuf,
+
+        /// Path to the .json twin
+        json_in: PathBuf,
+    },
+
+    /// Rewrite: JSON → PCF (for round-trip experiments)
+    Write {
+        /// Path to .json input file
+        json_in: PathBuf,
+
+        /// Path to output .pcf file
+        pcf_out: PathBuf,
+
+        /// Skip the shape checks on trusted input. Risky: over-long fields are
+        /// silently truncated, and a short pattern or wrong clk_sources count
+        /// aborts the write part-way through
+        #[arg(long, visible_alias = "no-pattern-check")]
+        no_validate: bool,
+    },
+
+    /// Converts between .pcf, .json and .txt (bit grid), chosen by extension;
+    /// PCF to PCF re-writes the file in canonical form
+    Convert {
+        /// Input file; an unknown extension is accepted if the content is a PCF
+        input: PathBuf,
+
+        /// Output file
+        output: PathBuf,
+
+        /// Output format, overriding the output extension: pcf, json or text
+        #[arg(long, value_parser = ["pcf", "json", "text"])]
+        format: Option<String>,
+    },
+
+    /// Lists files whose decoded content is identical, whatever they are called
+    Dedupe {
+        /// Files, directories or glob patterns (e.g. "patterns/**/*.pcf")
+        #[arg(required = true)]
+        paths: Vec<String>,
+
+        /// Descend into subdirectories of directory arguments
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Emit the duplicate groups as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Recovers raw pattern data from a byte offset, ignoring the header, as CSV
+    Carve {
+        /// Path to the damaged file
+        file: PathBuf,
+
+        /// Offset of the first pattern column, decimal or 0x-prefixed hex
+        #[arg(long, value_parser = parse_offset)]
+        offset: usize,
+
+        /// Cycles to read; defaults to every complete cycle after the offset
+        #[arg(long)]
+        cycles: Option<usize>,
+
+        /// Output CSV: one line per cycle, one value per bit
+        csv_out: PathBuf,
+    },
+
+    /// Prints a shell completion script to stdout
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate for: bash, zsh, fish, elvish or powershell
+        shell: Shell,
+    },
+}
+
+/// Accepts a string, parses to usize, and enforces 1..=64
+fn parse_byte_range(s: &str) -> Result<usize, String> {
+    let val: usize = s
+        .parse()
+        .map_err(|_| format!("`{}` isn’t a number", s))?;
+    if (1..=64).contains(&val) {
+        Ok(val)
+    } else {
+        Err(format!("must be in range 1..=64 (got {})", val))
+    }
+}
+
+fn parse_column_bits(s: &str) -> Result<[u8; 18], String> {
+    if s.len() != 18 {
+        return Err(format!("expected 18 bits (got {})", s.len()));
+    }
+    let mut column = [0u8; 18];
+    for (slot, c) in column.iter_mut().zip(s.chars()) {
+        *slot = match c {
+            '0' => 0,
+            '1' => 1,
+            _ => return Err(format!("`{}` is not a 0/1 string", s)),
+        };
+    }
+    Ok(column)
+}
+
+fn parse_cycle_range(s: &str) -> Result<(usize, usize), String> {
+    let (from, to) = s.split_once(':').ok_or_else(|| format!("expected FROM:TO (got `{}`)", s))?;
+    let bound = |t: &str, default: usize| {
+        if t.is_empty() {
+            Ok(default)
+        } else {
+            t.parse().map_err(|_| format!("`{}` isn’t a number", t))
+        }
+    };
+    let (from, to) = (bound(from, 0)?, bound(to, usize::MAX)?);
+    if from > to {
+        return Err(format!("range start {} is after end {}", from, to));
+    }
+    Ok((from, to))
+}
+
+fn parse_offset(s: &str) -> Result<usize, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("`{}` isn’t a decimal or 0x-prefixed offset", s))
+}
+
+fn parse_int_format(s: &str) -> Result<IntFormat, String> {
+    IntFormat::all()
+        .iter()
+        .copied()
+        .find(|f| f.name().eq_ignore_ascii_case(s))
+        .ok_or_else(|| format!("expected u16le, u16be, u32le or u32be (got `{}`)", s))
+}
+
+fn parse_name_template(s: &str) -> Result<String, String> {
+    if !s.contains("{n}") {
+        return Err(format!("template `{}` must contain `{{n}}`", s));
+    }
+    if s.contains(['/', '\\']) {
+        return Err(format!("template `{}` must be a file name, not a path", s));
+    }
+    Ok(s.to_string())
+}
+
+/// `template` with `{n}` replaced by `n`, zero-padded to three digits.
+fn record_file_name(template: &str, n: usize) -> String {
+    template.replace("{n}", &format!("{:03}", n))
+}
+
+fn parse_offset_base(s: &str) -> Result<OffsetBase, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "hex" => Ok(OffsetBase::Hex),
+        "dec" => Ok(OffsetBase::Dec),
+        _ => Err(format!("expected `hex` or `dec` (got `{}`)", s)),
+    }
+}
+
+/// Format `convert` infers from a file extension: `pcf`, `json` or `text`.
+fn format_of(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "pcf" => Some("pcf"),
+        "json" => Some("json"),
+        "txt" => Some("text"),
+        _ => None,
+    }
+}
+
+/// The pattern region of `buf` if `pattern`, otherwise its header.
+fn region(buf: &[u8], pattern: bool) -> &[u8] {
+    let (header, rest) = buf.split_at(buf.len().min(header_size()));
+    if pattern { rest } else { header }
+}
+
+/// A column (or any run of bit values) as a `0`/`1` string.
+fn bit_string(bits: &[u8]) -> String {
+    bits.iter().map(|&v| if v != 0 { '1' } else { '0' }).collect()
+}
+
+/// Prints the inserted and deleted runs of an [`lcs_diff`], with their text.
+fn print_lcs_diff(a: &[u8], b: &[u8], base: OffsetBase) {
+    let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).escape_debug().to_string();
+    let (mut inserted, mut deleted) = (0, 0);
+    for op in lcs_diff(a, b) {
+        match op {
+            DiffOp::Equal { .. } => {}
+            DiffOp::Delete { a: at, b: to, len } => {
+                deleted += len;
+                let line = format!("- A {} (B {})  {} byte(s)  \"{}\"", base.format(at, 6), base.format(to, 6), len, text(&a[at..at + len]));
+                println!("{}", line.red());
+            }
+            DiffOp::Insert { a: to, b: at, len } => {
+                inserted += len;
+                let line = format!("+ B {} (A {})  {} byte(s)  \"{}\"", base.format(at, 6), base.format(to, 6), len, text(&b[at..at + len]));
+                println!("{}", line.green());
+            }
+        }
+    }
+    if inserted + deleted == 0 {
+        println!("{}", "Regions are identical.".green().bold());
+    } else {
+        println!("{}", format!("{} byte(s) deleted, {} inserted", deleted, inserted).bold());
+    }
+}
+
+/// Column count of the terminal on stdout, or `None` when output is redirected.
+fn terminal_width() -> Option<usize> {
+    use std::io::IsTerminal;
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size().ok().map(|(cols, _)| cols as usize)
+}
+
+/// Parses a PCF file with the global `--strict` and `--tail-bytes` settings.
+fn load_pcf(path: &Path, opts: &DecodeOptions) -> Result<PatternFileData> {
+    parse_pcf_file_with(path, opts).with_context(|| format!("Failed to parse {:?}", path))
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let strict = cli.strict;
+    let load = DecodeOptions {
+        strict,
+        keep_trailer: cli.tail_bytes,
+        max_pattern_bytes: Some(DEFAULT_MAX_PATTERN_BYTES),
+        layout: PcfLayout { numeric_width: cli.numeric_width, string_width: cli.string_width, ..Default::default() },
+        ..Default::default()
+    };
+
+    match cli.cmd {
+        Command::Parse { file, json, json_compact, no_pattern, fields, width, debug, show_cycles } => {
+            let data = load_pcf(&file, &load)?;
+
+            if data.is_pattern_blank() {
+                eprintln!("{} {}", "warning:".yellow().bold(), ValidationIssue::BlankPattern);
+            }
+
+            if let Some(fields) = fields {
+                let projection = data.project(&fields).map_err(anyhow::Error::msg)?;
+                let output = if json_compact {
+                    serde_json::to_string(&projection)?
+                } else {
+                    serde_json::to_string_pretty(&projection)?
+                };
+                println!("{output}");
+            } else if json || json_compact {
+                let output = match (no_pattern, json_compact) {
+                    (true, true) => serde_json::to_string(&HeaderOnly(&data))?,
+                    (true, false) => serde_json::to_string_pretty(&HeaderOnly(&data))?,
+                    (false, true) => serde_json::to_string(&data)?,
+                    (false, false) => serde_json::to_string_pretty(&data)?,
+                };
+                println!("{output}");
+            } else if debug {
+                println!("{:#?}", data);
+            } else {
+                print!("{}", data.summary(width.or_else(terminal_width)));
+                if let Some(n) = show_cycles {
+                    let shown = n.min(data.cols());
+                    println!("\nfirst {} of {} cycles:", shown, data.cols());
+                    for (cycle, col) in data.columns().take(shown).enumerate() {
+                        println!("{:>8}  {}", cycle, bit_string(&col));
+                    }
+                }
+            }
+        }
+
+        Command::ParseMulti { file, json } => {
+            let input = std::fs::File::open(&file)
+                .with_context(|| format!("Reading {:?}", file))?;
+            let records = parse_pcf_multi_with(input, &load)
+                .with_context(|| format!("Failed to parse {:?}", file))?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&records)?);
+            } else {
+                for (i, data) in records.iter().enumerate() {
+                    println!("record {:>3}  version {:<10} {:>8} cycles", i, data.version, data.cols());
+                }
+                println!("\n{} record(s)", records.len());
+            }
+        }
+
+        Command::Split { file, out_dir, template } => {
+            let input = std::fs::File::open(&file)
+                .with_context(|| format!("Reading {:?}", file))?;
+            let records = parse_pcf_multi_with(input, &load)
+                .with_context(|| format!("Failed to parse {:?}", file))?;
+
+            std::fs::create_dir_all(&out_dir)
+                .with_context(|| format!("Creating {:?}", out_dir))?;
+            for (n, data) in records.iter().enumerate() {
+                let path = out_dir.join(record_file_name(&template, n));
+                write_pcf_file_with(&path, data, &load.layout)
+                    .with_context(|| format!("Writing {:?}", path))?;
+            }
+
+            println!("{}", format!("Wrote {} record(s) to {:?}", records.len(), out_dir).green());
+        }
+
+        Command::Dump {
+            file, bytes, every, html, compare, offset_base, rowsum, group, lowercase, repeat_header, hex_only, ascii_only, ..
+        } => {
+            let bytes = bytes.unwrap_or(if ascii_only { 64 } else { 16 });
+            if let Some(html_out) = html {
+                let buf_a = std::fs::read(&file)
+                    .with_context(|| format!("Reading {:?}", file))?;
+                let buf_b = match &compare {
+                    Some(p) => Some(std::fs::read(p).with_context(|| format!("Reading {:?}", p))?),
+                    None => None,
+                };
+
+                let out = std::fs::File::create(&html_out)
+                    .with_context(|| format!("Creating {:?}", html_out))?;
+                let mut writer = std::io::BufWriter::new(out);
+                hex_dump_html(&mut writer, &file.display().to_string(), &buf_a, buf_b.as_deref(), bytes)?;
+
+                println!("{}", format!("Wrote HTML dump to {:?}", html_out).green());
+            } else {
+                let columns = if hex_only {
+                    DumpColumns::HexOnly
+                } else if ascii_only {
+                    DumpColumns::AsciiOnly
+                } else {
+                    DumpColumns::Both
+                };
+                let opts = DumpOptions {
+                    bytes_per_line: bytes, every, offset_base, rowsum, group, uppercase: !lowercase, repeat_header, columns,
+                };
+                hex_dump_file_with(&file, &opts)?;
+            }
+        }
+
+        Command::Diff {
+            file_a, file_b, context, context_lines, merged, side_by_side, collapse, color, bytes, offset_base,
+            stream, pattern, header, lcs, ignore_ascii_ws, ignore_ascii_case, summary, json, swap,
+        } => {
+            let (file_a, file_b) = if swap { (file_b, file_a) } else { (file_a, file_b) };
+
+            // every mode but --stream compares the files in memory
+            let buffers = if stream {
+                None
+            } else {
+                let a = std::fs::read(&file_a).with_context(|| format!("Reading {:?}", file_a))?;
+                let b = std::fs::read(&file_b).with_context(|| format!("Reading {:?}", file_b))?;
+                let norm = TextNormalize { ignore_ws: ignore_ascii_ws, ignore_case: ignore_ascii_case };
+                let b = if norm.is_active() { equate_header_fields(&a, &b, header_size(), FIELD_WIDTH, norm) } else { b };
+                Some((a, b))
+            };
+            let regions = buffers.as_ref().map(|(a, b)| {
+                if pattern || header || lcs { (region(a, pattern), region(b, pattern)) } else { (&a[..], &b[..]) }
+            });
+
+            // the byte diff has no JSON form, so `--json` prints only the summary
+            match regions {
+                _ if json => {}
+                None => diff_files_streaming_with(&file_a, &file_b, context, offset_base)?,
+                Some((a, b)) if lcs => {
+                    println!("Aligning header region: {:?} vs {:?}", file_a, file_b);
+                    print_lcs_diff(a, b, offset_base);
+                }
+                Some((a, b)) => {
+                    if pattern || header {
+                        let name = if pattern { "pattern" } else { "header" };
+                        println!("Comparing {} region: {:?} vs {:?}", name, file_a, file_b);
+                    } else {
+                        println!("Comparing: {:?} vs {:?}", file_a, file_b);
+                    }
+
+                    if let Some(lines) = context_lines {
+                        let opts = DumpOptions { bytes_per_line: bytes, offset_base, ..Default::default() };
+                        let differing = diff_buffers_lines(&mut std::io::stdout().lock(), a, b, lines, &opts)?;
+                        if differing == 0 {
+                            println!("{}", "Files are identical.".green().bold());
+                        }
+                    } else if merged {
+                        dump_merged(a, b, bytes, &mut std::io::stdout().lock())?;
+                    } else if side_by_side {
+                        let color = match color.as_str() {
+                            "always" => true,
+                            "never" => false,
+                            _ => std::io::IsTerminal::is_terminal(&std::io::stdout()) && std::env::var_os("NO_COLOR").is_none(),
+                        };
+                        let opts = SideBySideOptions { bytes_per_line: bytes, offset_base, collapse, color };
+                        if diff_side_by_side_with(a, b, &opts, &mut std::io::stdout().lock())? == 0 {
+                            println!("{}", "Files are identical.".green().bold());
+                        }
+                    } else {
+                        diff_buffers_with(a, b, context, offset_base)?;
+                    }
+                }
+            }
+
+            if let (true, Some((a, b))) = (summary, regions) {
+                let totals = diff_summary(a, b, NUM_BITS);
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "summary": totals }))?);
+                } else {
+                    println!("{}", totals.to_string().bold());
+                }
+            }
+        }
+
+        Command::DiffBlocks { file_a, file_b, block, max, rowsum, sort_by_changes, pattern_only, summary, json, swap } => {
+            let (file_a, file_b) = if swap { (file_b, file_a) } else { (file_a, file_b) };
+            if !json {
+                let opts = BlockDiffOptions { block_size: block, max_blocks: max, rowsum, sort_by_changes, pattern_only };
+                diff_blocks_with(&file_a, &file_b, &opts)?;
+            }
+
+            if json || summary {
+                let a = std::fs::read(&file_a).with_context(|| format!("Reading {:?}", file_a))?;
+                let b = std::fs::read(&file_b).with_context(|| format!("Reading {:?}", file_b))?;
+                let regions = if pattern_only { (region(&a, true), region(&b, true)) } else { (&a[..], &b[..]) };
+                let totals = summary.then(|| diff_summary(regions.0, regions.1, block));
+                if json {
+                    let mut blocks = if pattern_only {
+                        diff_pattern_blocks_report(&a, &b, block)
+                    } else {
+                        diff_blocks_report(&a, &b, block)
+                    };
+                    if sort_by_changes {
+                        blocks.sort_by_key(|d| std::cmp::Reverse(d.changed));
+                    }
+                    let report = serde_json::json!({ "blocks": blocks, "summary": totals });
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else if let Some(totals) = totals {
+                    println!("{}", totals.to_string().bold());
+                }
+            }
+        }
+
+        Command::Bit { file, bit, raw } => {
+            let data = load_pcf(&file, &load)?;
+
+            let bit = bit as usize;
+            if raw {
+                let row = data.row_major(bit).unwrap_or(&[]);
+                let line: String = row.iter().map(|&v| if v != 0 { '1' } else { '0' }).collect();
+                println!("{line}");
+            } else {
+                let runs = data.bit_runs(bit).unwrap_or_default();
+                let line = runs
+                    .iter()
+                    .map(|(v, n)| format!("{}×{}", u8::from(*v != 0), n))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                println!("{line}");
+            }
+        }
+
+        Command::Validate { file } => {
+            let data = load_pcf(&file, &load)?;
+
+            let issues = data.validate();
+            if issues.is_empty() {
+                println!("{}", "No issues found.".green().bold());
+            } else {
+                for issue in &issues {
+                    println!("{} {}", "warning:".yellow().bold(), issue);
+                }
+                anyhow::bail!("{} issue(s) found in {:?}", issues.len(), file);
+            }
+        }
+
+        Command::Count { file, equals, bit, value, range } => {
+            let data = load_pcf(&file, &load)?;
+
+            let (from, to) = range.unwrap_or((0, usize::MAX));
+            let window = data.columns().take(to).skip(from);
+            let count = match (equals, bit) {
+                (Some(want), _) => window
+                    .filter(|col| col.iter().map(|&v| u8::from(v != 0)).eq(want))
+                    .count(),
+                (None, Some(bit)) => window
+                    .filter(|col| u8::from(col[bit as usize] != 0) == value)
+                    .count(),
+                (None, None) => unreachable!("clap requires --equals or --bit"),
+            };
+            println!("{count}");
+        }
+
+        Command::Wave { file, bits, from, to } => {
+            let data = load_pcf(&file, &load)?;
+            if from >= data.cols() {
+                anyhow::bail!("--from {} is past the end of the pattern ({} cycles)", from, data.cols());
+            }
+
+            // two characters per cycle after an 8-character label
+            let to = to.unwrap_or_else(|| from + terminal_width().map_or(64, |w| (w.saturating_sub(8) / 2).max(1)));
+            let bits: Vec<usize> = bits.iter().map(|&b| b as usize).collect();
+            let wave = render_waveform(data.columns(), &bits, from..to).map_err(anyhow::Error::msg)?;
+            print!("{wave}");
+        }
+
+        Command::Histogram { file, top } => {
+            let data = load_pcf(&file, &load)?;
+
+            let mut entries: Vec<_> = data.column_frequencies().into_iter().collect();
+            entries.sort_by(|(ca, na), (cb, nb)| nb.cmp(na).then(ca.cmp(cb)));
+
+            println!("{} distinct of {} columns", entries.len(), data.cols());
+            for (col, n) in entries.iter().take(top) {
+                println!("{}  {:>8}", bit_string(col), n);
+            }
+        }
+
+        Command::Info { file } => {
+            let bytes = std::fs::read(&file).with_context(|| format!("Reading {:?}", file))?;
+            let opts = DecodeOptions { total_len: Some(bytes.len()), ..load.clone() };
+            let decoded = decode_with(&mut &bytes[..], &opts);
+            let encoding = decoded.as_ref().map(|d| d.encoding).unwrap_or_default();
+            let layout = opts.layout;
+            let column_bytes = encoding.column_bytes(NUM_BITS);
+
+            println!("file size:    {} bytes", bytes.len());
+            println!(
+                "layout:       {}-byte numeric / {}-byte text fields, {} bits per cycle, {:?} ({} bytes per cycle)",
+                layout.numeric_width, layout.string_width, NUM_BITS, encoding, column_bytes
+            );
+            let guessed = guess_header_size(&bytes)
+                .map_or_else(|| "no guess".to_string(), |n| format!("{} bytes guessed from content", n));
+            println!("header:       {} bytes expected, {}", layout.header_size(), guessed);
+
+            let stated = inspect_field(&bytes, HeaderField::PatternFileLength).value.trim().parse::<i64>().ok();
+            let pattern_bytes = bytes.len().saturating_sub(layout.header_size());
+            let (implied, spare) = (pattern_bytes / column_bytes, pattern_bytes % column_bytes);
+            let stated = stated.map_or_else(|| "unreadable".to_string(), |n| (n + PATTERN_PAD_COLS as i64).to_string());
+            let spare = if spare > 0 { format!(" (+{} stray bytes)", spare) } else { String::new() };
+            println!("cycles:       {} stated, {} implied by file size{}", stated, implied, spare);
+            if let Some(data) = decoded.as_ref().ok().filter(|d| !d.trailer.is_empty()) {
+                println!("trailer:      {} bytes after the pattern", data.trailer.len());
+            }
+
+            let reencode = |data: &PatternFileData| {
+                let mut out = Vec::new();
+                encode_with(&mut out, data, &layout).map(|_| out).ok()
+            };
+            match decoded {
+                Ok(data) if reencode(&data).as_deref() == Some(&bytes[..]) => println!("round trip:   {}", "byte-identical".green()),
+                Ok(_) => println!("round trip:   {}", "re-encoding changes the file".yellow()),
+                Err(e) => println!("round trip:   {} ({})", "does not parse".red(), e),
+            }
+        }
+
+        Command::Stats { file, json } => {
+            let summary = load_pcf(&file, &load)?.summarize();
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&summary)?);
+            } else {
+                print!("{summary}");
+            }
+        }
+
+        Command::LoopActivity { file, json } => {
+            let activity = load_pcf(&file, &load)?.loop_activity();
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&activity)?);
+            } else if activity.is_empty() {
+                println!("{}", "No loop regions in use.".yellow());
+            } else {
+                println!("loop  cycles          toggling bits (bit:transitions)");
+                for a in &activity {
+                    let bits: Vec<String> = a.toggling_bits().map(|bit| format!("{}:{}", bit, a.transitions[bit])).collect();
+                    let bits = if bits.is_empty() { "none".to_string() } else { bits.join("  ") };
+                    println!("{:>4}  {:<14}  {}", a.index, format!("{}..={}", a.start, a.end), bits);
+                }
+            }
+        }
+
+        Command::Expand { file, pcf_out, max_cycles } => {
+            let mut data = load_pcf(&file, &load)?;
+
+            let cycles = data.executed_cycles();
+            if cycles > max_cycles {
+                anyhow::bail!(
+                    "expansion would produce {} cycles (limit {}); raise --max-cycles to proceed",
+                    cycles, max_cycles
+                );
+            }
+
+            let expanded = data.expand_loops();
+            data.set_columns(&expanded);
+            data.start_addrs = [0; 8];
+            data.end_addrs = [0; 8];
+            data.loop_counts = [1; 8];
+            data.end_addrs[0] = expanded.len() as i32 - 1;
+
+            write_pcf_file_with(&pcf_out, &data, &load.layout)
+                .with_context(|| format!("Writing {:?}", pcf_out))?;
+
+            println!("{}", format!("Expanded to {} cycles", expanded.len()).green());
+        }
+
+        Command::Timeline { file, summary, max_cycles, json } => {
+            let data = load_pcf(&file, &load)?;
+            let cycles = data.executed_cycles();
+            let total = data.execution_time();
+            let time = |ns: Option<f64>| ns.map_or_else(|| "unknown".to_string(), format_nanos);
+
+            if summary && json {
+                let report = serde_json::json!({ "executed_cycles": cycles, "total_ns": total });
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else if summary {
+                println!("executed cycles: {}", cycles);
+                println!("total time:      {}", time(total));
+            } else {
+                let steps = data
+                    .timeline(max_cycles)
+                    .map_err(|e| anyhow::anyhow!("{}; use --summary or raise --max-cycles", e))?;
+                if json {
+                    let report = serde_json::json!({ "executed_cycles": cycles, "total_ns": total, "steps": steps });
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    println!("{:>10}  {:>8}  {:>4}  {:>12}  {:>12}", "cycle", "column", "loop", "period", "start");
+                    for (cycle, step) in steps.iter().enumerate() {
+                        let slot = step.loop_index.map_or_else(|| "-".to_string(), |i| i.to_string());
+                        println!(
+                            "{:>10}  {:>8}  {:>4}  {:>12}  {:>12}",
+                            cycle, step.column, slot, time(step.period_ns), time(step.start_ns)
+                        );
+                    }
+                    println!("total time: {}", time(total));
+                }
+            }
+            if total.is_none() {
+                eprintln!("{}", "Some cycle_time entries are blank, unparseable or unitless; those cycles' timing is unknown.".yellow());
+            }
+        }
+
+        Command::ExtractLoop { file, index, pcf_out } => {
+            let data = load_pcf(&file, &load)?;
+            let body = data.extract_loop(index as usize).map_err(anyhow::Error::msg)?;
+            let cycles = body.end_addrs[index as usize] + 1;
+
+            write_pcf_file_with(&pcf_out, &body, &load.layout)
+                .with_context(|| format!("Writing {:?}", pcf_out))?;
+
+            println!("{}", format!("Wrote loop {} ({} cycles, x{}) to {:?}", index, cycles, body.loop_counts[index as usize], pcf_out).green());
+        }
+
+        Command::DiffLoops { file_a, file_b, json } => {
+            let a = load_pcf(&file_a, &load)?;
+            let b = load_pcf(&file_b, &load)?;
+            let diffs = loop_config_diff(&a, &b);
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diffs)?);
+            } else if diffs.is_empty() {
+                println!("{}", "Loop configurations are identical.".green().bold());
+            } else {
+                for d in &diffs {
+                    println!("loop {}  {:<12} {} -> {}", d.index, d.field, d.old, d.new.to_string().yellow().bold());
+                }
+            }
+        }
+
+        Command::DiffPattern { file_a, file_b, json, bits: bit_level } => {
+            let a = load_pcf(&file_a, &load)?;
+            let b = load_pcf(&file_b, &load)?;
+            let bits = active_bits_diff(&a, &b);
+            let bit_errors = bit_level.then(|| bit_diff(&a, &b)).transpose().map_err(anyhow::Error::msg)?;
+
+            if json {
+                let output = match &bit_errors {
+                    Some(errors) => serde_json::to_string_pretty(&serde_json::json!({ "active_bits": bits, "bit_diff": errors }))?,
+                    None => serde_json::to_string_pretty(&bits)?,
+                };
+                println!("{output}");
+            } else {
+                let changed = a.columns().zip(b.columns()).filter(|(x, y)| x != y).count();
+                println!("cycles: {} vs {}, {} of the shared cycles differ", a.cols(), b.cols(), changed);
+
+                if bits.is_empty() {
+                    println!("{}", "Active bits are identical.".green().bold());
+                }
+                for bit in &bits.gained {
+                    println!("bit {:>2}  {}", bit, "newly active".yellow().bold());
+                }
+                for bit in &bits.lost {
+                    println!("bit {:>2}  {}", bit, "now dormant".yellow().bold());
+                }
+
+                if let Some(errors) = bit_errors {
+                    let total = a.cols() * NUM_BITS;
+                    println!("\n{} of {} bits differ", errors.total.to_string().bold(), total);
+                    for (bit, &n) in errors.per_bit.iter().enumerate().filter(|(_, n)| **n > 0) {
+                        println!("bit {:>2}  {:>8}", bit, n);
+                    }
+                }
+            }
+        }
+
+        Command::FirstDiffCycle { file_a, file_b } => {
+            let a = load_pcf(&file_a, &load)?;
+            let b = load_pcf(&file_b, &load)?;
+
+            match first_diff_cycle(&a, &b) {
+                None => println!("{}", "identical patterns".green().bold()),
+                Some(cycle) => {
+                    let (col_a, col_b) = (a.column(cycle), b.column(cycle));
+                    let text = |col: Option<[u8; NUM_BITS]>| col.map_or_else(|| "(past the end)".to_string(), |c| bit_string(&c));
+
+                    println!("first differing cycle: {}", cycle.to_string().yellow().bold());
+                    println!("a  {}", text(col_a));
+                    println!("b  {}", text(col_b));
+                    if let (Some(x), Some(y)) = (col_a, col_b) {
+                        let marks: String = x.iter().zip(&y).map(|(p, q)| if (*p != 0) != (*q != 0) { '^' } else { ' ' }).collect();
+                        println!("   {}", marks.trim_end());
+                    }
+                }
+            }
+        }
+
+        Command::Gen { pcf_out, cycles, seed, density } => {
+            let data = generate(&GenOptions { cycles, seed, density }).map_err(anyhow::Error::msg)?;
+
+            write_pcf_file_with(&pcf_out, &data, &load.layout)
+                .with_context(|| format!("Writing {:?}", pcf_out))?;
+
+            println!("{}", format!("Generated {} cycles (seed {}) to {:?}", data.cols(), seed, pcf_out).green());
+        }
+
+        Command::Patch { file, patch_json, pcf_out } => {
+            let mut data = load_pcf(&file, &load)?;
+            let text = std::fs::read_to_string(&patch_json)
+                .with_context(|| format!("Reading {:?}", patch_json))?;
+            let patch: serde_json::Value = serde_json::from_str(&text)
+                .with_context(|| format!("Parsing {:?}", patch_json))?;
+
+            data.apply_patch(&patch)
+                .map_err(anyhow::Error::msg)
+                .with_context(|| format!("Applying {:?}", patch_json))?;
+
+            write_pcf_file_with(&pcf_out, &data, &load.layout)
+                .with_context(|| format!("Writing {:?}", pcf_out))?;
+
+            println!("{}", format!("Patched {:?} into {:?}", file, pcf_out).green());
+        }
+
+        Command::Edit { file, clear_bit, set_bit, out } => {
+            if let Some(bit) = clear_bit.iter().find(|b| set_bit.contains(b)) {
+                anyhow::bail!("Bit {} is both cleared and set", bit);
+            }
+            let mut data = load_pcf(&file, &load)?;
+
+            for &bit in &clear_bit {
+                data.clear_bit(bit as usize).map_err(anyhow::Error::msg)?;
+            }
+            for &bit in &set_bit {
+                data.set_bit_all(bit as usize).map_err(anyhow::Error::msg)?;
+            }
+
+            let dest = out.unwrap_or(file);
+            write_pcf_file_with(&dest, &data, &load.layout)
+                .with_context(|| format!("Writing {:?}", dest))?;
+
+            println!("{}", format!("Cleared bits {:?}, set bits {:?} in {:?}", clear_bit, set_bit, dest).green());
+        }
+
+        Command::ReplaceClk { file, slot, name, from, to, out } => {
+            let mut data = load_pcf(&file, &load)?;
+
+            let (slot, new_name) = match (slot, name, from, to) {
+                (Some(slot), Some(name), _, _) => (slot, name),
+                (_, _, Some(from), Some(to)) => {
+                    let slot = data
+                        .clk_slot(&from)
+                        .with_context(|| format!("No clock source named `{}`", from))?;
+                    (slot, to)
+                }
+                _ => anyhow::bail!("Specify --slot/--name or --from/--to"),
+            };
+
+            let old = data.rename_clk_source(slot, &new_name).map_err(anyhow::Error::msg)?;
+
+            let dest = out.unwrap_or(file);
+            write_pcf_file_with(&dest, &data, &load.layout)
+                .with_context(|| format!("Writing {:?}", dest))?;
+
+            println!("{}", format!("Renamed slot {}: `{}` -> `{}`", slot, old, new_name).green());
+        }
+
+        Command::Audit { paths, json, recursive, follow_symlinks, ext, any_ext, sniff } => {
+            let opts = ExpandOptions { recursive, follow_symlinks, extension: (!any_ext).then_some(ext), sniff };
+            let files = expand_paths(&paths, &opts)
+                .with_context(|| format!("Expanding {:?}", paths))?;
+            let report = audit_paths(&files, strict);
+            let failing = report.iter().filter(|e| e.issue_count() > 0).count();
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                let width = report.iter().map(|e| e.path.display().to_string().len()).max().unwrap_or(4).max(4);
+                println!("{:<width$}  {:>6}  FIRST ISSUE", "FILE", "ISSUES", width = width);
+                for entry in &report {
+                    let first = entry.first_issue().unwrap_or_else(|| "-".into());
+                    let count = entry.issue_count();
+                    let line = format!("{:<width$}  {:>6}  {}", entry.path.display(), count, first, width = width);
+                    if count > 0 {
+                        println!("{}", line.yellow());
+                    } else {
+                        println!("{line}");
+                    }
+                }
+                println!("\n{} file(s) checked, {} with issues", report.len(), failing);
+            }
+
+            if failing > 0 {
+                anyhow::bail!("{} of {} file(s) have issues", failing, report.len());
+            }
+        }
+
+        Command::FromVcd { vcd_in, header, pcf_out, max_cycles } => {
+            let template = load_pcf(&header, &load)?;
+            let input = std::fs::File::open(&vcd_in)
+                .with_context(|| format!("Reading {:?}", vcd_in))?;
+            let data = read_vcd_limited(std::io::BufReader::new(input), template, max_cycles)
+                .with_context(|| format!("Failed to read VCD {:?}", vcd_in))?;
+
+            write_pcf_file_with(&pcf_out, &data, &load.layout)
+                .with_context(|| format!("Writing {:?}", pcf_out))?;
+
+            println!("{}", format!("Wrote {} cycles to {:?}", data.cols(), pcf_out).green());
+        }
+
+        Command::FromText { text_in, pcf_out } => {
+            let input = std::fs::File::open(&text_in)
+                .with_context(|| format!("Reading {:?}", text_in))?;
+            let data = read_bitgrid(input)
+                .with_context(|| format!("Failed to read bit grid {:?}", text_in))?;
+
+            write_pcf_file_with(&pcf_out, &data, &load.layout)
+                .with_context(|| format!("Writing {:?}", pcf_out))?;
+
+            println!("{}", format!("Wrote {} cycles to {:?}", data.cols(), pcf_out).green());
+        }
+
+        Command::ToText { file, text_out } => {
+            let data = load_pcf(&file, &load)?;
+
+            let out = std::fs::File::create(&text_out)
+                .with_context(|| format!("Creating {:?}", text_out))?;
+            let mut writer = std::io::BufWriter::new(out);
+            write_bitgrid(&data, &mut writer)
+                .and_then(|()| std::io::Write::flush(&mut writer))
+                .with_context(|| format!("Writing {:?}", text_out))?;
+
+            println!("{}", format!("Wrote {} cycles to {:?}", data.cols(), text_out).green());
+        }
+
+        Command::Peek { file, offset, format } => {
+            let bytes = std::fs::read(&file).with_context(|| format!("Reading {:?}", file))?;
+            let value = format.read(&bytes, offset).with_context(|| {
+                format!("{} at 0x{:X} runs past the end of the {}-byte file", format.name(), offset, bytes.len())
+            })?;
+            println!("0x{:06X}  {}  0x{:0width$X}  {}", offset, format.name(), value, value, width = 2 * format.width());
+        }
+
+        Command::Hash { files, pattern_only, with_loops } => {
+            for file in &files {
+                let hash = if pattern_only {
+                    load_pcf(file, &load)?.content_hash_with(with_loops)
+                } else {
+                    let bytes = std::fs::read(file).with_context(|| format!("Reading {:?}", file))?;
+                    fnv1a64(&bytes)
+                };
+                println!("{:016x}  {}", hash, file.display());
+            }
+        }
+
+        Command::Check { file, json_in } => {
+            let pcf = load_pcf(&file, &load)?.normalize();
+            let text = std::fs::read_to_string(&json_in)
+                .with_context(|| format!("Reading {:?}", json_in))?;
+            let twin: PatternFileData = serde_json::from_str(&text)
+                .with_context(|| format!("Failed to deserialize {:?}", json_in))?;
+            let twin = twin.normalize();
+
+            let fields = pcf.diff_fields(&twin);
+            if fields.is_empty() {
+                println!("{}", format!("{:?} matches {:?}", file, json_in).green().bold());
+            } else {
+                let (pcf_value, twin_value) = (serde_json::to_value(&pcf)?, serde_json::to_value(&twin)?);
+                for &name in &fields {
+                    let detail = match name {
+                        "pattern_data" => match pcf.first_pattern_diff(&twin) {
+                            Some((bit, cycle)) => format!("first differs at bit {}, cycle {}", bit, cycle),
+                            None => "differs".to_string(),
+                        },
+                        "encoding" => format!("pcf {:?}  json {:?}", pcf.encoding, twin.encoding),
+                        _ => format!("pcf {}  json {}", pcf_value[name], twin_value[name]),
+                    };
+                    println!("{:<20} {}", name.yellow().bold(), detail);
+                }
+                anyhow::bail!("{} field(s) differ between {:?} and {:?}", fields.len(), file, json_in);
+            }
+        }
+
+        Command::Write { json_in, pcf_out, no_validate } => {
+            let text = std::fs::read_to_string(&json_in)
+                .with_context(|| format!("Reading {:?}", json_in))?;
+
+            let data: PatternFileData = serde_json::from_str(&text)
+                .with_context(|| "Failed to deserialize JSON")?;
+
+            if !no_validate {
+                data.check_shape_with(&load.layout)
+                    .map_err(anyhow::Error::msg)
+                    .with_context(|| format!("{:?} cannot be written as is", json_in))?;
+            }
+
+            write_pcf_file_with(&pcf_out, &data, &load.layout)
+                .with_context(|| format!("Writing {:?}", pcf_out))?;
+
+            println!("{}", "Wrote PCF file".green());
+        }
+
+        Command::Convert { input, output, format } => {
+            let original = std::fs::read(&input).with_context(|| format!("Reading {:?}", input))?;
+            let from = format_of(&input)
+                .or_else(|| looks_like_pcf(&original).then_some("pcf"))
+                .with_context(|| format!("Cannot tell the format of {:?}; use a .pcf, .json or .txt extension", input))?;
+            let to = format.as_deref().or_else(|| format_of(&output))
+                .with_context(|| format!("Cannot tell the format for {:?}; pass --format", output))?;
+
+            let data = match from {
+                "pcf" => load_pcf(&input, &load)?,
+                "json" => serde_json::from_slice(&original)
+                    .with_context(|| format!("Failed to deserialize {:?}", input))?,
+                _ => read_bitgrid(&original[..])
+                    .with_context(|| format!("Failed to read bit grid {:?}", input))?,
+            };
+
+            match to {
+                "pcf" => {
+                    let data = if from == "pcf" { data.normalize() } else { data };
+                    data.check_shape_with(&load.layout)
+                        .map_err(anyhow::Error::msg)
+                        .with_context(|| format!("{:?} cannot be written as a PCF", input))?;
+                    write_pcf_file_with(&output, &data, &load.layout)
+                        .with_context(|| format!("Writing {:?}", output))?;
+
+                    if from == "pcf" {
+                        let mut rewritten = Vec::new();
+                        encode_with(&mut rewritten, &data, &load.layout)?;
+                        if rewritten == original {
+                            println!("Output is byte-identical to the input.");
+                        } else {
+                            let changed = rewritten.iter().zip(&original).filter(|(a, b)| a != b).count()
+                                + rewritten.len().abs_diff(original.len());
+                            println!("{}", format!("Normalization changed {} byte(s).", changed).yellow());
+                        }
+                    }
+                }
+                "json" => {
+                    let text = serde_json::to_string_pretty(&data)?;
+                    std::fs::write(&output, text).with_context(|| format!("Writing {:?}", output))?;
+                }
+                _ => {
+                    let out = std::fs::File::create(&output)
+                        .with_context(|| format!("Creating {:?}", output))?;
+                    let mut writer = std::io::BufWriter::new(out);
+                    write_bitgrid(&data, &mut writer)
+                        .and_then(|()| std::io::Write::flush(&mut writer))
+                        .with_context(|| format!("Writing {:?}", output))?;
+                }
+            }
+
+            println!("{}", format!("Converted {:?} ({}) to {:?} ({})", input, from, output, to).green());
+        }
+
+        Command::Dedupe { paths, recursive, json } => {
+            let opts = ExpandOptions { recursive, ..Default::default() };
+            let files = expand_paths(&paths, &opts)
+                .with_context(|| format!("Expanding {:?}", paths))?;
+            let grouped = group_by_content(&files, strict);
+            let duplicates = grouped.duplicates();
+            for (path, error) in &grouped.failed {
+                eprintln!("{}", format!("Skipped {:?}: {}", path, error).yellow());
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&duplicates)?);
+            } else if duplicates.is_empty() {
+                println!("{}", format!("No duplicates among {} file(s).", files.len() - grouped.failed.len()).green());
+            } else {
+                for group in &duplicates {
+                    println!("{} identical:", group.len());
+                    for path in group.iter() {
+                        println!("  {}", path.display());
+                    }
+                }
+                let redundant: usize = duplicates.iter().map(|g| g.len() - 1).sum();
+                println!("\n{} group(s) of duplicates; {} file(s) could be removed", duplicates.len(), redundant);
+            }
+        }
+
+        Command::Carve { file, offset, cycles, csv_out } => {
+            let bytes = std::fs::read(&file).with_context(|| format!("Reading {:?}", file))?;
+            let rows = parse_pattern_region(&bytes, offset, cycles.unwrap_or(usize::MAX))
+                .with_context(|| format!("Carving {:?}", file))?;
+            let read = rows[0].len();
+
+            let mut csv = String::from("cycle");
+            for bit in 0..NUM_BITS {
+                csv.push_str(&format!(",bit{}", bit));
+            }
+            csv.push('\n');
+            for col in 0..read {
+                csv.push_str(&col.to_string());
+                for row in &rows {
+                    csv.push_str(&format!(",{}", row[col]));
+                }
+                csv.push('\n');
+            }
+            std::fs::write(&csv_out, csv).with_context(|| format!("Writing {:?}", csv_out))?;
+
+            match cycles {
+                Some(wanted) if read < wanted => println!(
+                    "{}",
+                    format!("Only {} of {} cycles fit after offset {:#x}; wrote {:?}", read, wanted, offset, csv_out).yellow()
+                ),
+                _ => println!("{}", format!("Read {} complete cycles from offset {:#x} into {:?}", read, offset, csv_out).green()),
+            }
+            let partial = bytes.len().saturating_sub(offset + read * NUM_BITS).min(NUM_BITS);
+            if cycles.is_none() && partial > 0 {
+                println!("{}", format!("Ignored {} trailing byte(s) of an incomplete cycle.", partial).yellow());
+            }
+        }
+
+        Command::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_cli_parse_command() {
+        let args = ["pcf", "parse", "file.pcf"];
+        let cli = Cli::parse_from(args);
+        match cli.cmd {
+            Command::Parse { file, json, json_compact, no_pattern, fields, width, debug, show_cycles } => {
+                assert_eq!(file, PathBuf::from("file.pcf"));
+                assert!(!json);
+                assert!(!json_compact);
+                assert!(!no_pattern);
+                assert!(fields.is_none());
+                assert!(width.is_none());
+                assert!(!debug);
+                assert!(show_cycles.is_none());
+            },
+            _ => panic!("Expected Parse command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_command_with_json() {
+        let args = ["pcf", "parse", "file.pcf", "--json"];
+        let cli = Cli::parse_from(args);
+        match cli.cmd {
+            Command::Parse { file, json, .. } => {
+                assert_eq!(file, PathBuf::from("file.pcf"));
+                assert!(json);
+            },
+            _ => panic!("Expected Parse command with --json"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_command_with_fields() {
+        let args = ["pcf", "parse", "file.pcf", "--json", "--fields", "version,loop_counts"];
+        match Cli::parse_from(args).cmd {
+            Command::Parse { fields, .. } => {
+                assert_eq!(fields, Some(vec!["version".to_string(), "loop_counts".to_string()]));
+            },
+            _ => panic!("Expected Parse command with --fields"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "parse", "f.pcf", "--fields", "version", "--no-pattern"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_command_with_json_compact() {
+        let args = ["pcf", "parse", "file.pcf", "--json-compact", "--no-pattern"];
+        match Cli::parse_from(args).cmd {
+            Command::Parse { json, json_compact, no_pattern, .. } => {
+                assert!(!json);
+                assert!(json_compact);
+                assert!(no_pattern);
+            },
+            _ => panic!("Expected Parse command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "parse", "f.pcf", "--json", "--json-compact"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_command_width() {
+        match Cli::parse_from(["pcf", "parse", "f.pcf", "--max-width", "60"]).cmd {
+            Command::Parse { width, .. } => assert_eq!(width, Some(60)),
+            _ => panic!("Expected Parse command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "parse", "f.pcf", "--debug", "--json"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_command_show_cycles() {
+        match Cli::parse_from(["pcf", "parse", "f.pcf", "--show-cycles", "5"]).cmd {
+            Command::Parse { show_cycles, .. } => assert_eq!(show_cycles, Some(5)),
+            _ => panic!("Expected Parse command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "parse", "f.pcf", "--show-cycles", "5", "--json"]).is_err());
+        assert_eq!(bit_string(&[0, 1, 0xFF]), "011");
+    }
+
+    #[test]
+    fn test_cli_parse_multi_command() {
+        match Cli::parse_from(["pcf", "parse-multi", "bundle.pcf", "--json"]).cmd {
+            Command::ParseMulti { file, json } => {
+                assert_eq!(file, PathBuf::from("bundle.pcf"));
+                assert!(json);
+            },
+            _ => panic!("Expected ParseMulti command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_dump_command() {
+        let args = ["pcf", "dump", "file.pcf", "--bytes", "32"];
+        let cli = Cli::parse_from(args);
+        match cli.cmd {
+            Command::Dump {
+                file, bytes, every, html, compare, offset_base, rowsum, group, lowercase, uppercase, repeat_header, hex_only, ascii_only,
+            } => {
+                assert_eq!(file, PathBuf::from("file.pcf"));
+                assert_eq!(bytes, Some(32));
+                assert_eq!(every, 1);
+                assert!(html.is_none());
+                assert!(compare.is_none());
+                assert_eq!(offset_base, OffsetBase::Hex);
+                assert!(!rowsum);
+                assert_eq!(group, 0);
+                assert!(!lowercase && !uppercase);
+                assert_eq!(repeat_header, 0);
+                assert!(!hex_only && !ascii_only);
+            },
+            _ => panic!("Expected Dump command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_dump_command_html() {
+        let args = ["pcf", "dump", "a.pcf", "--html", "out.html", "--compare", "b.pcf"];
+        let cli = Cli::parse_from(args);
+        match cli.cmd {
+            Command::Dump { html, compare, .. } => {
+                assert_eq!(html, Some(PathBuf::from("out.html")));
+                assert_eq!(compare, Some(PathBuf::from("b.pcf")));
+            },
+            _ => panic!("Expected Dump command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_dump_command_group_and_case() {
+        let args = ["pcf", "dump", "a.pcf", "--group", "4", "--lowercase", "--repeat-header", "40"];
+        match Cli::parse_from(args).cmd {
+            Command::Dump { group, lowercase, repeat_header, .. } => {
+                assert_eq!(group, 4);
+                assert!(lowercase);
+                assert_eq!(repeat_header, 40);
+            },
+            _ => panic!("Expected Dump command"),
+        }
+
+        let args = ["pcf", "dump", "a.pcf", "--lowercase", "--uppercase"];
+        match Cli::parse_from(args).cmd {
+            Command::Dump { lowercase, .. } => assert!(!lowercase),
+            _ => panic!("Expected Dump command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_dump_single_column() {
+        match Cli::parse_from(["pcf", "dump", "a.pcf", "--ascii-only"]).cmd {
+            Command::Dump { bytes, hex_only, ascii_only, .. } => {
+                assert_eq!(bytes, None);
+                assert!(ascii_only && !hex_only);
+            },
+            _ => panic!("Expected Dump command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "dump", "a.pcf", "--hex-only", "--ascii-only"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "dump", "a.pcf", "--hex-only", "--html", "o.html"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_diff_command() {
+        let args = ["pcf", "diff", "a.pcf", "b.pcf", "--context", "4", "--offset-base", "dec"];
+        let cli = Cli::parse_from(args);
+        match cli.cmd {
+            Command::Diff {
+                file_a, file_b, context, context_lines, merged, side_by_side, collapse, color, bytes, offset_base,
+                stream, pattern, header, lcs, ignore_ascii_ws, ignore_ascii_case, summary, json, swap,
+            } => {
+                assert_eq!(file_a, PathBuf::from("a.pcf"));
+                assert_eq!(file_b, PathBuf::from("b.pcf"));
+                assert_eq!(context, 4);
+                assert!(context_lines.is_none() && !merged && !side_by_side && !collapse);
+                assert_eq!(color, "auto");
+                assert_eq!(bytes, 16);
+                assert_eq!(offset_base, OffsetBase::Dec);
+                assert!(!stream);
+                assert!(!pattern);
+                assert!(!header && !lcs);
+                assert!(!ignore_ascii_ws && !ignore_ascii_case);
+                assert!(!summary && !json);
+                assert!(!swap);
+            },
+            _ => panic!("Expected Diff command"),
+        }
+
+        let args = ["pcf", "diff", "a.pcf", "b.pcf", "--pattern"];
+        match Cli::parse_from(args).cmd {
+            Command::Diff { pattern, header, .. } => assert!(pattern && !header),
+            _ => panic!("Expected Diff command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--pattern", "--header"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--header", "--stream"]).is_err());
+
+        match Cli::parse_from(["pcf", "diff", "a.pcf", "b.pcf", "--summary", "--json"]).cmd {
+            Command::Diff { summary, json, .. } => assert!(summary && json),
+            _ => panic!("Expected Diff command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--json"]).is_err());
+
+        match Cli::parse_from(["pcf", "diff", "a.pcf", "b.pcf", "--header", "--ignore-ascii-ws", "--ignore-ascii-case"]).cmd {
+            Command::Diff { ignore_ascii_ws, ignore_ascii_case, .. } => assert!(ignore_ascii_ws && ignore_ascii_case),
+            _ => panic!("Expected Diff command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--pattern", "--ignore-ascii-case"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--summary", "--stream"]).is_err());
 
-        /// Bytes per block (default 18 for PCF pattern row)
-        #[arg(long, default_value_t = 18)]
-        block: usize,
+        match Cli::parse_from(["pcf", "diff", "a.pcf", "b.pcf", "--lcs"]).cmd {
+            Command::Diff { lcs, .. } => assert!(lcs),
+            _ => panic!("Expected Diff command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--lcs", "--pattern"]).is_err());
 
-        /// Max mismatched blocks to show
-        #[arg(long, default_value_t = 10)]
-        max: usize,
-    },
+        match Cli::parse_from(["pcf", "diff", "a.pcf", "b.pcf", "--swap"]).cmd {
+            Command::Diff { file_a, swap, .. } => assert!(swap && file_a == Path::new("a.pcf")),
+            _ => panic!("Expected Diff command"),
+        }
 
-    /// Rewrite: JSON → PCF (for round-trip experiments)
-    Write {
-        /// Path to .json input file
-        json_in: PathBuf,
+        let args = ["pcf", "diff", "a.pcf", "b.pcf", "--context-lines", "2", "--bytes", "18"];
+        match Cli::parse_from(args).cmd {
+            Command::Diff { context_lines, bytes, .. } => assert_eq!((context_lines, bytes), (Some(2), 18)),
+            _ => panic!("Expected Diff command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--bytes", "18"]).is_err());
+        match Cli::parse_from(["pcf", "diff", "a", "b", "--merged", "--bytes", "18"]).cmd {
+            Command::Diff { merged, bytes, .. } => assert_eq!((merged, bytes), (true, 18)),
+            _ => panic!("Expected Diff command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--merged", "--context-lines", "1"]).is_err());
 
-        /// Path to output .pcf file
-        pcf_out: PathBuf,
-    },
-}
+        let args = ["pcf", "diff", "a", "b", "--side-by-side", "--collapse", "--color", "never"];
+        match Cli::parse_from(args).cmd {
+            Command::Diff { side_by_side, collapse, color, .. } => {
+                assert!(side_by_side && collapse);
+                assert_eq!(color, "never");
+            },
+            _ => panic!("Expected Diff command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--collapse"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--color", "always"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--side-by-side", "--merged"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "diff", "a", "b", "--side-by-side", "--color", "red"]).is_err());
+    }
 
-/// Accepts a string, parses to usize, and enforces 1..=64
-fn parse_byte_range(s: &str) -> Result<usize, String> {
-    let val: usize = s
-        .parse()
-        .map_err(|_| format!("`{}` isn’t a number", s))?;
-    if (1..=64).contains(&val) {
-        Ok(val)
-    } else {
-        Err(format!("must be in range 1..=64 (got {})", val))
+    #[test]
+    fn test_region_splits_at_header() {
+        let buf = vec![1u8; header_size() + 5];
+        assert_eq!(region(&buf, true).len(), 5);
+        assert_eq!(region(&buf, false).len(), header_size());
+        assert!(region(&buf[..10], true).is_empty());
     }
-}
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+    #[test]
+    fn test_cli_diffblocks_command() {
+        let args = ["pcf", "diff-blocks", "a.pcf", "b.pcf", "--block", "20", "--max", "2", "--rowsum"];
+        let cli = Cli::parse_from(args);
+        match cli.cmd {
+            Command::DiffBlocks { file_a, file_b, block, max, rowsum, sort_by_changes, pattern_only, summary, json, swap } => {
+                assert_eq!(file_a, PathBuf::from("a.pcf"));
+                assert_eq!(file_b, PathBuf::from("b.pcf"));
+                assert_eq!(block, 20);
+                assert_eq!(max, 2);
+                assert!(rowsum);
+                assert!(!sort_by_changes && !pattern_only);
+                assert!(!summary && !json);
+                assert!(!swap);
+            },
+            _ => panic!("Expected DiffBlocks command"),
+        }
 
-    match cli.cmd {
-        Command::Parse { file, json } => {
-            let data = parse_pcf_file(&file)
-                .with_context(|| format!("Failed to parse {:?}", file))?;
+        match Cli::parse_from(["pcf", "diff-blocks", "a.pcf", "b.pcf", "--sort-by-changes"]).cmd {
+            Command::DiffBlocks { sort_by_changes, .. } => assert!(sort_by_changes),
+            _ => panic!("Expected DiffBlocks command"),
+        }
 
-            if json {
-                let output = serde_json::to_string_pretty(&data)?;
-                println!("{output}");
-            } else {
-                println!("{:#?}", data);
+        for flag in ["--diff-only-pattern", "--pattern"] {
+            match Cli::parse_from(["pcf", "diff-blocks", "a.pcf", "b.pcf", flag]).cmd {
+                Command::DiffBlocks { pattern_only, .. } => assert!(pattern_only),
+                _ => panic!("Expected DiffBlocks command"),
             }
         }
+        match Cli::parse_from(["pcf", "diff-blocks", "a.pcf", "b.pcf", "--swap"]).cmd {
+            Command::DiffBlocks { swap, .. } => assert!(swap),
+            _ => panic!("Expected DiffBlocks command"),
+        }
+    }
 
-        Command::Dump { file, bytes } => {
-            hex_dump_file(&file, bytes)?;
+    #[test]
+    fn test_cli_bit_command() {
+        let args = ["pcf", "bit", "file.pcf", "--bit", "5", "--raw"];
+        let cli = Cli::parse_from(args);
+        match cli.cmd {
+            Command::Bit { file, bit, raw } => {
+                assert_eq!(file, PathBuf::from("file.pcf"));
+                assert_eq!(bit, 5);
+                assert!(raw);
+            },
+            _ => panic!("Expected Bit command"),
         }
+    }
 
-        Command::Diff { file_a, file_b, context } => {
-            diff_files(&file_a, &file_b, context)?;
+    #[test]
+    fn test_cli_bit_command_rejects_out_of_range() {
+        let args = ["pcf", "bit", "file.pcf", "--bit", "18"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_cli_count_command() {
+        let args = ["pcf", "count", "file.pcf", "--bit", "3", "--value", "0", "--range", "10:"];
+        match Cli::parse_from(args).cmd {
+            Command::Count { file, equals, bit, value, range } => {
+                assert_eq!(file, PathBuf::from("file.pcf"));
+                assert!(equals.is_none());
+                assert_eq!(bit, Some(3));
+                assert_eq!(value, 0);
+                assert_eq!(range, Some((10, usize::MAX)));
+            },
+            _ => panic!("Expected Count command"),
         }
 
-        Command::DiffBlocks { file_a, file_b, block, max } => {
-            diff_blocks(&file_a, &file_b, block, max)?;
+        let args = ["pcf", "count", "file.pcf", "--equals", "101000000000000001"];
+        match Cli::parse_from(args).cmd {
+            Command::Count { equals, .. } => {
+                let want = equals.unwrap();
+                assert_eq!((want[0], want[1], want[2], want[17]), (1, 0, 1, 1));
+            },
+            _ => panic!("Expected Count command"),
         }
 
-        Command::Write { json_in, pcf_out } => {
-            let text = std::fs::read_to_string(&json_in)
-                .with_context(|| format!("Reading {:?}", json_in))?;
+        assert!(Cli::try_parse_from(["pcf", "count", "file.pcf"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "count", "file.pcf", "--equals", "0101"]).is_err());
+    }
 
-            let data: PatternFileData = serde_json::from_str(&text)
-                .with_context(|| "Failed to deserialize JSON")?;
+    #[test]
+    fn test_cli_histogram_command() {
+        let args = ["pcf", "histogram", "file.pcf", "--top", "3"];
+        match Cli::parse_from(args).cmd {
+            Command::Histogram { file, top } => {
+                assert_eq!(file, PathBuf::from("file.pcf"));
+                assert_eq!(top, 3);
+            },
+            _ => panic!("Expected Histogram command"),
+        }
+    }
 
-            if data.clk_sources.len() != 65 {
-                anyhow::bail!(
-                    "clk_sources must have exactly 65 elements (found {})",
-                    data.clk_sources.len()
-                );
-            }
+    #[test]
+    fn test_cli_info_command() {
+        match Cli::parse_from(["pcf", "info", "mystery.pcf"]).cmd {
+            Command::Info { file } => assert_eq!(file, PathBuf::from("mystery.pcf")),
+            _ => panic!("Expected Info command"),
+        }
+    }
 
-            write_pcf_file(&pcf_out, &data)
-                .with_context(|| format!("Writing {:?}", pcf_out))?;
+    #[test]
+    fn test_cli_field_widths_are_global() {
+        let cli = Cli::parse_from(["pcf", "write", "in.json", "out.pcf", "--string-width", "12"]);
+        assert_eq!((cli.numeric_width, cli.string_width), (FIELD_WIDTH, 12));
+        assert!(matches!(cli.cmd, Command::Write { .. }));
+    }
 
-            println!("{}", "Wrote PCF file".green());
+    #[test]
+    fn test_cli_stats_command() {
+        match Cli::parse_from(["pcf", "stats", "file.pcf", "--json"]).cmd {
+            Command::Stats { file, json } => {
+                assert_eq!(file, PathBuf::from("file.pcf"));
+                assert!(json);
+            },
+            _ => panic!("Expected Stats command"),
         }
     }
 
-    Ok(())
-}
+    #[test]
+    fn test_cli_loop_activity_command() {
+        match Cli::parse_from(["pcf", "loop-activity", "file.pcf"]).cmd {
+            Command::LoopActivity { file, json } => {
+                assert_eq!(file, PathBuf::from("file.pcf"));
+                assert!(!json);
+            },
+            _ => panic!("Expected LoopActivity command"),
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use clap::CommandFactory;
+    #[test]
+    fn test_cli_validate_command() {
+        let args = ["pcf", "validate", "file.pcf"];
+        let cli = Cli::parse_from(args);
+        match cli.cmd {
+            Command::Validate { file } => assert_eq!(file, PathBuf::from("file.pcf")),
+            _ => panic!("Expected Validate command"),
+        }
+    }
 
     #[test]
-    fn test_cli_parse_command() {
-        let args = ["pcf", "parse", "file.pcf"];
-        let cli = Cli::parse_from(&args);
+    fn test_cli_expand_command() {
+        let args = ["pcf", "expand", "in.pcf", "out.pcf", "--max-cycles", "500"];
+        let cli = Cli::parse_from(args);
         match cli.cmd {
-            Command::Parse { file, json } => {
-                assert_eq!(file, PathBuf::from("file.pcf"));
+            Command::Expand { file, pcf_out, max_cycles } => {
+                assert_eq!(file, PathBuf::from("in.pcf"));
+                assert_eq!(pcf_out, PathBuf::from("out.pcf"));
+                assert_eq!(max_cycles, 500);
+            },
+            _ => panic!("Expected Expand command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_dedupe_command() {
+        match Cli::parse_from(["pcf", "dedupe", "archive", "-r"]).cmd {
+            Command::Dedupe { paths, recursive, json } => {
+                assert_eq!(paths, vec!["archive".to_string()]);
+                assert!(recursive);
                 assert!(!json);
             },
-            _ => panic!("Expected Parse command"),
+            _ => panic!("Expected Dedupe command"),
         }
+        assert!(Cli::try_parse_from(["pcf", "dedupe"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_command_with_json() {
-        let args = ["pcf", "parse", "file.pcf", "--json"];
-        let cli = Cli::parse_from(&args);
+    fn test_cli_carve_command() {
+        let cli = Cli::parse_from(["pcf", "carve", "bad.pcf", "--offset", "0x4ec", "--cycles", "40", "out.csv"]);
         match cli.cmd {
-            Command::Parse { file, json } => {
-                assert_eq!(file, PathBuf::from("file.pcf"));
-                assert!(json);
+            Command::Carve { file, offset, cycles, csv_out } => {
+                assert_eq!(file, PathBuf::from("bad.pcf"));
+                assert_eq!(offset, 1260);
+                assert_eq!(cycles, Some(40));
+                assert_eq!(csv_out, PathBuf::from("out.csv"));
             },
-            _ => panic!("Expected Parse command with --json"),
+            _ => panic!("Expected Carve command"),
         }
+        assert!(Cli::try_parse_from(["pcf", "carve", "bad.pcf", "out.csv"]).is_err());
     }
 
     #[test]
-    fn test_cli_dump_command() {
-        let args = ["pcf", "dump", "file.pcf", "--bytes", "32"];
-        let cli = Cli::parse_from(&args);
+    fn test_cli_timeline_command() {
+        let cli = Cli::parse_from(["pcf", "timeline", "in.pcf", "--summary", "--max-cycles", "50"]);
         match cli.cmd {
-            Command::Dump { file, bytes } => {
-                assert_eq!(file, PathBuf::from("file.pcf"));
-                assert_eq!(bytes, 32);
+            Command::Timeline { file, summary, max_cycles, json } => {
+                assert_eq!(file, PathBuf::from("in.pcf"));
+                assert!(summary);
+                assert_eq!(max_cycles, 50);
+                assert!(!json);
             },
-            _ => panic!("Expected Dump command"),
+            _ => panic!("Expected Timeline command"),
         }
     }
 
     #[test]
-    fn test_cli_diff_command() {
-        let args = ["pcf", "diff", "a.pcf", "b.pcf", "--context", "4"];
-        let cli = Cli::parse_from(&args);
+    fn test_cli_extract_loop() {
+        let cli = Cli::parse_from(["pcf", "extract-loop", "in.pcf", "--loop", "3", "out.pcf"]);
+        match cli.cmd {
+            Command::ExtractLoop { file, index, pcf_out } => {
+                assert_eq!(file, PathBuf::from("in.pcf"));
+                assert_eq!(index, 3);
+                assert_eq!(pcf_out, PathBuf::from("out.pcf"));
+            },
+            _ => panic!("Expected ExtractLoop command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "extract-loop", "in.pcf", "--loop", "8", "out.pcf"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_strict_is_global() {
+        let cli = Cli::parse_from(["pcf", "validate", "file.pcf", "--strict"]);
+        assert!(cli.strict);
+        let cli = Cli::parse_from(["pcf", "--strict", "parse", "file.pcf"]);
+        assert!(cli.strict);
+        let cli = Cli::parse_from(["pcf", "parse", "file.pcf"]);
+        assert!(!cli.strict && !cli.tail_bytes);
+        let cli = Cli::parse_from(["pcf", "info", "file.pcf", "--tail-bytes"]);
+        assert!(cli.tail_bytes);
+    }
+
+    #[test]
+    fn test_cli_diff_loops_command() {
+        let args = ["pcf", "diff-loops", "a.pcf", "b.pcf", "--json"];
+        let cli = Cli::parse_from(args);
         match cli.cmd {
-            Command::Diff { file_a, file_b, context } => {
+            Command::DiffLoops { file_a, file_b, json } => {
                 assert_eq!(file_a, PathBuf::from("a.pcf"));
                 assert_eq!(file_b, PathBuf::from("b.pcf"));
-                assert_eq!(context, 4);
+                assert!(json);
             },
-            _ => panic!("Expected Diff command"),
+            _ => panic!("Expected DiffLoops command"),
         }
     }
 
     #[test]
-    fn test_cli_diffblocks_command() {
-        let args = ["pcf", "diff-blocks", "a.pcf", "b.pcf", "--block", "20", "--max", "2"];
-        let cli = Cli::parse_from(&args);
-        match cli.cmd {
-            Command::DiffBlocks { file_a, file_b, block, max } => {
+    fn test_cli_diff_pattern_command() {
+        let args = ["pcf", "diff-pattern", "a.pcf", "b.pcf"];
+        match Cli::parse_from(args).cmd {
+            Command::DiffPattern { file_a, file_b, json, bits } => {
                 assert_eq!(file_a, PathBuf::from("a.pcf"));
                 assert_eq!(file_b, PathBuf::from("b.pcf"));
-                assert_eq!(block, 20);
-                assert_eq!(max, 2);
+                assert!(!json && !bits);
             },
-            _ => panic!("Expected DiffBlocks command"),
+            _ => panic!("Expected DiffPattern command"),
+        }
+        match Cli::parse_from(["pcf", "diff-pattern", "a.pcf", "b.pcf", "--bits", "--json"]).cmd {
+            Command::DiffPattern { json, bits, .. } => assert!(json && bits),
+            _ => panic!("Expected DiffPattern command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_wave() {
+        match Cli::parse_from(["pcf", "wave", "f.pcf", "--bits", "0,1,5", "--from", "100", "--to", "140"]).cmd {
+            Command::Wave { file, bits, from, to } => {
+                assert_eq!(file, PathBuf::from("f.pcf"));
+                assert_eq!(bits, [0, 1, 5]);
+                assert_eq!((from, to), (100, Some(140)));
+            },
+            _ => panic!("Expected Wave command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "wave", "f.pcf"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "wave", "f.pcf", "--bits", "3,18"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_first_diff_cycle() {
+        match Cli::parse_from(["pcf", "first-diff-cycle", "a.pcf", "b.pcf"]).cmd {
+            Command::FirstDiffCycle { file_a, file_b } => {
+                assert_eq!(file_a, PathBuf::from("a.pcf"));
+                assert_eq!(file_b, PathBuf::from("b.pcf"));
+            },
+            _ => panic!("Expected FirstDiffCycle command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_split_command() {
+        match Cli::parse_from(["pcf", "split", "bundle.pcf", "--out-dir", "out"]).cmd {
+            Command::Split { file, out_dir, template } => {
+                assert_eq!(file, PathBuf::from("bundle.pcf"));
+                assert_eq!(out_dir, PathBuf::from("out"));
+                assert_eq!(record_file_name(&template, 0), "000.pcf");
+            },
+            _ => panic!("Expected Split command"),
+        }
+        match Cli::parse_from(["pcf", "split", "b.pcf", "--out-dir", "o", "--template", "pat_{n}.pcf"]).cmd {
+            Command::Split { template, .. } => assert_eq!(record_file_name(&template, 12), "pat_012.pcf"),
+            _ => panic!("Expected Split command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "split", "b.pcf", "--out-dir", "o", "--template", "same.pcf"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "split", "b.pcf", "--out-dir", "o", "--template", "../{n}.pcf"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "split", "b.pcf"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_gen_command() {
+        let args = ["pcf", "gen", "out.pcf", "--cycles", "100000", "--seed", "42", "--density", "0.3"];
+        match Cli::parse_from(args).cmd {
+            Command::Gen { pcf_out, cycles, seed, density } => {
+                assert_eq!(pcf_out, PathBuf::from("out.pcf"));
+                assert_eq!((cycles, seed, density), (100_000, 42, 0.3));
+            },
+            _ => panic!("Expected Gen command"),
+        }
+        match Cli::parse_from(["pcf", "gen", "out.pcf"]).cmd {
+            Command::Gen { cycles, seed, density, .. } => assert_eq!((cycles, seed, density), (1000, 0, 0.5)),
+            _ => panic!("Expected Gen command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_patch_command() {
+        match Cli::parse_from(["pcf", "patch", "a.pcf", "changes.json", "out.pcf"]).cmd {
+            Command::Patch { file, patch_json, pcf_out } => {
+                assert_eq!(file, PathBuf::from("a.pcf"));
+                assert_eq!(patch_json, PathBuf::from("changes.json"));
+                assert_eq!(pcf_out, PathBuf::from("out.pcf"));
+            },
+            _ => panic!("Expected Patch command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "patch", "a.pcf", "changes.json"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_edit_command() {
+        match Cli::parse_from(["pcf", "edit", "a.pcf", "--clear-bit", "5", "--clear-bit", "7", "--set-bit", "0"]).cmd {
+            Command::Edit { clear_bit, set_bit, out, .. } => {
+                assert_eq!(clear_bit, vec![5, 7]);
+                assert_eq!(set_bit, vec![0]);
+                assert!(out.is_none());
+            },
+            _ => panic!("Expected Edit command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "edit", "a.pcf"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "edit", "a.pcf", "--clear-bit", "18"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_replace_clk_command() {
+        let args = ["pcf", "replace-clk", "f.pcf", "--slot", "12", "--name", "NEWCLK"];
+        match Cli::parse_from(args).cmd {
+            Command::ReplaceClk { slot, name, from, .. } => {
+                assert_eq!(slot, Some(12));
+                assert_eq!(name.as_deref(), Some("NEWCLK"));
+                assert!(from.is_none());
+            },
+            _ => panic!("Expected ReplaceClk command"),
+        }
+
+        let args = ["pcf", "replace-clk", "f.pcf", "--from", "OLD", "--to", "NEW"];
+        match Cli::parse_from(args).cmd {
+            Command::ReplaceClk { from, to, .. } => {
+                assert_eq!(from.as_deref(), Some("OLD"));
+                assert_eq!(to.as_deref(), Some("NEW"));
+            },
+            _ => panic!("Expected ReplaceClk command"),
+        }
+
+        assert!(Cli::try_parse_from(["pcf", "replace-clk", "f.pcf"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "replace-clk", "f.pcf", "--slot", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_audit_command() {
+        let args = ["pcf", "audit", "patterns", "--json"];
+        match Cli::parse_from(args).cmd {
+            Command::Audit { paths, json, recursive, follow_symlinks, ext, any_ext, sniff } => {
+                assert_eq!(paths, vec!["patterns".to_string()]);
+                assert!(json);
+                assert!(!recursive);
+                assert!(!follow_symlinks);
+                assert_eq!(ext, "pcf");
+                assert!(!any_ext && !sniff);
+            },
+            _ => panic!("Expected Audit command"),
+        }
+
+        let args = ["pcf", "audit", "a/**/*.pcf", "b.pcf", "-r", "--any-ext", "--sniff"];
+        match Cli::parse_from(args).cmd {
+            Command::Audit { paths, recursive, any_ext, sniff, .. } => {
+                assert_eq!(paths.len(), 2);
+                assert!(recursive);
+                assert!(any_ext && sniff);
+            },
+            _ => panic!("Expected Audit command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_from_vcd_command() {
+        let args = ["pcf", "from-vcd", "in.vcd", "--header", "t.pcf", "out.pcf"];
+        match Cli::parse_from(args).cmd {
+            Command::FromVcd { vcd_in, header, pcf_out, max_cycles } => {
+                assert_eq!(vcd_in, PathBuf::from("in.vcd"));
+                assert_eq!(header, PathBuf::from("t.pcf"));
+                assert_eq!(pcf_out, PathBuf::from("out.pcf"));
+                assert_eq!(max_cycles, DEFAULT_MAX_VCD_CYCLES);
+            },
+            _ => panic!("Expected FromVcd command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_from_text_command() {
+        match Cli::parse_from(["pcf", "from-text", "in.txt", "out.pcf"]).cmd {
+            Command::FromText { text_in, pcf_out } => {
+                assert_eq!(text_in, PathBuf::from("in.txt"));
+                assert_eq!(pcf_out, PathBuf::from("out.pcf"));
+            },
+            _ => panic!("Expected FromText command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_to_text_command() {
+        match Cli::parse_from(["pcf", "to-text", "f.pcf", "out.txt"]).cmd {
+            Command::ToText { file, text_out } => {
+                assert_eq!(file, PathBuf::from("f.pcf"));
+                assert_eq!(text_out, PathBuf::from("out.txt"));
+            },
+            _ => panic!("Expected ToText command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_convert_command() {
+        match Cli::parse_from(["pcf", "convert", "in.pcf", "out.pcf"]).cmd {
+            Command::Convert { input, output, format } => {
+                assert_eq!(input, PathBuf::from("in.pcf"));
+                assert_eq!(output, PathBuf::from("out.pcf"));
+                assert_eq!(format, None);
+            },
+            _ => panic!("Expected Convert command"),
+        }
+        match Cli::parse_from(["pcf", "convert", "in.bin", "out", "--format", "pcf"]).cmd {
+            Command::Convert { format, .. } => assert_eq!(format.as_deref(), Some("pcf")),
+            _ => panic!("Expected Convert command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "convert", "a", "b", "--format", "csv"]).is_err());
+    }
+
+    #[test]
+    fn test_format_of() {
+        assert_eq!(format_of(Path::new("a.PCF")), Some("pcf"));
+        assert_eq!(format_of(Path::new("a.json")), Some("json"));
+        assert_eq!(format_of(Path::new("a.txt")), Some("text"));
+        assert_eq!(format_of(Path::new("a.bin")), None);
+        assert_eq!(format_of(Path::new("a")), None);
+    }
+
+    #[test]
+    fn test_cli_peek_command() {
+        match Cli::parse_from(["pcf", "peek", "a.pcf", "0x4EC", "--as", "U32BE"]).cmd {
+            Command::Peek { offset, format, .. } => {
+                assert_eq!(offset, 0x4EC);
+                assert_eq!(format, IntFormat::U32Be);
+            },
+            _ => panic!("Expected Peek command"),
+        }
+        match Cli::parse_from(["pcf", "peek", "a.pcf", "1260"]).cmd {
+            Command::Peek { offset, format, .. } => assert_eq!((offset, format), (1260, IntFormat::U32Le)),
+            _ => panic!("Expected Peek command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "peek", "a.pcf", "0", "--as", "u64be"]).is_err());
+        assert!(Cli::try_parse_from(["pcf", "peek", "a.pcf", "0xZZ"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_hash_command() {
+        match Cli::parse_from(["pcf", "hash", "a.pcf", "b.pcf", "--pattern-only"]).cmd {
+            Command::Hash { files, pattern_only, with_loops } => {
+                assert_eq!(files.len(), 2);
+                assert!(pattern_only);
+                assert!(!with_loops);
+            },
+            _ => panic!("Expected Hash command"),
         }
+        assert!(Cli::try_parse_from(["pcf", "hash", "a.pcf", "--with-loops"]).is_err());
     }
 
     #[test]
     fn test_cli_write_command() {
         let args = ["pcf", "write", "input.json", "output.pcf"];
-        let cli = Cli::parse_from(&args);
+        let cli = Cli::parse_from(args);
         match cli.cmd {
-            Command::Write { json_in, pcf_out } => {
+            Command::Write { json_in, pcf_out, no_validate } => {
                 assert_eq!(json_in, PathBuf::from("input.json"));
                 assert_eq!(pcf_out, PathBuf::from("output.pcf"));
+                assert!(!no_validate);
             },
             _ => panic!("Expected Write command"),
         }
+        match Cli::parse_from(["pcf", "write", "in.json", "out.pcf", "--no-validate"]).cmd {
+            Command::Write { no_validate, .. } => assert!(no_validate),
+            _ => panic!("Expected Write command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_check_command() {
+        match Cli::parse_from(["pcf", "check", "file.pcf", "file.json"]).cmd {
+            Command::Check { file, json_in } => {
+                assert_eq!(file, PathBuf::from("file.pcf"));
+                assert_eq!(json_in, PathBuf::from("file.json"));
+            },
+            _ => panic!("Expected Check command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "check", "file.pcf"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_completions_command() {
+        match Cli::parse_from(["pcf", "completions", "zsh"]).cmd {
+            Command::Completions { shell } => assert_eq!(shell, Shell::Zsh),
+            _ => panic!("Expected Completions command"),
+        }
+        assert!(Cli::try_parse_from(["pcf", "completions", "tcsh"]).is_err());
+
+        let mut out = Vec::new();
+        clap_complete::generate(Shell::Bash, &mut Cli::command(), "pcf", &mut out);
+        assert!(String::from_utf8(out).unwrap().contains("diff-blocks"));
     }
 }
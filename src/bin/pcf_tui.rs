@@ -1,104 +1,724 @@
 //! Interactive TUI viewer for PCF files.
 //!
-//! Keys: ↑/k/Mouse-Up  ↓/j/Mouse-Down   g-goto   q-quit
+//! Keys: ↑/k/Mouse-Up  ↓/j/Mouse-Down   PgUp/PgDn  Home/End   ←/→-view   g-goto   L-next loop   i-int at top   c-cycle rows   x-difference list   h-heat map   t-theme   e-edit   q-quit
+//!
+//! Edit mode (hex view, file A): arrows move the cursor, two hex digits
+//! overwrite a byte, u undoes, Ctrl-r redoes, Ctrl-s saves, Esc leaves.
+//! Quitting with unsaved edits asks first.
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseEventKind},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind},
     execute,
     terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{backend::{Backend, CrosstermBackend}, layout::{Constraint, Direction, Layout, Rect}, style::{Color, Style}, text::{Line, Span}, widgets::{Block, Borders, Paragraph}, Frame, Terminal};
-use std::{cmp, fs, io, path::PathBuf, time::Duration};
+use ratatui::{backend::CrosstermBackend, layout::{Constraint, Direction, Layout, Rect}, style::{Color, Modifier, Style}, text::{Line, Span}, widgets::{Block, Borders, Clear, Paragraph}, Frame, Terminal};
+use std::{cmp, collections::VecDeque, fs, io, ops::Range, path::PathBuf, time::Duration};
+
+use pcf_parser::{decode_with, layout::{byte_offset_to_cycle, cycle_byte_offset, PatternEncoding, PcfLayout, NUM_BITS, NUM_LOOPS}, write_file_atomic, DecodeOptions, IntFormat, PatternFileData};
 
 /// CLI arguments.
 #[derive(Parser)]
 struct Args {
     file_a: PathBuf,
     file_b: Option<PathBuf>,
+
+    /// Colour theme
+    #[arg(long, value_enum, default_value_t = ThemeName::Dark)]
+    theme: ThemeName,
+}
+
+/// Named colour themes selectable with `--theme` (cycled at runtime with `t`)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum ThemeName {
+    Dark,
+    Light,
+    HighContrast,
+    Mono,
+}
+
+impl ThemeName {
+    fn all() -> &'static [ThemeName] {
+        &[ThemeName::Dark, ThemeName::Light, ThemeName::HighContrast, ThemeName::Mono]
+    }
+
+    fn next(self) -> ThemeName {
+        let all = Self::all();
+        let idx = all.iter().position(|t| *t == self).unwrap_or(0);
+        all[(idx + 1) % all.len()]
+    }
+}
+
+/// Every style the viewer draws with, so no colour is hardcoded at a call site.
+#[derive(Copy, Clone, Debug)]
+struct Theme {
+    name: ThemeName,
+    byte: Style,
+    diff: Style,
+    offset: Style,
+    title: Style,
+    menu_active: Style,
+    menu_inactive: Style,
+    key: Style,
+    prompt: Style,
+    bit_set: Style,
+}
+
+impl Theme {
+    fn new(name: ThemeName) -> Theme {
+        let bold = Modifier::BOLD;
+        match name {
+            ThemeName::Dark => Theme {
+                name,
+                byte: Style::default().fg(Color::White),
+                diff: Style::default().fg(Color::Red),
+                offset: Style::default().fg(Color::DarkGray),
+                title: Style::default().fg(Color::Magenta).add_modifier(bold),
+                menu_active: Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(bold),
+                menu_inactive: Style::default().fg(Color::Yellow),
+                key: Style::default().fg(Color::Cyan),
+                prompt: Style::default().fg(Color::Yellow),
+                bit_set: Style::default().fg(Color::Green).add_modifier(bold),
+            },
+            ThemeName::Light => Theme {
+                name,
+                byte: Style::default().fg(Color::Black),
+                diff: Style::default().fg(Color::Red).add_modifier(bold),
+                offset: Style::default().fg(Color::Gray),
+                title: Style::default().fg(Color::Blue).add_modifier(bold),
+                menu_active: Style::default().fg(Color::White).bg(Color::Blue).add_modifier(bold),
+                menu_inactive: Style::default().fg(Color::Blue),
+                key: Style::default().fg(Color::Magenta),
+                prompt: Style::default().fg(Color::Blue),
+                bit_set: Style::default().fg(Color::Blue).add_modifier(bold),
+            },
+            ThemeName::HighContrast => Theme {
+                name,
+                byte: Style::default().fg(Color::White),
+                diff: Style::default().fg(Color::Black).bg(Color::LightYellow).add_modifier(bold),
+                offset: Style::default().fg(Color::LightCyan),
+                title: Style::default().fg(Color::LightYellow).add_modifier(bold),
+                menu_active: Style::default().fg(Color::Black).bg(Color::White).add_modifier(bold),
+                menu_inactive: Style::default().fg(Color::White),
+                key: Style::default().fg(Color::LightYellow).add_modifier(bold),
+                prompt: Style::default().fg(Color::LightYellow),
+                bit_set: Style::default().fg(Color::LightGreen).add_modifier(bold),
+            },
+            ThemeName::Mono => Theme {
+                name,
+                byte: Style::default(),
+                diff: Style::default().add_modifier(Modifier::REVERSED),
+                offset: Style::default().add_modifier(Modifier::DIM),
+                title: Style::default().add_modifier(bold),
+                menu_active: Style::default().add_modifier(Modifier::REVERSED | bold),
+                menu_inactive: Style::default(),
+                key: Style::default().add_modifier(bold),
+                prompt: Style::default().add_modifier(bold),
+                bit_set: Style::default().add_modifier(bold),
+            },
+        }
+    }
 }
 
 /// One rendered line (offset, hex, ascii, per-byte diff flags)
 struct HexLine {
     off: usize,
+    /// Pattern cycle shown in place of the offset in cycle-aligned rendering
+    cycle: Option<usize>,
     hex_spans: Vec<Span<'static>>,
     ascii_spans: Vec<Span<'static>>,
 }
 
-fn build_lines(buf_a: &[u8], buf_b: Option<&[u8]>, bytes: usize) -> Vec<HexLine> {
-    let mut out = Vec::new();
-    for (row, chunk_a) in buf_a.chunks(bytes).enumerate() {
-        let offset = row * bytes;
-        let chunk_b = buf_b.and_then(|b| b.get(offset..offset + bytes)).unwrap_or(&[]);
+/// One hex-view row: the bytes it covers and the number of cells it shows
+/// (a short final row is padded with `00` cells).
+struct RowSpan {
+    range: Range<usize>,
+    cells: usize,
+    cycle: Option<usize>,
+}
 
-        let mut hex_spans = Vec::with_capacity(bytes * 2);
-        let mut ascii_spans = Vec::with_capacity(bytes);
+/// Row geometry of one buffer in the hex view: `bytes` per row up to `split`,
+/// then one row of `column` bytes per pattern cycle. Rows are computed on
+/// demand, so only the visible ones are ever rendered.
+#[derive(Copy, Clone, Debug)]
+struct Rows {
+    len: usize,
+    split: usize,
+    bytes: usize,
+    column: usize,
+}
 
-        for i in 0..bytes {
-            let a = *chunk_a.get(i).unwrap_or(&0);
-            let b = *chunk_b.get(i).unwrap_or(&0);
-            let diff = buf_b.is_some() && a != b;
+impl Rows {
+    /// `bytes` per row throughout, or, given the header size and column width
+    /// in `cycle_rows`, the header at `bytes` per row followed by one row per
+    /// pattern cycle.
+    fn new(len: usize, bytes: usize, cycle_rows: Option<(usize, usize)>) -> Rows {
+        match cycle_rows {
+            None => Rows { len, split: len, bytes, column: bytes },
+            Some((header, column)) => Rows { len, split: header.min(len), bytes, column: column.max(1) },
+        }
+    }
 
-            let fg = if diff { Color::Red } else { Color::White };
-            hex_spans.push(Span::styled(format!("{:02X}", a), Style::default().fg(fg)));
-            if i != bytes - 1 {
-                hex_spans.push(Span::raw(" "));
-            }
+    fn header_rows(&self) -> usize {
+        self.split.div_ceil(self.bytes)
+    }
+
+    fn count(&self) -> usize {
+        self.header_rows() + (self.len - self.split).div_ceil(self.column)
+    }
+
+    /// Row `row`, which must be below [`count`](Self::count).
+    fn span(&self, row: usize) -> RowSpan {
+        let header_rows = self.header_rows();
+        if row < header_rows {
+            let start = row * self.bytes;
+            RowSpan { range: start..cmp::min(start + self.bytes, self.split), cells: self.bytes, cycle: None }
+        } else {
+            let cycle = row - header_rows;
+            let start = self.split + cycle * self.column;
+            RowSpan { range: start..cmp::min(start + self.column, self.len), cells: self.column, cycle: Some(cycle) }
+        }
+    }
+
+    /// Row containing file offset `off`; offsets past the end map to the last row.
+    fn row_of_offset(&self, off: usize) -> usize {
+        let row = if off < self.split {
+            off / self.bytes
+        } else {
+            self.header_rows() + (off - self.split) / self.column
+        };
+        row.min(self.count().saturating_sub(1))
+    }
+
+    /// File offset of the first byte on row `row`, or 0 past the end.
+    fn offset_of_row(&self, row: usize) -> usize {
+        if row < self.count() { self.span(row).range.start } else { 0 }
+    }
+}
+
+/// Renders row `row` of `buf`, flagging bytes that differ from `other` and
+/// showing the byte at `cursor` reversed.
+fn build_line(buf: &[u8], other: Option<&[u8]>, row: &RowSpan, cursor: Option<usize>, theme: &Theme) -> HexLine {
+    let mut hex_spans = Vec::with_capacity(row.cells * 2);
+    let mut ascii_spans = Vec::with_capacity(row.cells);
+
+    for i in 0..row.cells {
+        let off = row.range.start + i;
+        let a = if off < row.range.end { buf.get(off).copied() } else { None };
+        let diff = other.is_some_and(|b| a.is_some() && a != b.get(off).copied());
+        let a = a.unwrap_or(0);
+
+        let style = if diff { theme.diff } else { theme.byte };
+        let style = if cursor == Some(off) { style.add_modifier(Modifier::REVERSED) } else { style };
+        hex_spans.push(Span::styled(format!("{:02X}", a), style));
+        if i != row.cells - 1 {
+            hex_spans.push(Span::raw(" "));
+        }
+
+        let chr = if a.is_ascii_graphic() { a as char } else { '.' };
+        ascii_spans.push(Span::styled(chr.to_string(), style));
+    }
+
+    HexLine { off: row.range.start, cycle: row.cycle, hex_spans, ascii_spans }
+}
+
+/// Largest bytes-per-line whose hex line fits a pane when the terminal is
+/// `term_width` columns wide (split in two when comparing). A line of `n`
+/// bytes takes `4n + 11` columns: offset, gaps, hex cells and ascii gutter.
+fn fit_bytes_per_line(term_width: u16, two_panes: bool) -> usize {
+    let pane = if two_panes { term_width / 2 } else { term_width } as usize;
+    let inner = pane.saturating_sub(2); // borders
+    (inner.saturating_sub(11) / 4).clamp(1, 64)
+}
+
+enum Mode { View, Goto, Diffs, Edit, ConfirmQuit }
+
+/// Most edits [`EditHistory`] keeps; older ones can no longer be undone.
+const UNDO_LIMIT: usize = 1000;
+
+/// One byte of file A overwritten in edit mode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Edit {
+    offset: usize,
+    old: u8,
+    new: u8,
+}
+
+/// Bounded undo/redo stacks of [`Edit`]s. A new edit clears the redo stack.
+#[derive(Debug, Default)]
+struct EditHistory {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl EditHistory {
+    /// Writes `value` at `offset` and records the edit. Returns false, and
+    /// records nothing, when `offset` is past the end or the byte already
+    /// holds `value`.
+    fn apply(&mut self, buf: &mut [u8], offset: usize, value: u8) -> bool {
+        let Some(byte) = buf.get_mut(offset) else { return false };
+        if *byte == value {
+            return false;
+        }
+        self.undo.push_back(Edit { offset, old: *byte, new: value });
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+        *byte = value;
+        true
+    }
+
+    /// Reverts the latest edit, returning its offset.
+    fn undo(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let edit = self.undo.pop_back()?;
+        buf[edit.offset] = edit.old;
+        self.redo.push(edit);
+        Some(edit.offset)
+    }
+
+    /// Reapplies the latest undone edit, returning its offset.
+    fn redo(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let edit = self.redo.pop()?;
+        buf[edit.offset] = edit.new;
+        self.undo.push_back(edit);
+        Some(edit.offset)
+    }
+}
 
-            let chr = if a.is_ascii_graphic() { a as char } else { '.' };
-            ascii_spans.push(Span::styled(chr.to_string(), Style::default().fg(fg)));
+/// Maximal runs of offsets at which the files differ; bytes present in only
+/// one of them differ too.
+fn diff_runs(a: &[u8], b: &[u8]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for i in (0..a.len().max(b.len())).filter(|&i| a.get(i) != b.get(i)) {
+        match runs.last_mut() {
+            Some(run) if run.end == i => run.end += 1,
+            _ => runs.push(i..i + 1),
         }
+    }
+    runs
+}
 
-        out.push(HexLine { off: offset, hex_spans, ascii_spans });
+/// Brings `runs` (as [`diff_runs`] builds them) up to date after only the
+/// byte at `offset` changed, without rescanning the files.
+fn update_diff_runs(runs: &mut Vec<Range<usize>>, offset: usize, differs: bool) {
+    let i = runs.partition_point(|run| run.end <= offset);
+    let inside = runs.get(i).is_some_and(|run| run.start <= offset);
+    match (differs, inside) {
+        (true, true) | (false, false) => {}
+        (true, false) => {
+            let joins_left = i > 0 && runs[i - 1].end == offset;
+            let joins_right = runs.get(i).is_some_and(|run| run.start == offset + 1);
+            match (joins_left, joins_right) {
+                (true, true) => {
+                    runs[i - 1].end = runs[i].end;
+                    runs.remove(i);
+                }
+                (true, false) => runs[i - 1].end += 1,
+                (false, true) => runs[i].start -= 1,
+                (false, false) => runs.insert(i, offset..offset + 1),
+            }
+        }
+        (false, true) => {
+            let run = runs[i].clone();
+            match (run.start == offset, run.end == offset + 1) {
+                (true, true) => {
+                    runs.remove(i);
+                }
+                (true, false) => runs[i].start += 1,
+                (false, true) => runs[i].end -= 1,
+                (false, false) => {
+                    runs[i].end = offset;
+                    runs.insert(i + 1, offset + 1..run.end);
+                }
+            }
+        }
     }
-    out
 }
 
-enum Mode { View, Goto }
+/// Stores `byte` at offset `rel` into the pattern region (or the trailer
+/// after it) of an already decoded record, as decoding the edited file would.
+fn set_pattern_byte(data: &mut PatternFileData, rel: usize, byte: u8) {
+    let column_bytes = data.encoding.column_bytes(NUM_BITS);
+    let (col, k, cols) = (rel / column_bytes, rel % column_bytes, data.cols());
+    if col < cols {
+        match data.encoding {
+            PatternEncoding::BytesPerBit => data.pattern_data[k][col] = byte,
+            PatternEncoding::PackedBits => {
+                for bit in (8 * k..8 * k + 8).take_while(|&bit| bit < NUM_BITS) {
+                    data.pattern_data[bit][col] = (byte >> (bit % 8)) & 1;
+                }
+            }
+        }
+    } else if let Some(t) = data.trailer.get_mut(rel - cols * column_bytes) {
+        *t = byte;
+    }
+}
 
 /// Menu options for the TUI
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 enum MenuItem {
     HexView,
+    PatternView,
     DiffView,
 }
 
 impl MenuItem {
     fn all() -> &'static [MenuItem] {
-        &[MenuItem::HexView, MenuItem::DiffView]
+        &[MenuItem::HexView, MenuItem::PatternView, MenuItem::DiffView]
     }
     fn title(&self) -> &'static str {
         match self {
             MenuItem::HexView => "Hex View",
+            MenuItem::PatternView => "Pattern View",
             MenuItem::DiffView => "Diff View",
         }
     }
 }
 
 struct App<'a> {
-    lines_a: Vec<HexLine>,
-    lines_b: Option<Vec<HexLine>>,
+    rows_a: Rows,
+    rows_b: Option<Rows>,
     scroll: usize,
     bytes_per_line: usize,
     mode: Mode,
     goto_input: String,
     menu_selected: usize,
-    _buf: &'a [u8],
+    theme: Theme,
+    /// File A, as edited in edit mode
+    buf_a: Vec<u8>,
+    path_a: PathBuf,
+    buf_b: Option<&'a [u8]>,
+    /// Decoded header of file A, if it parses as a PCF
+    header: Option<PatternFileData>,
+    /// Loop most recently jumped to with `L`
+    current_loop: Option<usize>,
+    /// Pattern view shades each cycle by bit density instead of per-bit cells
+    heat: bool,
+    /// Rows visible in the viewer pane, updated on every draw
+    page_rows: usize,
+    /// Hex view shows the pattern region one cycle per line (toggled with `c`)
+    cycle_aligned: bool,
+    /// Every run of differing bytes between the two files, computed up front
+    diff_runs: Vec<Range<usize>>,
+    /// Entry highlighted in the difference list
+    diff_selected: usize,
+    /// Integer interpretation of the bytes at the top offset, cycled with `i`
+    int_format: Option<IntFormat>,
+    /// Byte of file A selected in edit mode
+    cursor: usize,
+    /// First hex digit typed for the byte at the cursor
+    pending_nibble: Option<u8>,
+    history: EditHistory,
+    /// File A has edits that are not saved
+    dirty: bool,
+    /// Outcome of the last save, shown in the edit bar
+    status: String,
 }
 
 impl<'a> App<'a> {
+    /// Switches to the next theme; lines pick it up on the next draw.
+    fn cycle_theme(&mut self) {
+        self.theme = Theme::new(self.theme.name.next());
+    }
+
+    /// Re-lays the hex view at `bytes` per line, keeping the top offset in view.
+    fn set_bytes_per_line(&mut self, bytes: usize) {
+        if bytes == self.bytes_per_line {
+            return;
+        }
+        let top = self.offset_of_row(self.scroll);
+        self.bytes_per_line = bytes;
+        self.relayout();
+        self.scroll = self.row_of_offset(top);
+    }
+
+    /// Toggles cycle-aligned rendering of the pattern region, keeping the top
+    /// offset in view. Needs a parsed header to know where the pattern starts.
+    fn toggle_cycle_aligned(&mut self) {
+        if self.header.is_none() {
+            return;
+        }
+        let top = self.offset_of_row(self.scroll);
+        self.cycle_aligned = !self.cycle_aligned;
+        self.relayout();
+        self.scroll = self.row_of_offset(top);
+    }
+
+    /// Recomputes the row geometry after a change of width or row mode.
+    fn relayout(&mut self) {
+        let bytes = self.bytes_per_line;
+        let cycle_rows = (self.cycle_aligned && self.header.is_some())
+            .then(|| (PcfLayout::default().header_size(), self.encoding().column_bytes(NUM_BITS)));
+        self.rows_a = Rows::new(self.buf_a.len(), bytes, cycle_rows);
+        self.rows_b = self.buf_b.map(|b| Rows::new(b.len(), bytes, cycle_rows));
+    }
+
+    /// Hex-view row containing file offset `off`.
+    fn row_of_offset(&self, off: usize) -> usize {
+        self.rows_a.row_of_offset(off)
+    }
+
+    /// File offset of the first byte on hex-view row `row`.
+    fn offset_of_row(&self, row: usize) -> usize {
+        self.rows_a.offset_of_row(row)
+    }
+
+    /// Number of scrollable rows in the current view.
+    fn total_rows(&self) -> usize {
+        if MenuItem::all()[self.menu_selected] == MenuItem::PatternView {
+            return self.header.as_ref().map_or(0, |h| h.cols());
+        }
+        let b = self.rows_b.as_ref().map_or(0, Rows::count);
+        self.rows_a.count().max(b)
+    }
+
+    /// Scroll position at which the last row sits at the bottom of the pane.
+    fn max_scroll(&self) -> usize {
+        self.total_rows().saturating_sub(self.page_rows)
+    }
+
+    /// Moves the view by `delta` rows, clamped to the content.
+    fn scroll_by(&mut self, delta: isize) {
+        let target = self.scroll.saturating_add_signed(delta);
+        self.scroll = target.min(self.max_scroll());
+    }
+
+    fn page(&self) -> isize {
+        self.page_rows.max(1) as isize
+    }
+
+    /// Pattern encoding of file A, from the decoded header when available.
+    fn encoding(&self) -> PatternEncoding {
+        self.header.as_ref().map(|h| h.encoding).unwrap_or_default()
+    }
+
+    /// File offset of the first byte of `cycle` in file A.
+    fn cycle_offset(&self, cycle: usize) -> usize {
+        cycle_byte_offset(&PcfLayout::default(), self.encoding(), cycle)
+    }
+
+    /// The cycle of file A containing byte `off`, or `None` in the header.
+    fn cycle_of_offset(&self, off: usize) -> Option<usize> {
+        byte_offset_to_cycle(&PcfLayout::default(), self.encoding(), off)
+    }
+
+    /// File offset of the first row in view.
+    fn top_offset(&self) -> usize {
+        if MenuItem::all()[self.menu_selected] == MenuItem::PatternView {
+            self.cycle_offset(self.scroll)
+        } else {
+            self.offset_of_row(self.scroll)
+        }
+    }
+
+    /// Status readout for the top of the view: offset in hex and decimal, plus
+    /// the cycle it falls in once past the header of a parsed file.
+    fn position_label(&self) -> String {
+        let off = self.top_offset();
+        let cycle = self.header.as_ref().and_then(|_| self.cycle_of_offset(off));
+        match cycle {
+            Some(c) => format!("@0x{:06X} ({})  cycle {}   ", off, off, c),
+            None => format!("@0x{:06X} ({})   ", off, off),
+        }
+    }
+
+    /// Moves the difference-list highlight by `delta` entries.
+    fn select_diff_by(&mut self, delta: isize) {
+        let last = self.diff_runs.len().saturating_sub(1);
+        self.diff_selected = self.diff_selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Shows the highlighted difference at the top of the hex view.
+    fn jump_to_selected_diff(&mut self) {
+        if let Some(run) = self.diff_runs.get(self.diff_selected) {
+            self.menu_selected = 0;
+            self.scroll = self.row_of_offset(run.start).min(self.max_scroll());
+        }
+    }
+
+    /// One difference-list line: index, offset, length and cycle.
+    fn diff_label(&self, i: usize) -> String {
+        let run = &self.diff_runs[i];
+        let cycle = self.header.as_ref().and_then(|_| self.cycle_of_offset(run.start));
+        let cycle = cycle.map_or_else(|| "header".to_string(), |c| format!("cycle {}", c));
+        format!("{:>5}  0x{:06X} ({:>8})  {:>6} byte(s)  {}", i + 1, run.start, run.start, run.len(), cycle)
+    }
+
+    /// Steps through off → each [`IntFormat`] → off.
+    fn cycle_int_format(&mut self) {
+        let all = IntFormat::all();
+        self.int_format = match self.int_format {
+            None => Some(all[0]),
+            Some(f) => all.iter().position(|&g| g == f).and_then(|i| all.get(i + 1)).copied(),
+        };
+    }
+
+    /// File A's bytes at the top offset read with the selected [`IntFormat`].
+    fn int_label(&self) -> Option<String> {
+        let format = self.int_format?;
+        let value = format.read(&self.buf_a, self.top_offset());
+        Some(match value {
+            Some(v) => format!("{} 0x{:0width$X} ({})   ", format.name(), v, v, width = 2 * format.width()),
+            None => format!("{} past end   ", format.name()),
+        })
+    }
+
+    /// Scrolls to the start of the next non-empty loop region after the current one.
+    fn jump_to_next_loop(&mut self) {
+        let Some(header) = &self.header else { return };
+        let first = self.current_loop.map_or(0, |i| i + 1);
+        let next = (first..first + NUM_LOOPS)
+            .map(|i| i % NUM_LOOPS)
+            .find_map(|i| header.loop_range(i).map(|(start, _)| (i, start)));
+
+        if let Some((i, start)) = next {
+            self.scroll_to_cycle(start);
+            self.current_loop = Some(i);
+        }
+    }
+
+    /// Enters edit mode at the top of the hex view.
+    fn start_edit(&mut self) {
+        if self.buf_a.is_empty() {
+            return;
+        }
+        self.cursor = self.offset_of_row(self.scroll).min(self.buf_a.len() - 1);
+        self.pending_nibble = None;
+        self.status.clear();
+        self.mode = Mode::Edit;
+    }
+
+    /// Moves the cursor by `delta` bytes, scrolling to keep it in view.
+    fn move_cursor(&mut self, delta: isize) {
+        self.place_cursor(self.cursor.saturating_add_signed(delta));
+    }
+
+    /// Moves the cursor `delta` rows up or down, keeping its column where the
+    /// target row is long enough.
+    fn move_cursor_rows(&mut self, delta: isize) {
+        let row = self.row_of_offset(self.cursor);
+        let column = self.cursor - self.offset_of_row(row);
+        let target = row.saturating_add_signed(delta).min(self.rows_a.count().saturating_sub(1));
+        let span = self.rows_a.span(target).range;
+        self.place_cursor((span.start + column).min(span.end.saturating_sub(1)));
+    }
+
+    fn place_cursor(&mut self, offset: usize) {
+        self.cursor = offset.min(self.buf_a.len().saturating_sub(1));
+        self.pending_nibble = None;
+        let row = self.row_of_offset(self.cursor);
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row >= self.scroll + self.page_rows.max(1) {
+            self.scroll = row + 1 - self.page_rows.max(1);
+        }
+    }
+
+    /// Takes one typed hex digit: the first is held, the second completes
+    /// the byte at the cursor, which is then written and the cursor advanced.
+    fn type_nibble(&mut self, digit: u8) {
+        let Some(high) = self.pending_nibble.take() else {
+            self.pending_nibble = Some(digit);
+            return;
+        };
+        if self.history.apply(&mut self.buf_a, self.cursor, high << 4 | digit) {
+            self.after_edit(self.cursor);
+        }
+        self.move_cursor(1);
+    }
+
+    fn undo(&mut self) {
+        if let Some(offset) = self.history.undo(&mut self.buf_a) {
+            self.after_edit(offset);
+            self.place_cursor(offset);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(offset) = self.history.redo(&mut self.buf_a) {
+            self.after_edit(offset);
+            self.place_cursor(offset);
+        }
+    }
+
+    /// Updates what is derived from file A's bytes after the byte at
+    /// `offset` changed: the diff run around it, and the decoded header,
+    /// which is only decoded again when the byte lies inside it.
+    fn after_edit(&mut self, offset: usize) {
+        self.dirty = true;
+        self.status.clear();
+        if let Some(b) = self.buf_b {
+            update_diff_runs(&mut self.diff_runs, offset, self.buf_a.get(offset) != b.get(offset));
+            self.diff_selected = self.diff_selected.min(self.diff_runs.len().saturating_sub(1));
+        }
+
+        let header_size = PcfLayout::default().header_size();
+        if offset < header_size {
+            let opts = DecodeOptions { total_len: Some(self.buf_a.len()), ..Default::default() };
+            self.header = decode_with(&mut &self.buf_a[..], &opts).ok();
+            if self.header.is_none() {
+                self.cycle_aligned = false;
+            }
+            self.relayout();
+        } else if let Some(header) = self.header.as_mut() {
+            set_pattern_byte(header, offset - header_size, self.buf_a[offset]);
+        }
+    }
+
+    fn save(&mut self) {
+        self.status = match write_file_atomic(&self.path_a, &self.buf_a) {
+            Ok(()) => {
+                self.dirty = false;
+                format!("saved {}", self.path_a.display())
+            }
+            Err(e) => format!("save failed: {}", e),
+        };
+    }
+
+    /// Edit-mode status: cursor, pending digit, history depth and last save.
+    fn edit_label(&self) -> String {
+        let pending = self.pending_nibble.map_or_else(String::new, |n| format!("  typed {:X}_", n));
+        format!(
+            "0x{:06X} = {:02X}{}  undo {} / redo {}{}  {}",
+            self.cursor, self.buf_a[self.cursor], pending, self.history.undo.len(), self.history.redo.len(),
+            if self.dirty { "  [modified]" } else { "" }, self.status
+        )
+    }
+
     fn try_jump(&mut self) -> Result<()> {
         let s = self.goto_input.trim();
         if s.is_empty() { return Ok(()); }
+        // `#N` is pattern cycle N rather than a byte offset
+        if let Some(cycle) = s.strip_prefix('#') {
+            self.scroll_to_cycle(cycle.trim().parse()?);
+            return Ok(());
+        }
         let off = if let Some(hex) = s.strip_prefix("0x") {
             usize::from_str_radix(hex, 16)?
         } else if let Some(hex) = s.strip_suffix('h').or_else(|| s.strip_suffix('H')) {
             usize::from_str_radix(hex, 16)?
         } else { s.parse()? };
-        self.scroll = off / self.bytes_per_line;
+        self.scroll = if MenuItem::all()[self.menu_selected] == MenuItem::PatternView {
+            // the pattern view scrolls by cycle; the header counts as cycle 0
+            self.cycle_of_offset(off).unwrap_or(0)
+        } else {
+            self.row_of_offset(off)
+        };
         Ok(())
     }
+
+    /// Scrolls so `cycle` is the top row: the pattern view scrolls by cycle,
+    /// the hex view to the row holding the cycle's first byte.
+    fn scroll_to_cycle(&mut self, cycle: usize) {
+        self.scroll = if MenuItem::all()[self.menu_selected] == MenuItem::PatternView {
+            cycle
+        } else {
+            self.row_of_offset(self.cycle_offset(cycle))
+        };
+    }
 }
 
 fn main() -> Result<()> {
@@ -114,7 +734,9 @@ fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut term = Terminal::new(backend)?;
 
-    let res = run(&mut term, &buf_a, buf_b.as_deref());
+    let opts = DecodeOptions { total_len: Some(buf_a.len()), ..Default::default() };
+    let header = decode_with(&mut &buf_a[..], &opts).ok();
+    let res = run(&mut term, buf_a, args.file_a, buf_b.as_deref(), header, Theme::new(args.theme));
 
     terminal::disable_raw_mode()?;
     execute!(term.backend_mut(), DisableMouseCapture, LeaveAlternateScreen)?;
@@ -122,12 +744,17 @@ fn main() -> Result<()> {
     res
 }
 
-fn run(term: &mut Terminal<CrosstermBackend<io::Stdout>>, buf_a: &[u8], buf_b: Option<&[u8]>) -> Result<()> {
-    let bytes = 16;
-    let lines_a = build_lines(buf_a, buf_b, bytes);
-    let lines_b = buf_b.map(|b| build_lines(b, Some(buf_a), bytes));
+fn run(term: &mut Terminal<CrosstermBackend<io::Stdout>>, buf_a: Vec<u8>, path_a: PathBuf, buf_b: Option<&[u8]>, header: Option<PatternFileData>, theme: Theme) -> Result<()> {
+    let bytes = fit_bytes_per_line(term.size()?.width, buf_b.is_some());
+    let rows_a = Rows::new(buf_a.len(), bytes, None);
+    let rows_b = buf_b.map(|b| Rows::new(b.len(), bytes, None));
 
-    let mut app = App { lines_a, lines_b, scroll: 0, bytes_per_line: bytes, mode: Mode::View, goto_input: String::new(), menu_selected: 0, _buf: buf_a };
+    let diff_runs = buf_b.map(|b| diff_runs(&buf_a, b)).unwrap_or_default();
+    let mut app = App {
+        rows_a, rows_b, scroll: 0, bytes_per_line: bytes, mode: Mode::View, goto_input: String::new(), menu_selected: 0, theme,
+        buf_a, path_a, buf_b, header, current_loop: None, heat: false, page_rows: 1, cycle_aligned: false, diff_runs, diff_selected: 0,
+        int_format: None, cursor: 0, pending_nibble: None, history: EditHistory::default(), dirty: false, status: String::new(),
+    };
 
     loop {
         let mut should_quit = false;
@@ -136,37 +763,29 @@ fn run(term: &mut Terminal<CrosstermBackend<io::Stdout>>, buf_a: &[u8], buf_b: O
             // Draw menu bar
             let menu_items = MenuItem::all();
             let menu_spans: Vec<Span> = menu_items.iter().enumerate().map(|(i, item)| {
-                if i == app.menu_selected {
-                    Span::styled(
-                        format!(" {} ", item.title()),
-                        Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(ratatui::style::Modifier::BOLD),
-                    )
-                } else {
-                    Span::styled(
-                        format!(" {} ", item.title()),
-                        Style::default().fg(Color::Yellow),
-                    )
-                }
+                let style = if i == app.menu_selected { app.theme.menu_active } else { app.theme.menu_inactive };
+                Span::styled(format!(" {} ", item.title()), style)
             }).collect();
             let menu = Paragraph::new(Line::from(menu_spans)).block(Block::default().borders(Borders::BOTTOM));
             f.render_widget(menu, Rect { x: 0, y: 0, width: f.size().width, height: 3 });
 
             // Adjust layout to leave space for menu
-            let rows = if matches!(app.mode, Mode::Goto) {
+            let rows = if matches!(app.mode, Mode::Goto | Mode::Edit | Mode::ConfirmQuit) {
                 Layout::default()
                     .direction(Direction::Vertical)
-                    .constraints([Constraint::Length(1), Constraint::Min(1), Constraint::Length(3), Constraint::Length(1)])
+                    .constraints([Constraint::Length(1), Constraint::Min(1), Constraint::Length(3), Constraint::Length(2)])
                     .split(f.size())
                     .to_vec()
             } else {
                 Layout::default()
                     .direction(Direction::Vertical)
-                    .constraints([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)])
+                    .constraints([Constraint::Length(1), Constraint::Min(1), Constraint::Length(2)])
                     .split(f.size())
                     .to_vec()
             };
             let viewer_area = rows[1];
-            let panes = if app.lines_b.is_some() {
+            app.page_rows = viewer_area.height.saturating_sub(2) as usize;
+            let panes = if app.buf_b.is_some() {
                 Layout::default()
                     .direction(Direction::Horizontal)
                     .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
@@ -177,11 +796,15 @@ fn run(term: &mut Terminal<CrosstermBackend<io::Stdout>>, buf_a: &[u8], buf_b: O
             // Show view based on menu selection
             match menu_items[app.menu_selected] {
                 MenuItem::HexView => {
-                    draw_side::<CrosstermBackend<io::Stdout>>(f, panes[0], &app.lines_a, "File A", app.scroll);
-                    if let (Some(lines), Some(area)) = (app.lines_b.as_ref(), panes.get(1)) {
-                        draw_side::<CrosstermBackend<io::Stdout>>(f, *area, lines, "File B", app.scroll);
+                    let cursor = matches!(app.mode, Mode::Edit).then_some(app.cursor);
+                    draw_side(f, panes[0], &app.buf_a, app.buf_b, &app.rows_a, "File A", app.scroll, cursor, &app.theme);
+                    if let (Some(buf), Some(rows), Some(area)) = (app.buf_b, app.rows_b.as_ref(), panes.get(1)) {
+                        draw_side(f, *area, buf, Some(&app.buf_a), rows, "File B", app.scroll, None, &app.theme);
                     }
                 }
+                MenuItem::PatternView => {
+                    draw_pattern(f, viewer_area, app.header.as_ref(), app.scroll, app.heat, &app.theme);
+                }
                 MenuItem::DiffView => {
                     // Placeholder: show a message for now
                     let diff_msg = Paragraph::new("Diff view coming soon!").block(Block::default().borders(Borders::ALL).title("Diff"));
@@ -189,20 +812,77 @@ fn run(term: &mut Terminal<CrosstermBackend<io::Stdout>>, buf_a: &[u8], buf_b: O
                 }
             }
     
+            if matches!(app.mode, Mode::Diffs) {
+                draw_diff_list(f, viewer_area, &app);
+            }
+
             if matches!(app.mode, Mode::Goto) {
                 let prompt = Paragraph::new(Line::from(vec![
-                    Span::styled("Goto offset: ", Style::default().fg(Color::Yellow)),
+                    Span::styled("Goto offset (#N for cycle N): ", app.theme.prompt),
                     Span::raw(&app.goto_input),
                 ]))
                     .block(Block::default().borders(Borders::ALL).title("Input"));
                 f.render_widget(prompt, rows[2]);
             }
 
-            let help = Line::from(vec![
-                Span::styled("↑/k", Style::default().fg(Color::Cyan)), Span::raw(" Scroll   "),
-                Span::styled("g", Style::default().fg(Color::Cyan)), Span::raw(" Goto   "),
-                Span::styled("q", Style::default().fg(Color::Cyan)), Span::raw(" Quit"),
+            if matches!(app.mode, Mode::Edit) {
+                let bar = Paragraph::new(Line::from(vec![
+                    Span::styled("Edit ", app.theme.prompt),
+                    Span::raw(app.edit_label()),
+                ]))
+                    .block(Block::default().borders(Borders::ALL).title("Edit — hex digits overwrite, u undo, Ctrl-r redo, Ctrl-s save, Esc done"));
+                f.render_widget(bar, rows[2]);
+            }
+
+            if matches!(app.mode, Mode::ConfirmQuit) {
+                let prompt = Paragraph::new(Line::from(vec![
+                    Span::styled("Unsaved changes to file A. ", app.theme.prompt),
+                    Span::styled("s", app.theme.key), Span::raw(" save and quit   "),
+                    Span::styled("q", app.theme.key), Span::raw(" quit without saving   "),
+                    Span::styled("Esc", app.theme.key), Span::raw(" keep editing   "),
+                    Span::raw(app.status.as_str()),
+                ]))
+                    .block(Block::default().borders(Borders::ALL).title("Quit?"));
+                f.render_widget(prompt, rows[2]);
+            }
+
+            let mut help = vec![
+                Span::styled(app.position_label(), app.theme.prompt),
+                Span::styled(app.int_label().unwrap_or_default(), app.theme.prompt),
+                Span::styled("↑/k", app.theme.key), Span::raw(" Scroll   "),
+                Span::styled("PgUp/PgDn", app.theme.key), Span::raw(" Page   "),
+                Span::styled("g", app.theme.key), Span::raw(" Goto   "),
+            ];
+            if app.header.is_some() {
+                let loop_label = app.current_loop.map_or("-".to_string(), |i| i.to_string());
+                help.push(Span::styled("L", app.theme.key));
+                help.push(Span::raw(format!(" Next loop ({})   ", loop_label)));
+            }
+            if app.buf_b.is_some() {
+                help.push(Span::styled("x", app.theme.key));
+                help.push(Span::raw(format!(" Diffs ({})   ", app.diff_runs.len())));
+            }
+            if menu_items[app.menu_selected] == MenuItem::HexView {
+                let label = if app.dirty { " Edit (modified)   " } else { " Edit   " };
+                help.push(Span::styled("e", app.theme.key));
+                help.push(Span::raw(label));
+            }
+            if menu_items[app.menu_selected] == MenuItem::HexView && app.header.is_some() {
+                let label = if app.cycle_aligned { "cycles" } else { "bytes" };
+                help.push(Span::styled("c", app.theme.key));
+                help.push(Span::raw(format!(" Rows ({})   ", label)));
+            }
+            if menu_items[app.menu_selected] == MenuItem::PatternView {
+                let label = if app.heat { "heat" } else { "cells" };
+                help.push(Span::styled("h", app.theme.key));
+                help.push(Span::raw(format!(" Render ({})   ", label)));
+            }
+            help.extend([
+                Span::styled("i", app.theme.key), Span::raw(" Int   "),
+                Span::styled("t", app.theme.key), Span::raw(format!(" Theme ({:?})   ", app.theme.name)),
+                Span::styled("q", app.theme.key), Span::raw(" Quit"),
             ]);
+            let help = Line::from(help);
             let bar = Paragraph::new(help).block(Block::default().borders(Borders::TOP));
             if let Some(help_area) = rows.last() {
                 f.render_widget(bar, *help_area);
@@ -213,27 +893,73 @@ fn run(term: &mut Terminal<CrosstermBackend<io::Stdout>>, buf_a: &[u8], buf_b: O
             match event::read()? {
                 Event::Key(k) if k.kind == KeyEventKind::Press => match app.mode {
                     Mode::View => match k.code {
+                        KeyCode::Char('q') if app.dirty => { app.status.clear(); app.mode = Mode::ConfirmQuit; }
                         KeyCode::Char('q') => should_quit = true,
-                        KeyCode::Up | KeyCode::Char('k') => app.scroll = app.scroll.saturating_sub(1),
-                        KeyCode::Down | KeyCode::Char('j') => app.scroll += 1,
+                        KeyCode::Up | KeyCode::Char('k') => app.scroll_by(-1),
+                        KeyCode::Down | KeyCode::Char('j') => app.scroll_by(1),
+                        KeyCode::PageUp => app.scroll_by(-app.page()),
+                        KeyCode::PageDown => app.scroll_by(app.page()),
+                        KeyCode::Home => app.scroll = 0,
+                        KeyCode::End => app.scroll = app.max_scroll(),
                         KeyCode::Char('g') | KeyCode::Char('G') => { app.mode = Mode::Goto; app.goto_input.clear(); }
+                        KeyCode::Char('t') => app.cycle_theme(),
+                        KeyCode::Char('L') => app.jump_to_next_loop(),
+                        KeyCode::Char('h') => app.heat = !app.heat,
+                        KeyCode::Char('i') => app.cycle_int_format(),
+                        KeyCode::Char('c') => app.toggle_cycle_aligned(),
+                        KeyCode::Char('x') if app.buf_b.is_some() => app.mode = Mode::Diffs,
+                        KeyCode::Char('e') if app.menu_selected == 0 => app.start_edit(),
                         KeyCode::Left => app.menu_selected = app.menu_selected.saturating_sub(1),
                         KeyCode::Right => app.menu_selected = (app.menu_selected + 1).min(MenuItem::all().len() - 1),
                         _ => {}
                     },
+                    Mode::Diffs => match k.code {
+                        KeyCode::Esc | KeyCode::Char('x') | KeyCode::Char('q') => app.mode = Mode::View,
+                        KeyCode::Up | KeyCode::Char('k') => app.select_diff_by(-1),
+                        KeyCode::Down | KeyCode::Char('j') => app.select_diff_by(1),
+                        KeyCode::PageUp => app.select_diff_by(-app.page()),
+                        KeyCode::PageDown => app.select_diff_by(app.page()),
+                        KeyCode::Home => app.diff_selected = 0,
+                        KeyCode::End => app.diff_selected = app.diff_runs.len().saturating_sub(1),
+                        KeyCode::Enter => { app.jump_to_selected_diff(); app.mode = Mode::View; }
+                        _ => {}
+                    },
+                    Mode::Edit => match k.code {
+                        KeyCode::Esc => app.mode = Mode::View,
+                        KeyCode::Char('r') if k.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
+                        KeyCode::Char('s') if k.modifiers.contains(KeyModifiers::CONTROL) => app.save(),
+                        KeyCode::Char('u') => app.undo(),
+                        KeyCode::Char(c) if c.is_ascii_hexdigit() => app.type_nibble(c.to_digit(16).unwrap_or(0) as u8),
+                        KeyCode::Left => app.move_cursor(-1),
+                        KeyCode::Right => app.move_cursor(1),
+                        KeyCode::Up => app.move_cursor_rows(-1),
+                        KeyCode::Down => app.move_cursor_rows(1),
+                        KeyCode::PageUp => app.move_cursor_rows(-app.page()),
+                        KeyCode::PageDown => app.move_cursor_rows(app.page()),
+                        _ => {}
+                    },
+                    Mode::ConfirmQuit => match k.code {
+                        KeyCode::Char('s') => { app.save(); should_quit = !app.dirty; }
+                        KeyCode::Char('q') | KeyCode::Char('y') => should_quit = true,
+                        KeyCode::Esc | KeyCode::Char('n') => app.mode = Mode::View,
+                        _ => {}
+                    },
                     Mode::Goto => match k.code {
                         KeyCode::Esc => app.mode = Mode::View,
-                        KeyCode::Enter => if app.try_jump().is_ok() { app.mode = Mode::View },
+                        KeyCode::Enter if app.try_jump().is_ok() => app.mode = Mode::View,
                         KeyCode::Backspace => { app.goto_input.pop(); },
                         KeyCode::Char(c) => app.goto_input.push(c),
                         _ => {}
                     },
                 },
                 Event::Mouse(m) if matches!(app.mode, Mode::View) => match m.kind {
-                    MouseEventKind::ScrollUp => app.scroll = app.scroll.saturating_sub(1),
-                    MouseEventKind::ScrollDown => app.scroll += 1,
+                    MouseEventKind::ScrollUp => app.scroll_by(-1),
+                    MouseEventKind::ScrollDown => app.scroll_by(1),
                     _ => {}
                 },
+                Event::Resize(width, _) => {
+                    app.set_bytes_per_line(fit_bytes_per_line(width, app.buf_b.is_some()));
+                }
                 _ => {}
             }
         }
@@ -244,33 +970,42 @@ fn run(term: &mut Terminal<CrosstermBackend<io::Stdout>>, buf_a: &[u8], buf_b: O
     Ok(())
 }
 
-/// Draws a single pane (file view) at the given `area`.
-fn draw_side<B: Backend>(
+/// Draws a single pane (file view) at the given `area`, rendering only the
+/// rows in view.
+#[allow(clippy::too_many_arguments)]
+fn draw_side(
     f: &mut Frame,
     area: Rect,
-    lines: &[HexLine],
+    buf: &[u8],
+    other: Option<&[u8]>,
+    rows: &Rows,
     title: &str,
     scroll: usize,
+    cursor: Option<usize>,
+    theme: &Theme,
 ) {
     let max_rows = area.height.saturating_sub(2) as usize;
-    let start = cmp::min(scroll, lines.len().saturating_sub(max_rows));
-    let slice = &lines[start..cmp::min(start + max_rows, lines.len())];
+    let start = cmp::min(scroll, rows.count().saturating_sub(max_rows));
+    let lines: Vec<HexLine> = (start..cmp::min(start + max_rows, rows.count()))
+        .map(|row| build_line(buf, other, &rows.span(row), cursor, theme))
+        .collect();
 
-    let header = Span::styled(
-        format!(" {} ", title),
-        Style::default().fg(Color::Magenta).add_modifier(ratatui::style::Modifier::BOLD),
-    );
+    let header = Span::styled(format!(" {} ", title), theme.title);
     let block = Block::default().borders(Borders::ALL).title(header);
 
-    let body: Vec<Line> = slice
-        .iter()
+    let body: Vec<Line> = lines
+        .into_iter()
         .map(|l| {
             let mut spans = Vec::with_capacity(l.hex_spans.len() + l.ascii_spans.len() + 4);
-            spans.push(Span::styled(format!("{:06X}", l.off), Style::default().fg(Color::DarkGray)));
+            let label = match l.cycle {
+                Some(cycle) => format!("c{:<5}", cycle),
+                None => format!("{:06X}", l.off),
+            };
+            spans.push(Span::styled(label, theme.offset));
             spans.push(Span::raw("  "));
-            spans.extend(l.hex_spans.clone());
+            spans.extend(l.hex_spans);
             spans.push(Span::raw("  |"));
-            spans.extend(l.ascii_spans.clone());
+            spans.extend(l.ascii_spans);
             spans.push(Span::raw("|"));
             Line::from(spans)
         })
@@ -279,3 +1014,151 @@ fn draw_side<B: Backend>(
     let paragraph = Paragraph::new(body).block(block);
     f.render_widget(paragraph, area);
 }
+
+/// Draws the difference list over `area`, keeping the highlighted entry in view.
+fn draw_diff_list(f: &mut Frame, area: Rect, app: &App) {
+    let area = Rect {
+        x: area.x + area.width / 8,
+        y: area.y + 1,
+        width: area.width - area.width / 4,
+        height: area.height.saturating_sub(2),
+    };
+    let total_bytes: usize = app.diff_runs.iter().map(|r| r.len()).sum();
+    let title = format!(" {} differing byte(s) in {} run(s) — Enter jumps, Esc closes ", total_bytes, app.diff_runs.len());
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(title, app.theme.title));
+
+    let rows = area.height.saturating_sub(2) as usize;
+    let start = app.diff_selected.saturating_sub(rows / 2).min(app.diff_runs.len().saturating_sub(rows));
+    let body: Vec<Line> = (start..cmp::min(start + rows, app.diff_runs.len()))
+        .map(|i| {
+            let style = if i == app.diff_selected { app.theme.menu_active } else { app.theme.byte };
+            Line::from(Span::styled(app.diff_label(i), style))
+        })
+        .collect();
+    let body = if body.is_empty() { vec![Line::from("Files are identical.")] } else { body };
+
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(body).block(block), area);
+}
+
+/// Background shade for a cycle with `set` of `NUM_BITS` bits high
+/// (xterm greyscale ramp, dark to light).
+fn heat_color(set: usize) -> Color {
+    Color::Indexed(232 + (set * 23 / NUM_BITS) as u8)
+}
+
+/// Draws the decoded pattern, one cycle per row with bit 0 on the left.
+fn draw_pattern(
+    f: &mut Frame,
+    area: Rect,
+    header: Option<&PatternFileData>,
+    scroll: usize,
+    heat: bool,
+    theme: &Theme,
+) {
+    let title = Span::styled(if heat { " Pattern (heat) " } else { " Pattern " }, theme.title);
+    let block = Block::default().borders(Borders::ALL).title(title);
+
+    let Some(data) = header else {
+        let msg = Paragraph::new("File A did not parse as a PCF").block(block);
+        f.render_widget(msg, area);
+        return;
+    };
+
+    let max_rows = area.height.saturating_sub(2) as usize;
+    let cycles = data.cols();
+    let start = cmp::min(scroll, cycles.saturating_sub(max_rows));
+
+    let body: Vec<Line> = (start..cmp::min(start + max_rows, cycles))
+        .filter_map(|cycle| data.column(cycle).map(|col| (cycle, col)))
+        .map(|(cycle, col)| {
+            let set = col.iter().filter(|&&v| v != 0).count();
+            let mut spans = Vec::with_capacity(NUM_BITS + 3);
+            spans.push(Span::styled(format!("{:>8}", cycle), theme.offset));
+            spans.push(Span::raw("  "));
+            for &v in &col {
+                let (chr, style) = if v != 0 { ('1', theme.bit_set) } else { ('·', theme.byte) };
+                let style = if heat { style.bg(heat_color(set)) } else { style };
+                spans.push(Span::styled(chr.to_string(), style));
+            }
+            spans.push(Span::styled(format!("  {:>2}", set), theme.offset));
+            Line::from(spans)
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(body).block(block), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_history_undoes_and_redoes_in_order() {
+        let mut buf = vec![0u8; 4];
+        let mut history = EditHistory::default();
+        assert!(history.apply(&mut buf, 1, 0xAA));
+        assert!(history.apply(&mut buf, 1, 0xBB));
+        assert!(history.apply(&mut buf, 3, 0x11));
+        assert!(!history.apply(&mut buf, 3, 0x11)); // unchanged
+        assert!(!history.apply(&mut buf, 4, 0x11)); // past the end
+        assert_eq!(buf, [0, 0xBB, 0, 0x11]);
+
+        assert_eq!(history.undo(&mut buf), Some(3));
+        assert_eq!(history.undo(&mut buf), Some(1));
+        assert_eq!(buf, [0, 0xAA, 0, 0]);
+        assert_eq!(history.redo(&mut buf), Some(1));
+        assert_eq!(buf, [0, 0xBB, 0, 0]);
+
+        // a new edit drops what is left to redo
+        assert!(history.apply(&mut buf, 0, 0x22));
+        assert_eq!(history.redo(&mut buf), None);
+        assert_eq!(history.undo(&mut buf), Some(0));
+        assert_eq!(history.undo(&mut buf), Some(1));
+        assert_eq!(history.undo(&mut buf), Some(1));
+        assert_eq!(history.undo(&mut buf), None);
+        assert_eq!(buf, [0; 4]);
+    }
+
+    #[test]
+    fn diff_runs_update_in_place() {
+        let b = [0u8; 12];
+        let mut a = [0u8; 12];
+        let mut runs = Vec::new();
+        // split, trim, join and drop runs one byte at a time
+        for (offset, value) in [(3, 1), (5, 1), (4, 1), (9, 1), (4, 0), (3, 0), (5, 0), (10, 1), (11, 1), (9, 0), (10, 0)] {
+            a[offset] = value;
+            update_diff_runs(&mut runs, offset, a[offset] != b[offset]);
+            assert_eq!(runs, diff_runs(&a, &b), "after setting byte {} to {}", offset, value);
+        }
+    }
+
+    #[test]
+    fn pattern_edits_match_a_fresh_decode() {
+        for encoding in [PatternEncoding::BytesPerBit, PatternEncoding::PackedBits] {
+            let mut data = PatternFileData::builder().encoding(encoding).build().unwrap();
+            data.trailer = b"END".to_vec();
+            let mut bytes = pcf_parser::encode_to_vec(&data).unwrap();
+            let opts = DecodeOptions { total_len: Some(bytes.len()), encoding: Some(encoding), keep_trailer: true, ..Default::default() };
+
+            let header_size = PcfLayout::default().header_size();
+            for (offset, byte) in [(header_size, 1), (header_size + 40, 0xA5), (bytes.len() - 2, b'X')] {
+                bytes[offset] = byte;
+                set_pattern_byte(&mut data, offset - header_size, byte);
+                assert_eq!(data, decode_with(&mut &bytes[..], &opts).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn edit_history_forgets_the_oldest_edits() {
+        let mut buf = vec![0u8; 1];
+        let mut history = EditHistory::default();
+        for i in 0..UNDO_LIMIT + 5 {
+            history.apply(&mut buf, 0, (i % 2 + 1) as u8);
+        }
+        let undone = std::iter::from_fn(|| history.undo(&mut buf)).count();
+        assert_eq!(undone, UNDO_LIMIT);
+        assert_ne!(buf[0], 0);
+    }
+}
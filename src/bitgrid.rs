@@ -0,0 +1,254 @@
+//! Plain-text "bit grid" form of a pattern, for hand-authoring small files.
+//!
+//! ```text
+//! # comments run from `#` to the end of the line
+//! version=v1.0
+//! compiled_flag=True
+//! cycle_time=20,4,16,,,,,,4000
+//! 100000000000000001
+//! 010000000000000000
+//! ```
+//!
+//! Each `key=value` line sets one header field; list fields take
+//! comma-separated values in memory order (`clk_sources` lists slots 1–64).
+//! Omitted fields keep their defaults. Every other non-blank line is one
+//! cycle: 18 `0`/`1` characters, bit 0 first. A grid with fewer than
+//! [`PATTERN_PAD_COLS`] cycles is padded with all-zero cycles.
+//!
+//! [`write_bitgrid`] emits every header field followed by every column, so a
+//! file survives the trip through text unchanged.
+
+use std::fmt::Display;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use crate::layout::{PatternEncoding, NUM_BITS, NUM_CLK_SOURCES, PATTERN_PAD_COLS};
+use crate::pattern::PatternFileData;
+
+fn invalid(line: usize, msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+}
+
+fn parse_list<T: std::str::FromStr + Default, const N: usize>(key: &str, value: &str) -> Result<[T; N], String> {
+    let items: Vec<&str> = value.split(',').map(str::trim).collect();
+    if items.len() != N {
+        return Err(format!("`{}` needs {} comma-separated values (got {})", key, N, items.len()));
+    }
+    let mut out: [T; N] = std::array::from_fn(|_| T::default());
+    for (slot, item) in out.iter_mut().zip(items) {
+        *slot = item.parse().map_err(|_| format!("`{}` is not a valid {} entry", item, key))?;
+    }
+    Ok(out)
+}
+
+fn parse_int(key: &str, value: &str) -> Result<i32, String> {
+    value.parse().map_err(|_| format!("`{}` is not a valid {}", value, key))
+}
+
+fn set_field(data: &mut PatternFileData, key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "compiled_flag" => {
+            data.compiled_flag = value
+                .to_lowercase()
+                .parse()
+                .map_err(|_| format!("`{}` is not True/False", value))?;
+        }
+        "version" => data.version = value.to_string(),
+        "source_combo_index" => data.source_combo_index = parse_int(key, value)?,
+        "pclk_source_indices" => data.pclk_source_indices = parse_list(key, value)?,
+        "vtime_reqd" => data.vtime_reqd = parse_list(key, value)?,
+        "cycle_time" => data.cycle_time = parse_list(key, value)?,
+        "pulse_time" => data.pulse_time = parse_list(key, value)?,
+        "clk_sources" => {
+            let slots: [String; NUM_CLK_SOURCES] = parse_list(key, value)?;
+            data.clk_sources = std::iter::once(String::new()).chain(slots).collect();
+        }
+        "start_addrs" => data.start_addrs = parse_list(key, value)?,
+        "end_addrs" => data.end_addrs = parse_list(key, value)?,
+        "loop_counts" => data.loop_counts = parse_list(key, value)?,
+        "encoding" => {
+            data.encoding = match value.to_ascii_lowercase().as_str() {
+                "bytes" => PatternEncoding::BytesPerBit,
+                "packed" => PatternEncoding::PackedBits,
+                _ => return Err(format!("encoding must be `bytes` or `packed` (got `{}`)", value)),
+            };
+        }
+        _ => return Err(format!("unknown header key `{}`", key)),
+    }
+    Ok(())
+}
+
+fn parse_row(text: &str) -> Result<[u8; NUM_BITS], String> {
+    if text.chars().count() != NUM_BITS {
+        return Err(format!("expected {} bits (got {} characters in `{}`)", NUM_BITS, text.chars().count(), text));
+    }
+    let mut column = [0u8; NUM_BITS];
+    for (slot, c) in column.iter_mut().zip(text.chars()) {
+        *slot = match c {
+            '0' => 0,
+            '1' => 1,
+            _ => return Err(format!("`{}` is not a 0/1 character", c)),
+        };
+    }
+    Ok(column)
+}
+
+/// Reads a bit grid. Errors name the 1-based line that could not be parsed.
+pub fn read_bitgrid<R: Read>(r: R) -> io::Result<PatternFileData> {
+    let mut data = PatternFileData::new();
+    let mut columns: Vec<[u8; NUM_BITS]> = Vec::new();
+
+    for (i, line) in BufReader::new(r).lines().enumerate() {
+        let line = line?;
+        let text = line.split_once('#').map_or(line.as_str(), |(before, _)| before).trim();
+        if text.is_empty() {
+            continue;
+        }
+        let result = match text.split_once('=') {
+            Some((key, value)) => set_field(&mut data, key.trim(), value.trim()),
+            None => parse_row(text).map(|col| columns.push(col)),
+        };
+        result.map_err(|msg| invalid(i + 1, msg))?;
+    }
+
+    while columns.len() < PATTERN_PAD_COLS {
+        columns.push([0; NUM_BITS]);
+    }
+    data.set_columns(&columns);
+    Ok(data)
+}
+
+/// Text that [`read_bitgrid`] would read back differently: a comment marker,
+/// a line break, (inside a list) the separator, or leading or trailing
+/// whitespace, which the reader trims.
+fn check_text(key: &str, value: &str, list: bool) -> io::Result<()> {
+    if value.contains(['#', '\n', '\r']) || (list && value.contains(',')) || value.trim() != value {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} value `{}` cannot be written as bit-grid text", key, value),
+        ));
+    }
+    Ok(())
+}
+
+fn write_list<W: Write, T: Display>(w: &mut W, key: &str, items: &[T]) -> io::Result<()> {
+    let items: Vec<String> = items.iter().map(ToString::to_string).collect();
+    for item in &items {
+        check_text(key, item, true)?;
+    }
+    writeln!(w, "{}={}", key, items.join(","))
+}
+
+/// Writes `data` as a bit grid that [`read_bitgrid`] reads back unchanged.
+///
+/// Fails with `InvalidData` if a pattern byte is not 0 or 1, or a text field
+/// holds a character the format reserves or starts or ends with whitespace.
+pub fn write_bitgrid<W: Write>(data: &PatternFileData, w: &mut W) -> io::Result<()> {
+    check_text("version", &data.version, false)?;
+    writeln!(w, "compiled_flag={}", if data.compiled_flag { "True" } else { "False" })?;
+    writeln!(w, "version={}", data.version)?;
+    writeln!(w, "source_combo_index={}", data.source_combo_index)?;
+    write_list(w, "pclk_source_indices", &data.pclk_source_indices)?;
+    write_list(w, "vtime_reqd", &data.vtime_reqd)?;
+    write_list(w, "cycle_time", &data.cycle_time)?;
+    write_list(w, "pulse_time", &data.pulse_time)?;
+    write_list(w, "clk_sources", data.clk_sources.get(1..).unwrap_or_default())?;
+    write_list(w, "start_addrs", &data.start_addrs)?;
+    write_list(w, "end_addrs", &data.end_addrs)?;
+    write_list(w, "loop_counts", &data.loop_counts)?;
+    if !data.encoding.is_bytes_per_bit() {
+        writeln!(w, "encoding=packed")?;
+    }
+
+    let mut line = String::with_capacity(NUM_BITS + 1);
+    for (cycle, col) in data.columns().enumerate() {
+        line.clear();
+        for &v in &col {
+            line.push(match v {
+                0 => '0',
+                1 => '1',
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("cycle {} holds byte {:#04X}, which is not a 0/1 bit", cycle, v),
+                    ));
+                }
+            });
+        }
+        line.push('\n');
+        w.write_all(line.as_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_header_and_rows() {
+        let text = format!("\
+# hand-written
+version=v1.0
+compiled_flag=True
+cycle_time=20,4,,,,,,,4000   # trailing comment
+clk_sources=MCLK{}
+
+100000000000000001
+010000000000000000
+", ",".repeat(NUM_CLK_SOURCES - 1));
+        let data = read_bitgrid(text.as_bytes()).unwrap();
+        assert!(data.compiled_flag);
+        assert_eq!(data.version, "v1.0");
+        assert_eq!(data.cycle_time[8], "4000");
+        assert_eq!(data.cycle_time[2], "");
+        assert_eq!(data.clk_slot("MCLK"), Some(1));
+        assert_eq!(data.cols(), PATTERN_PAD_COLS);
+        assert_eq!(data.pattern_file_length, 0);
+        assert_eq!(data.column(0).unwrap()[17], 1);
+        assert_eq!(data.column(1).unwrap()[1], 1);
+        assert_eq!(data.column(2), Some([0; NUM_BITS]));
+    }
+
+    #[test]
+    fn reports_offending_line() {
+        let err = read_bitgrid("version=x\n\n10101\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 3: expected 18 bits"));
+
+        let err = read_bitgrid("colour=red\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("line 1: unknown header key `colour`"));
+
+        let err = read_bitgrid("start_addrs=1,2\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("needs 8 comma-separated values"));
+    }
+
+    #[test]
+    fn writer_rejects_what_it_cannot_represent() {
+        let mut data = PatternFileData::new();
+        data.fill_pattern(2);
+        let err = write_bitgrid(&data, &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("cycle 0 holds byte 0x02"));
+
+        data.clear_pattern();
+        data.clk_sources[3] = "A,B".into();
+        assert!(write_bitgrid(&data, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn round_trips_or_refuses_space_padded_fields() {
+        let mut data = PatternFileData::builder().version("v1.0").build().unwrap();
+        data.cycle_time[0] = "20".into();
+        data.pattern_data[4][7] = 1;
+        let mut text = Vec::new();
+        write_bitgrid(&data, &mut text).unwrap();
+        assert_eq!(read_bitgrid(&text[..]).unwrap(), data);
+
+        for (version, cycle_time) in [(" v1.0", "20"), ("v1.0", "20 ")] {
+            let mut padded = data.clone();
+            padded.version = version.into();
+            padded.cycle_time[0] = cycle_time.into();
+            let err = write_bitgrid(&padded, &mut Vec::new()).unwrap_err();
+            assert!(err.to_string().contains("cannot be written as bit-grid text"), "{}", err);
+        }
+    }
+}
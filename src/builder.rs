@@ -0,0 +1,196 @@
+//! Step-by-step construction of a [`PatternFileData`].
+//!
+//! [`PatternFileData`] is `#[non_exhaustive]`, so code outside this crate
+//! cannot build it with a struct literal. [`PatternFileData::new`] gives an
+//! empty record; [`PatternFileData::builder`] fills one in field by field and
+//! checks the result:
+//!
+//! ```
+//! use pcf_parser::PatternFileData;
+//!
+//! let data = PatternFileData::builder()
+//!     .version("V3")
+//!     .clk_source(1, "MCLK")
+//!     .loop_region(0, 2, 9, 4)
+//!     .columns(&[[0; 18]; 32])
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(data.cols(), 32);
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::layout::{PatternEncoding, NUM_BITS, NUM_CLK_SOURCES, NUM_LOOPS, NUM_TIMINGS};
+use crate::pattern::PatternFileData;
+
+/// Builder returned by [`PatternFileData::builder`]. Unset header fields keep
+/// their defaults; an unset pattern is all zero, sized to
+/// `pattern_file_length`.
+#[derive(Debug, Clone)]
+pub struct PatternFileDataBuilder {
+    data: PatternFileData,
+    pattern_set: bool,
+}
+
+impl PatternFileData {
+    /// Starts from [`PatternFileData::new`].
+    pub fn builder() -> PatternFileDataBuilder {
+        PatternFileDataBuilder { data: PatternFileData::new(), pattern_set: false }
+    }
+}
+
+impl PatternFileDataBuilder {
+    pub fn compiled_flag(mut self, flag: bool) -> Self {
+        self.data.compiled_flag = flag;
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.data.version = version.into();
+        self
+    }
+
+    pub fn source_combo_index(mut self, index: i32) -> Self {
+        self.data.source_combo_index = index;
+        self
+    }
+
+    pub fn pclk_source_indices(mut self, indices: [i32; NUM_LOOPS]) -> Self {
+        self.data.pclk_source_indices = indices;
+        self
+    }
+
+    pub fn vtime_reqd(mut self, values: [String; NUM_TIMINGS]) -> Self {
+        self.data.vtime_reqd = values;
+        self
+    }
+
+    pub fn cycle_time(mut self, values: [String; NUM_TIMINGS]) -> Self {
+        self.data.cycle_time = values;
+        self
+    }
+
+    pub fn pulse_time(mut self, values: [String; NUM_TIMINGS]) -> Self {
+        self.data.pulse_time = values;
+        self
+    }
+
+    /// Names clock-source `slot` (1–64).
+    ///
+    /// # Panics
+    /// If `slot` is 0 or above 64.
+    pub fn clk_source(mut self, slot: usize, name: impl Into<String>) -> Self {
+        assert!((1..=NUM_CLK_SOURCES).contains(&slot), "clock slot {} is out of range", slot);
+        self.data.clk_sources[slot] = name.into();
+        self
+    }
+
+    /// Replaces every clock source; index 0 is the unused slot.
+    pub fn clk_sources(mut self, sources: Vec<String>) -> Self {
+        self.data.clk_sources = sources;
+        self
+    }
+
+    /// Sets loop `i`'s inclusive cycle range and repeat count.
+    ///
+    /// # Panics
+    /// If `i` is not below [`NUM_LOOPS`].
+    pub fn loop_region(mut self, i: usize, start: i32, end: i32, count: i32) -> Self {
+        self.data.start_addrs[i] = start;
+        self.data.end_addrs[i] = end;
+        self.data.loop_counts[i] = count;
+        self
+    }
+
+    pub fn start_addrs(mut self, addrs: [i32; NUM_LOOPS]) -> Self {
+        self.data.start_addrs = addrs;
+        self
+    }
+
+    pub fn end_addrs(mut self, addrs: [i32; NUM_LOOPS]) -> Self {
+        self.data.end_addrs = addrs;
+        self
+    }
+
+    pub fn loop_counts(mut self, counts: [i32; NUM_LOOPS]) -> Self {
+        self.data.loop_counts = counts;
+        self
+    }
+
+    /// Sets the stored length; without [`columns`](Self::columns) or
+    /// [`pattern_data`](Self::pattern_data) the pattern is zero-filled to match.
+    pub fn pattern_file_length(mut self, length: i32) -> Self {
+        self.data.pattern_file_length = length;
+        self
+    }
+
+    /// Sets the pattern from columns and `pattern_file_length` to match.
+    pub fn columns(mut self, columns: &[[u8; NUM_BITS]]) -> Self {
+        self.data.set_columns(columns);
+        self.pattern_set = true;
+        self
+    }
+
+    /// Sets the raw `[bit][col]` pattern; `pattern_file_length` must agree.
+    pub fn pattern_data(mut self, pattern: Vec<Vec<u8>>) -> Self {
+        self.data.pattern_data = pattern;
+        self.pattern_set = true;
+        self
+    }
+
+    pub fn encoding(mut self, encoding: PatternEncoding) -> Self {
+        self.data.encoding = encoding;
+        self
+    }
+
+    pub fn trailer(mut self, trailer: Vec<u8>) -> Self {
+        self.data.trailer = trailer;
+        self
+    }
+
+    /// Finishes the record, failing if it could not be written as given (see
+    /// [`PatternFileData::check_shape`]).
+    pub fn build(mut self) -> Result<PatternFileData, String> {
+        if !self.pattern_set {
+            self.data.clear_pattern();
+        }
+        self.data.check_shape()?;
+        Ok(self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_a_writable_record() {
+        let data = PatternFileData::builder()
+            .compiled_flag(true)
+            .version("V3")
+            .clk_source(64, "MCLK")
+            .loop_region(2, 4, 8, 3)
+            .pattern_file_length(5)
+            .build()
+            .unwrap();
+        assert_eq!(data.version(), "V3");
+        assert_eq!(data.clk_sources()[64], "MCLK");
+        assert_eq!((data.start_addrs()[2], data.end_addrs()[2], data.loop_counts()[2]), (4, 8, 3));
+        assert_eq!(data.cols(), 25);
+        assert!(data.pattern_data().iter().all(|row| row.len() == 25));
+    }
+
+    #[test]
+    fn build_rejects_inconsistent_shapes() {
+        assert!(PatternFileData::builder().columns(&[[0; NUM_BITS]; 3]).build().is_err());
+        assert!(PatternFileData::builder().clk_sources(Vec::new()).build().is_err());
+        assert!(PatternFileData::builder().version("far too long a version").build().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn clock_slot_zero_panics() {
+        let _ = PatternFileData::builder().clk_source(0, "X");
+    }
+}
@@ -0,0 +1,881 @@
+//! Encoding and decoding of the PCF byte format.
+//!
+//! Everything here needs only `core` and `alloc`, so the format can be used
+//! from `no_std` firmware tools. Byte I/O goes through the minimal
+//! [`ByteRead`]/[`ByteWrite`] traits; with the `std` feature they are
+//! implemented for every `std::io::Read`/`Write`, otherwise for `&[u8]` and
+//! `Vec<u8>`.
+//!
+//! Header text is left-aligned in its field. On read, trailing spaces, other
+//! whitespace and NUL bytes are all treated as padding and dropped (some
+//! generators pad with NUL); on write every field is padded with
+//! [`PAD_BYTE`], so a NUL-padded file is rewritten space-padded.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::layout::{FieldDelimiter, PatternEncoding, PcfLayout, NUM_BITS, PATTERN_PAD_COLS};
+use crate::pattern::PatternFileData;
+
+/// Byte used to fill header fields on write.
+pub const PAD_BYTE: u8 = b' ';
+
+/// `text` without trailing field padding: whitespace and NUL bytes.
+pub(crate) fn trim_padding(text: &str) -> &str {
+    text.trim_end_matches(|c: char| c == '\0' || c.is_whitespace())
+}
+
+/// Error produced while encoding or decoding.
+#[derive(Debug)]
+pub enum CodecError {
+    /// The input ended before a complete record was read.
+    UnexpectedEof,
+    /// A field was malformed (strict decoding only).
+    InvalidData(String),
+    /// The underlying reader or writer failed.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::UnexpectedEof => write!(f, "unexpected end of input"),
+            CodecError::InvalidData(msg) => write!(f, "{}", msg),
+            #[cfg(feature = "std")]
+            CodecError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CodecError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for CodecError {
+    fn from(e: std::io::Error) -> Self {
+        CodecError::Io(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<CodecError> for std::io::Error {
+    fn from(e: CodecError) -> Self {
+        use std::io::{Error, ErrorKind};
+        match e {
+            CodecError::UnexpectedEof => Error::new(ErrorKind::UnexpectedEof, "unexpected end of input"),
+            CodecError::InvalidData(msg) => Error::new(ErrorKind::InvalidData, msg),
+            CodecError::Io(e) => e,
+        }
+    }
+}
+
+/// Source of bytes for [`decode`].
+pub trait ByteRead {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), CodecError>;
+}
+
+/// Sink of bytes for [`encode`].
+pub trait ByteWrite {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), CodecError>;
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + ?Sized> ByteRead for R {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), CodecError> {
+        std::io::Read::read_exact(self, buf).map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => CodecError::UnexpectedEof,
+            _ => CodecError::Io(e),
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write + ?Sized> ByteWrite for W {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), CodecError> {
+        std::io::Write::write_all(self, buf).map_err(CodecError::Io)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl ByteRead for &[u8] {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), CodecError> {
+        if self.len() < buf.len() {
+            return Err(CodecError::UnexpectedEof);
+        }
+        let (head, tail) = self.split_at(buf.len());
+        buf.copy_from_slice(head);
+        *self = tail;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl ByteWrite for Vec<u8> {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), CodecError> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
+/// Reads header fields while tracking how many bytes have been consumed.
+struct FieldReader<'r, R: ?Sized> {
+    inner: &'r mut R,
+    offset: usize,
+    strict: bool,
+    numeric_width: usize,
+    delimiter: FieldDelimiter,
+}
+
+impl<R: ByteRead + ?Sized> FieldReader<'_, R> {
+    // Read a fixed length in as a string, or up to the next newline in a
+    // newline-delimited header.
+    fn read_fixed(&mut self, len: usize) -> Result<String, CodecError> {
+        let buf = match self.delimiter {
+            FieldDelimiter::FixedWidth => {
+                let mut buf = vec![0u8; len];
+                self.inner.read_exact(&mut buf)?;
+                self.offset += len;
+                buf
+            }
+            FieldDelimiter::Newline => {
+                let mut buf = Vec::with_capacity(len);
+                loop {
+                    match self.read_u8()? {
+                        b'\n' => break,
+                        b => buf.push(b),
+                    }
+                }
+                if buf.last() == Some(&b'\r') {
+                    buf.pop();
+                }
+                buf
+            }
+        };
+        Ok(trim_padding(&String::from_utf8_lossy(&buf)).to_string())
+    }
+
+    // Read a fixed-width integer; blank or garbage is 0 unless strict.
+    fn read_int(&mut self, name: &str) -> Result<i32, CodecError> {
+        let start = self.offset;
+        let text = self.read_fixed(self.numeric_width)?;
+        match text.trim().parse() {
+            Ok(v) => Ok(v),
+            Err(_) if self.strict => Err(CodecError::InvalidData(format!(
+                "{} at byte {}: `{}` is not an integer", name, start, text
+            ))),
+            Err(_) => Ok(0),
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, CodecError> {
+        let mut buf = [0u8; 1];
+        self.inner.read_exact(&mut buf)?;
+        self.offset += 1;
+        Ok(buf[0])
+    }
+}
+
+/// Knobs for [`decode_with`].
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    /// Turn lenient defaults into errors.
+    pub strict: bool,
+    /// Total input size in bytes, when known up front. Used to cross-check
+    /// the header's `pattern_file_length` against the bytes actually present.
+    pub total_len: Option<usize>,
+    /// Encoding of the pattern region. `None` detects it from `total_len`
+    /// (packed only when the size matches the declared length exactly as
+    /// packed columns), falling back to [`PatternEncoding::BytesPerBit`].
+    pub encoding: Option<PatternEncoding>,
+    /// Refuse to decode a pattern whose in-memory form (one byte per bit)
+    /// would exceed this many bytes. `None` allows any size.
+    pub max_pattern_bytes: Option<usize>,
+    /// Header field widths and delimiter; the pattern encoding comes from
+    /// `encoding`.
+    pub layout: PcfLayout,
+    /// With `total_len`, read any bytes past the declared pattern region
+    /// into [`PatternFileData::trailer`] instead of treating them as extra
+    /// pattern columns (lenient) or an error (strict). Inputs without a
+    /// trailer decode the same either way.
+    pub keep_trailer: bool,
+}
+
+/// Pattern size cap used by the `std` parse functions: 1 GiB, far beyond any
+/// real file but small enough that a corrupt length cannot exhaust memory.
+pub const DEFAULT_MAX_PATTERN_BYTES: usize = 1 << 30;
+
+/// Decodes one record. In strict mode malformed numeric fields, an
+/// unrecognised compiled flag, or a header that does not end at
+/// the layout's [`header_size`](PcfLayout::header_size) are errors instead of being defaulted.
+pub fn decode<R: ByteRead + ?Sized>(reader: &mut R, strict: bool) -> Result<PatternFileData, CodecError> {
+    decode_with(reader, &DecodeOptions { strict, ..Default::default() })
+}
+
+/// Decodes one record with explicit [`DecodeOptions`].
+///
+/// When `total_len` is given and disagrees with the header's
+/// `pattern_file_length`, strict mode fails with both values; lenient mode
+/// trusts the size of the input and adjusts `pattern_file_length` to match.
+pub fn decode_with<R: ByteRead + ?Sized>(reader: &mut R, opts: &DecodeOptions) -> Result<PatternFileData, CodecError> {
+    let (mut data, pattern) = decode_into::<R, Vec<Vec<u8>>>(reader, opts)?;
+    data.pattern_data = pattern;
+    Ok(data)
+}
+
+/// Where [`decode_into`] puts pattern values and [`encode_from`] reads them:
+/// `pattern_data`'s byte-per-bit rows, or a
+/// [`PackedPattern`](crate::packed::PackedPattern) with the `packed` feature.
+pub(crate) trait PatternStore: Sized {
+    /// An all-zero store of `cols` columns.
+    fn zeroed(cols: usize) -> Self;
+
+    /// Heap bytes a store of `cols` columns takes, checked against
+    /// [`DecodeOptions::max_pattern_bytes`] before allocating.
+    fn bytes_for(cols: usize) -> usize;
+
+    /// Stores one value read from the file.
+    fn put(&mut self, bit: usize, col: usize, value: u8) -> Result<(), CodecError>;
+
+    /// The value to write for `bit` in column `col`.
+    fn value(&self, bit: usize, col: usize) -> u8;
+
+    /// Checks that the store holds [`NUM_BITS`] rows of `cols` values, so
+    /// [`value`](Self::value) is in range for every column written.
+    fn check_cols(&self, cols: usize) -> Result<(), String>;
+}
+
+impl PatternStore for Vec<Vec<u8>> {
+    fn zeroed(cols: usize) -> Self {
+        vec![vec![0u8; cols]; NUM_BITS]
+    }
+
+    fn bytes_for(cols: usize) -> usize {
+        cols.saturating_mul(NUM_BITS)
+    }
+
+    fn put(&mut self, bit: usize, col: usize, value: u8) -> Result<(), CodecError> {
+        self[bit][col] = value;
+        Ok(())
+    }
+
+    fn value(&self, bit: usize, col: usize) -> u8 {
+        self[bit][col]
+    }
+
+    fn check_cols(&self, cols: usize) -> Result<(), String> {
+        if self.len() != NUM_BITS {
+            return Err(format!("pattern_data must have {} rows (found {})", NUM_BITS, self.len()));
+        }
+        if let Some((bit, row)) = self.iter().enumerate().find(|(_, row)| row.len() != cols) {
+            return Err(format!("pattern_data[{}] has {} columns, expected {}", bit, row.len(), cols));
+        }
+        Ok(())
+    }
+}
+
+/// [`decode_with`] into any [`PatternStore`]; the returned header's
+/// `pattern_data` is left empty.
+pub(crate) fn decode_into<R: ByteRead + ?Sized, S: PatternStore>(reader: &mut R, opts: &DecodeOptions) -> Result<(PatternFileData, S), CodecError> {
+    let strict = opts.strict;
+    let string_width = opts.layout.string_width;
+    let mut reader = FieldReader {
+        inner: reader,
+        offset: 0,
+        strict,
+        numeric_width: opts.layout.numeric_width,
+        delimiter: opts.layout.delimiter,
+    };
+
+    // The first record is "{flag} {version}". Any run of leading/separating
+    // spaces is accepted; on write it is re-emitted in the canonical form
+    // `format_flag_version` produces, so the first round trip of a
+    // non-canonical file may change these bytes.
+    let compiled: String = reader.read_fixed(string_width)?;
+    let compiled = compiled.trim_start();
+    let (flag_text, version_text) = compiled.split_once(' ').unwrap_or((compiled, ""));
+    let flag_text = if flag_text.is_empty() { "False" } else { flag_text };
+
+    let flag: bool = match flag_text.to_lowercase().parse() {
+        Ok(v) => v,
+        Err(_) if strict => {
+            return Err(CodecError::InvalidData(format!(
+                "compiled flag at byte 0: `{}` is not True/False", flag_text
+            )));
+        }
+        Err(_) => false,
+    };
+
+    let version: String = version_text.trim().to_string();
+
+    let source_combo_index: i32 = reader.read_int("source_combo_index")?;
+
+    let mut pclk_source_indices: [i32; 8] = [0; 8];
+    for idx in pclk_source_indices.iter_mut() {
+        *idx = reader.read_int("pclk_source_indices")?;
+    }
+
+    let mut vtime_reqd: [String; 9] = Default::default();
+    vtime_reqd[8] = reader.read_fixed(string_width)?;
+    for v in vtime_reqd.iter_mut().take(8) {
+        *v = reader.read_fixed(string_width)?;
+    }
+
+    let mut cycle_time: [String; 9] = Default::default();
+    cycle_time[8] = reader.read_fixed(string_width)?;
+    for v in cycle_time.iter_mut().take(8) {
+        *v = reader.read_fixed(string_width)?;
+    }
+
+    let mut pulse_time: [String; 9] = Default::default();
+    pulse_time[8] = reader.read_fixed(string_width)?;
+    for v in pulse_time.iter_mut().take(8) {
+        *v = reader.read_fixed(string_width)?;
+    }
+
+    let mut clk_sources = vec![String::new(); 65];
+    for src in clk_sources.iter_mut().skip(1) {
+        *src = reader.read_fixed(string_width)?;
+    }
+
+    let mut start_addrs: [i32; 8] = [0; 8];
+    let mut end_addrs: [i32; 8] = [0; 8];
+    let mut loop_counts: [i32; 8] = [0; 8];
+
+    for i in 0..8 {
+        start_addrs[i] = reader.read_int("start_addrs")?;
+        end_addrs[i] = reader.read_int("end_addrs")?;
+        loop_counts[i] = reader.read_int("loop_counts")?;
+    }
+
+    let mut pattern_file_length = reader.read_int("pattern_file_length")?;
+
+    // a newline-delimited header is as long as its values make it
+    let header_size = match opts.layout.delimiter {
+        FieldDelimiter::FixedWidth => opts.layout.header_size(),
+        FieldDelimiter::Newline => reader.offset,
+    };
+    if strict && reader.offset != header_size {
+        return Err(CodecError::InvalidData(format!(
+            "header ended at byte {} but the pattern region should start at {} ({:+} bytes)",
+            reader.offset,
+            header_size,
+            reader.offset as isize - header_size as isize
+        )));
+    }
+
+    let declared = pattern_file_length as i64 + PATTERN_PAD_COLS as i64;
+    let encoding = opts.encoding.unwrap_or_else(|| match opts.total_len {
+        Some(total) => {
+            let region = total.saturating_sub(header_size) as i64;
+            let packed = PatternEncoding::PackedBits.column_bytes(NUM_BITS) as i64;
+            if region == declared * packed && region != declared * NUM_BITS as i64 {
+                PatternEncoding::PackedBits
+            } else {
+                PatternEncoding::BytesPerBit
+            }
+        }
+        None => PatternEncoding::BytesPerBit,
+    });
+    let column_bytes = encoding.column_bytes(NUM_BITS);
+
+    let mut trailer_len = 0;
+    if let Some(total) = opts.total_len {
+        let region = total.saturating_sub(header_size);
+        let file_cols = region / column_bytes;
+        if opts.keep_trailer && declared >= 0 && declared <= file_cols as i64 {
+            trailer_len = region - declared as usize * column_bytes;
+        } else if declared != file_cols as i64 {
+            if strict {
+                return Err(CodecError::InvalidData(format!(
+                    "pattern_file_length {} implies {} columns but the input holds {}",
+                    pattern_file_length, declared, file_cols
+                )));
+            }
+            pattern_file_length = file_cols as i32 - PATTERN_PAD_COLS as i32;
+        }
+    }
+
+    let cols = pattern_file_length as i64 + PATTERN_PAD_COLS as i64;
+    if cols < 0 {
+        return Err(CodecError::InvalidData(format!(
+            "pattern_file_length {} is below -{}", pattern_file_length, PATTERN_PAD_COLS
+        )));
+    }
+    let cols = cols as usize;
+    if let Some(max) = opts.max_pattern_bytes {
+        let needed = S::bytes_for(cols);
+        if needed > max {
+            return Err(CodecError::InvalidData(format!(
+                "pattern of {} columns needs {} bytes, above the limit of {}", cols, needed, max
+            )));
+        }
+    }
+
+    let mut pattern = S::zeroed(cols);
+
+    match encoding {
+        PatternEncoding::BytesPerBit => {
+            for col in 0..cols {
+                for bit in 0..NUM_BITS {
+                    pattern.put(bit, col, reader.read_u8()?)?;
+                }
+            }
+        }
+        PatternEncoding::PackedBits => {
+            let mut packed = vec![0u8; column_bytes];
+            for col in 0..cols {
+                for byte in packed.iter_mut() {
+                    *byte = reader.read_u8()?;
+                }
+                for bit in 0..NUM_BITS {
+                    pattern.put(bit, col, (packed[bit / 8] >> (bit % 8)) & 1)?;
+                }
+            }
+        }
+    }
+
+    let mut trailer = vec![0u8; trailer_len];
+    reader.inner.read_exact(&mut trailer)?;
+
+    let data = PatternFileData{
+        compiled_flag: flag,
+        version,
+        source_combo_index,
+        pclk_source_indices,
+        vtime_reqd,
+        cycle_time,
+        pulse_time,
+        clk_sources,
+        start_addrs,
+        end_addrs,
+        loop_counts,
+        pattern_file_length,
+        pattern_data: Vec::new(),
+        encoding,
+        trailer,
+    };
+    Ok((data, pattern))
+}
+
+/// Canonical text of the flag/version record: `True`/`False`, one space,
+/// then the version; padded to the field width on write.
+pub fn format_flag_version(flag: bool, version: &str) -> String {
+    let flag_str = if flag { "True" } else { "False" };
+    if version.is_empty() {
+        flag_str.to_string()
+    } else {
+        format!("{} {}", flag_str, version)
+    }
+}
+
+/// Reads up to `cols` columns of byte-per-bit pattern data starting at
+/// `offset`, ignoring any header: the recovery path for files whose header
+/// is damaged but whose payload is intact. Returns `[bit][col]` rows like
+/// [`PatternFileData::pattern_data`]; only complete columns are read, so the
+/// row length is the number of cycles recovered. Fails if `offset` is past
+/// the end of `bytes`.
+pub fn parse_pattern_region(bytes: &[u8], offset: usize, cols: usize) -> Result<Vec<Vec<u8>>, CodecError> {
+    let region = bytes.get(offset..).ok_or_else(|| {
+        CodecError::InvalidData(format!("offset {} is past the end of the input ({} bytes)", offset, bytes.len()))
+    })?;
+    let capacity = cols.min(region.len() / NUM_BITS);
+    let mut rows: Vec<Vec<u8>> = (0..NUM_BITS).map(|_| Vec::with_capacity(capacity)).collect();
+    for column in region.chunks_exact(NUM_BITS).take(cols) {
+        for (row, &value) in rows.iter_mut().zip(column) {
+            row.push(value);
+        }
+    }
+    Ok(rows)
+}
+
+/// Encodes one record.
+///
+/// Fails with `InvalidData`, before writing anything, if the record does
+/// not pass [`check_shape`](PatternFileData::check_shape).
+pub fn encode<W: ByteWrite + ?Sized>(writer: &mut W, data: &PatternFileData) -> Result<(), CodecError> {
+    encode_with(writer, data, &PcfLayout::default())
+}
+
+/// Encodes one record with the header field widths and delimiter of
+/// `layout`; the pattern is written in `data.encoding`. A newline-delimited
+/// header fails on a value that itself contains a newline.
+///
+/// Fails with `InvalidData`, before writing anything, if the record does
+/// not pass [`check_shape_with`](PatternFileData::check_shape_with) for
+/// `layout`.
+pub fn encode_with<W: ByteWrite + ?Sized>(writer: &mut W, data: &PatternFileData, layout: &PcfLayout) -> Result<(), CodecError> {
+    encode_from(writer, data, &data.pattern_data, layout)
+}
+
+/// [`encode_with`] taking the pattern values from `pattern` instead of
+/// `data.pattern_data`.
+pub(crate) fn encode_from<W: ByteWrite + ?Sized, S: PatternStore>(writer: &mut W, data: &PatternFileData, pattern: &S, layout: &PcfLayout) -> Result<(), CodecError> {
+    data.check_header_with(layout)
+        .and_then(|()| pattern.check_cols(data.cols()))
+        .map_err(CodecError::InvalidData)?;
+
+    let (numeric, string) = (layout.numeric_width, layout.string_width);
+    let delimiter = layout.delimiter;
+    let write_fixed = |writer: &mut W, val: &str, len: usize| -> Result<(), CodecError> {
+        match delimiter {
+            FieldDelimiter::FixedWidth => {
+                let mut bytes: Vec<u8> = val
+                    .as_bytes()
+                    .to_vec();
+
+                bytes.resize(len, PAD_BYTE);
+
+                writer.write_all(&bytes[..len])
+            }
+            FieldDelimiter::Newline => {
+                writer.write_all(val.as_bytes())?;
+                writer.write_all(b"\n")
+            }
+        }
+    };
+
+    write_fixed(writer, &format_flag_version(data.compiled_flag, &data.version), string)?;
+
+    write_fixed(writer, &data.source_combo_index.to_string(), numeric)?;
+
+    for v in &data.pclk_source_indices {
+        write_fixed(writer, &v.to_string(), numeric)?;
+    }
+
+    write_fixed(writer, &data.vtime_reqd[8], string)?;
+    for v in &data.vtime_reqd[..8] {
+        write_fixed(writer, v, string)?;
+    }
+
+    write_fixed(writer, &data.cycle_time[8], string)?;
+    for v in &data.cycle_time[..8] {
+        write_fixed(writer, v, string)?;
+    }
+
+    write_fixed(writer, &data.pulse_time[8], string)?;
+    for v in &data.pulse_time[..8] {
+        write_fixed(writer, v, string)?;
+    }
+
+    for src in &data.clk_sources[1..] {
+        write_fixed(writer, src, string)?;
+    }
+
+    for i in 0..8 {
+        write_fixed(writer, &data.start_addrs[i].to_string(), numeric)?;
+        write_fixed(writer, &data.end_addrs[i].to_string(), numeric)?;
+        write_fixed(writer, &data.loop_counts[i].to_string(), numeric)?;
+    }
+
+    write_fixed(writer, &data.pattern_file_length.to_string(), numeric)?;
+
+    let cols = data.cols();
+
+    match data.encoding {
+        PatternEncoding::BytesPerBit => {
+            for col in 0..cols {
+                for bit in 0..NUM_BITS {
+                    writer.write_all(&[pattern.value(bit, col)])?;
+                }
+            }
+        }
+        PatternEncoding::PackedBits => {
+            let mut packed = vec![0u8; data.encoding.column_bytes(NUM_BITS)];
+            for col in 0..cols {
+                packed.fill(0);
+                for bit in 0..NUM_BITS {
+                    packed[bit / 8] |= u8::from(pattern.value(bit, col) != 0) << (bit % 8);
+                }
+                writer.write_all(&packed)?;
+            }
+        }
+    }
+
+    writer.write_all(&data.trailer)?;
+
+    Ok(())
+}
+
+/// Decodes a record held in memory, with the same defaults as
+/// `parse_pcf_reader`: lenient parsing and the [`DEFAULT_MAX_PATTERN_BYTES`] cap.
+impl TryFrom<&[u8]> for PatternFileData {
+    type Error = CodecError;
+
+    fn try_from(mut bytes: &[u8]) -> Result<Self, CodecError> {
+        let opts = DecodeOptions { max_pattern_bytes: Some(DEFAULT_MAX_PATTERN_BYTES), ..Default::default() };
+        decode_with(&mut bytes, &opts)
+    }
+}
+
+impl TryFrom<Vec<u8>> for PatternFileData {
+    type Error = CodecError;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, CodecError> {
+        PatternFileData::try_from(bytes.as_slice())
+    }
+}
+
+/// Encodes one record into a new byte vector; fails as [`encode`] does.
+pub fn encode_to_vec(data: &PatternFileData) -> Result<Vec<u8>, CodecError> {
+    let mut out = Vec::new();
+    encode(&mut out, data)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inspect::HeaderField;
+    use crate::layout::{header_size, FIELD_WIDTH};
+
+    fn sample() -> PatternFileData {
+        PatternFileData::builder()
+            .version("v1")
+            .columns(&[[1; NUM_BITS]; 22])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn trailer_is_kept_and_written_back() {
+        let mut bytes = encode_to_vec(&sample()).unwrap();
+        bytes.extend_from_slice(&[0xC5; 40]);
+        let opts = DecodeOptions { strict: true, total_len: Some(bytes.len()), keep_trailer: true, ..Default::default() };
+
+        let data = decode_with(&mut &bytes[..], &opts).unwrap();
+        assert_eq!(data.trailer, [0xC5; 40]);
+        assert_eq!(data.cols(), 22);
+        assert_eq!(encode_to_vec(&data).unwrap(), bytes);
+
+        let without = DecodeOptions { keep_trailer: false, ..opts.clone() };
+        assert!(decode_with(&mut &bytes[..], &without).is_err());
+        let plain = encode_to_vec(&sample()).unwrap();
+        let opts = DecodeOptions { total_len: Some(plain.len()), ..opts };
+        assert!(decode_with(&mut &plain[..], &opts).unwrap().trailer.is_empty());
+    }
+
+    #[test]
+    fn encode_decode_round_trip_over_slices() {
+        let data = sample();
+        let bytes = encode_to_vec(&data).unwrap();
+        assert_eq!(bytes.len(), header_size() + 22 * NUM_BITS);
+
+        let parsed = decode(&mut &bytes[..], true).unwrap();
+        assert_eq!(parsed, data);
+    }
+
+    #[test]
+    fn decode_with_total_len_checks_stale_length() {
+        let mut data = sample();
+        let bytes = encode_to_vec(&data).unwrap();
+        // header claims one column fewer than is actually present
+        let mut stale = bytes.clone();
+        let field = &mut stale[header_size() - FIELD_WIDTH..header_size()];
+        field.copy_from_slice(b"1         ");
+
+        let strict = DecodeOptions { strict: true, total_len: Some(stale.len()), ..Default::default() };
+        let err = decode_with(&mut &stale[..], &strict).unwrap_err();
+        assert!(err.to_string().contains("implies 21 columns but the input holds 22"));
+
+        let lenient = DecodeOptions { strict: false, total_len: Some(stale.len()), ..Default::default() };
+        let parsed = decode_with(&mut &stale[..], &lenient).unwrap();
+        data.pattern_file_length = 2;
+        assert_eq!(parsed, data);
+    }
+
+    #[test]
+    fn packed_bits_round_trip_and_detection() {
+        let mut data = sample();
+        data.clear_pattern();
+        data.pattern_data[0][0] = 1;
+        data.pattern_data[9][1] = 1;
+        data.pattern_data[17][21] = 1;
+        data.encoding = PatternEncoding::PackedBits;
+
+        let bytes = encode_to_vec(&data).unwrap();
+        assert_eq!(bytes.len(), header_size() + 22 * 3);
+        assert_eq!(&bytes[header_size()..header_size() + 6], &[0x01, 0, 0, 0, 0x02, 0]);
+        assert_eq!(bytes[bytes.len() - 1], 0x02);
+
+        let detect = DecodeOptions { total_len: Some(bytes.len()), ..Default::default() };
+        assert_eq!(decode_with(&mut &bytes[..], &detect).unwrap(), data);
+
+        let explicit = DecodeOptions { encoding: Some(PatternEncoding::PackedBits), ..Default::default() };
+        assert_eq!(decode_with(&mut &bytes[..], &explicit).unwrap(), data);
+
+        let plain = encode_to_vec(&sample()).unwrap();
+        let detect = DecodeOptions { total_len: Some(plain.len()), ..Default::default() };
+        assert_eq!(decode_with(&mut &plain[..], &detect).unwrap().encoding, PatternEncoding::BytesPerBit);
+    }
+
+    #[test]
+    fn pattern_region_reads_complete_columns_only() {
+        let data = sample();
+        let bytes = encode_to_vec(&data).unwrap();
+        let rows = parse_pattern_region(&bytes, header_size(), usize::MAX).unwrap();
+        assert_eq!(rows, data.pattern_data);
+
+        let rows = parse_pattern_region(&bytes, header_size() + NUM_BITS, 2).unwrap();
+        assert_eq!(rows[5], data.pattern_data[5][1..3]);
+
+        // 10 bytes short of a full column at the end
+        let rows = parse_pattern_region(&bytes, header_size() + 10, usize::MAX).unwrap();
+        assert_eq!(rows[0].len(), data.cols() - 1);
+        assert_eq!(parse_pattern_region(&bytes, bytes.len(), 4).unwrap()[0].len(), 0);
+        assert!(parse_pattern_region(&bytes, bytes.len() + 1, 4).is_err());
+    }
+
+    fn with_flag_field(field: &[u8; FIELD_WIDTH]) -> Vec<u8> {
+        let mut bytes = encode_to_vec(&sample()).unwrap();
+        bytes[..FIELD_WIDTH].copy_from_slice(field);
+        bytes
+    }
+
+    #[test]
+    fn flag_field_double_space_is_normalized() {
+        let bytes = with_flag_field(b"True  V3  ");
+        let parsed = decode(&mut &bytes[..], true).unwrap();
+        assert!(parsed.compiled_flag);
+        assert_eq!(parsed.version, "V3");
+        assert_eq!(&encode_to_vec(&parsed).unwrap()[..FIELD_WIDTH], b"True V3   ");
+    }
+
+    #[test]
+    fn flag_field_fully_padded_round_trips_exactly() {
+        for field in [b"False     ", b"True      ", b"True V3   "] {
+            let bytes = with_flag_field(field);
+            let parsed = decode(&mut &bytes[..], true).unwrap();
+            assert_eq!(&encode_to_vec(&parsed).unwrap()[..FIELD_WIDTH], field);
+        }
+    }
+
+    #[test]
+    fn nul_padding_is_stripped_and_rewritten_as_spaces() {
+        let mut bytes = with_flag_field(b"True V3\0\0\0");
+        let at = HeaderField::ClkSource(1).byte_range();
+        bytes[at.clone()].copy_from_slice(b"MCLK \0\0\0\0\0");
+        let loops = HeaderField::LoopCount(0).byte_range();
+        bytes[loops.clone()].copy_from_slice(b"12\0\0\0\0\0\0\0\0");
+
+        let parsed = decode(&mut &bytes[..], true).unwrap();
+        assert_eq!((parsed.version.as_str(), parsed.clk_sources[1].as_str()), ("V3", "MCLK"));
+        assert_eq!(parsed.loop_counts[0], 12);
+
+        let rewritten = encode_to_vec(&parsed).unwrap();
+        assert_eq!(&rewritten[..FIELD_WIDTH], b"True V3   ");
+        assert_eq!(&rewritten[at], b"MCLK      ");
+        assert_eq!(&rewritten[loops], b"12        ");
+        assert_eq!(decode(&mut &rewritten[..], true).unwrap(), parsed);
+    }
+
+    #[test]
+    fn flag_field_leading_padding_keeps_flag() {
+        let bytes = with_flag_field(b"  True V3 ");
+        let parsed = decode(&mut &bytes[..], true).unwrap();
+        assert!(parsed.compiled_flag);
+        assert_eq!(parsed.version, "V3");
+    }
+
+    #[test]
+    fn mixed_field_widths_round_trip() {
+        let layout = PcfLayout { string_width: 12, ..Default::default() };
+        let mut data = sample();
+        data.clk_sources[1] = "TWELVE_CHARS".into();
+        data.loop_counts[7] = 1_234_567_890;
+
+        let mut bytes = Vec::new();
+        encode_with(&mut bytes, &data, &layout).unwrap();
+        assert_eq!(bytes.len(), layout.header_size() + data.cols() * NUM_BITS);
+        assert_eq!(&bytes[..12], b"False v1    ");
+
+        let opts = DecodeOptions { strict: true, total_len: Some(bytes.len()), layout, ..Default::default() };
+        assert_eq!(decode_with(&mut &bytes[..], &opts).unwrap(), data);
+    }
+
+    #[test]
+    fn newline_delimited_header_round_trips() {
+        let layout = PcfLayout { delimiter: FieldDelimiter::Newline, ..Default::default() };
+        let mut data = sample();
+        data.clk_sources[1] = "A NAME LONGER THAN TEN".into();
+        data.loop_counts[7] = 42;
+
+        let mut bytes = Vec::new();
+        encode_with(&mut bytes, &data, &layout).unwrap();
+        assert!(bytes.starts_with(b"False v1\n0\n"));
+
+        let opts = DecodeOptions { strict: true, total_len: Some(bytes.len()), layout, ..Default::default() };
+        assert_eq!(decode_with(&mut &bytes[..], &opts).unwrap(), data);
+
+        // CRLF line ends read the same
+        let header_len = bytes.len() - data.cols() * NUM_BITS;
+        let mut crlf = Vec::new();
+        for &b in &bytes[..header_len] {
+            if b == b'\n' {
+                crlf.push(b'\r');
+            }
+            crlf.push(b);
+        }
+        crlf.extend_from_slice(&bytes[header_len..]);
+        let opts = DecodeOptions { total_len: Some(crlf.len()), ..opts };
+        assert_eq!(decode_with(&mut &crlf[..], &opts).unwrap(), data);
+
+        data.version = "v\n2".into();
+        assert!(encode_with(&mut Vec::new(), &data, &layout).is_err());
+    }
+
+    #[test]
+    fn try_from_bytes_decodes() {
+        let data = PatternFileData::builder().version("V3").build().unwrap();
+        let bytes = encode_to_vec(&data).unwrap();
+
+        assert_eq!(PatternFileData::try_from(&bytes[..]).unwrap(), data);
+        assert_eq!(PatternFileData::try_from(bytes.clone()).unwrap(), data);
+        assert!(matches!(PatternFileData::try_from(&bytes[..100]), Err(CodecError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn decode_rejects_negative_and_oversized_lengths() {
+        let mut bytes = encode_to_vec(&sample()).unwrap();
+        bytes[header_size() - FIELD_WIDTH..header_size()].copy_from_slice(b"-21       ");
+        let err = decode(&mut &bytes[..], false).unwrap_err();
+        assert!(err.to_string().contains("pattern_file_length -21 is below -20"));
+
+        bytes[header_size() - FIELD_WIDTH..header_size()].copy_from_slice(b"999999999 ");
+        let capped = DecodeOptions { max_pattern_bytes: Some(1 << 20), ..Default::default() };
+        let err = decode_with(&mut &bytes[..], &capped).unwrap_err();
+        assert!(err.to_string().contains("above the limit of 1048576"));
+
+        let ok = DecodeOptions { max_pattern_bytes: Some(22 * NUM_BITS), ..Default::default() };
+        assert!(decode_with(&mut &encode_to_vec(&sample()).unwrap()[..], &ok).is_ok());
+    }
+
+    #[test]
+    fn decode_reports_truncated_input() {
+        let bytes = encode_to_vec(&sample()).unwrap();
+        let err = decode(&mut &bytes[..bytes.len() - 1], false).unwrap_err();
+        assert!(matches!(err, CodecError::UnexpectedEof));
+    }
+
+    #[test]
+    fn encode_rejects_malformed_records_without_writing() {
+        let mut short_row = sample();
+        short_row.pattern_data[4].pop();
+        let mut too_short = sample();
+        too_short.pattern_file_length = -21;
+        let mut few_clocks = sample();
+        few_clocks.clk_sources.pop();
+
+        for (data, expected) in [(short_row, "pattern_data[4]"), (too_short, "below -20"), (few_clocks, "clk_sources")] {
+            let mut out = Vec::new();
+            match encode(&mut out, &data) {
+                Err(CodecError::InvalidData(msg)) => assert!(msg.contains(expected), "{}", msg),
+                other => panic!("expected InvalidData, got {:?}", other),
+            }
+            assert!(out.is_empty());
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Semantic comparisons between two parsed files.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use crate::layout::{NUM_BITS, NUM_LOOPS};
+use crate::pattern::PatternFileData;
+
+/// One changed loop setting between two files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LoopDiff {
+    /// Loop index (0–7).
+    pub index: usize,
+    /// Name of the changed field: `start_addrs`, `end_addrs` or `loop_counts`.
+    pub field: &'static str,
+    pub old: i32,
+    pub new: i32,
+}
+
+/// Differences in the loop configuration only, ordered by loop index and then
+/// by field. The pattern bytes and every other header field are ignored.
+pub fn loop_config_diff(a: &PatternFileData, b: &PatternFileData) -> Vec<LoopDiff> {
+    let mut out = Vec::new();
+    for index in 0..NUM_LOOPS {
+        let fields = [
+            ("start_addrs", a.start_addrs[index], b.start_addrs[index]),
+            ("end_addrs", a.end_addrs[index], b.end_addrs[index]),
+            ("loop_counts", a.loop_counts[index], b.loop_counts[index]),
+        ];
+        for (field, old, new) in fields {
+            if old != new {
+                out.push(LoopDiff { index, field, old, new });
+            }
+        }
+    }
+    out
+}
+
+/// Bits that are exercised in only one of two files (see
+/// [`PatternFileData::active_bits`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ActiveBitsDiff {
+    /// Active in the second file but never set in the first.
+    pub gained: Vec<usize>,
+    /// Active in the first file but never set in the second.
+    pub lost: Vec<usize>,
+}
+
+impl ActiveBitsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.gained.is_empty() && self.lost.is_empty()
+    }
+}
+
+/// Which bits became active or went dormant going from `a` to `b`.
+pub fn active_bits_diff(a: &PatternFileData, b: &PatternFileData) -> ActiveBitsDiff {
+    let (old, new) = (a.active_bits(), b.active_bits());
+    let mut out = ActiveBitsDiff::default();
+    for bit in 0..NUM_BITS {
+        match (old[bit], new[bit]) {
+            (false, true) => out.gained.push(bit),
+            (true, false) => out.lost.push(bit),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Bit-level distance between two patterns of the same size.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BitDiff {
+    /// Differing bits over the whole pattern (the Hamming distance).
+    pub total: usize,
+    /// Differing cycles for each bit.
+    pub per_bit: [usize; NUM_BITS],
+}
+
+/// Compares the patterns bit by bit; any non-zero byte counts as a set bit,
+/// so files in different encodings compare by value. Fails unless both
+/// patterns have the same number of rows of the same length.
+pub fn bit_diff(a: &PatternFileData, b: &PatternFileData) -> Result<BitDiff, String> {
+    let shape = |d: &PatternFileData| d.pattern_data.iter().map(Vec::len).collect::<Vec<_>>();
+    if shape(a) != shape(b) {
+        return Err(format!(
+            "patterns differ in size ({} vs {} cycles); a bit-level diff needs equal dimensions",
+            a.cols(), b.cols()
+        ));
+    }
+
+    let mut out = BitDiff::default();
+    for (bit, (ra, rb)) in a.pattern_data.iter().zip(&b.pattern_data).enumerate().take(NUM_BITS) {
+        out.per_bit[bit] = ra.iter().zip(rb).filter(|(x, y)| (**x != 0) != (**y != 0)).count();
+    }
+    out.total = out.per_bit.iter().sum();
+    Ok(out)
+}
+
+/// Total number of differing pattern bits; see [`bit_diff`].
+pub fn bit_diff_count(a: &PatternFileData, b: &PatternFileData) -> Result<usize, String> {
+    bit_diff(a, b).map(|d| d.total)
+}
+
+/// Lowest cycle whose 18 bits differ, stopping at the first mismatch; any
+/// non-zero byte counts as a set bit. When one pattern is a prefix of the
+/// other, the first cycle past the shorter one is returned.
+pub fn first_diff_cycle(a: &PatternFileData, b: &PatternFileData) -> Option<usize> {
+    let bits = |col: [u8; NUM_BITS]| col.map(|v| u8::from(v != 0));
+    let (mut ca, mut cb) = (a.columns(), b.columns());
+    let mut cycle = 0;
+    loop {
+        match (ca.next(), cb.next()) {
+            (None, None) => return None,
+            (Some(x), Some(y)) if bits(x) == bits(y) => cycle += 1,
+            _ => return Some(cycle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_config_diff_reports_changed_fields_only() {
+        let a = PatternFileData::default();
+        let mut b = PatternFileData::default();
+        b.end_addrs[2] = 40;
+        b.loop_counts[2] = 3;
+        b.start_addrs[7] = 5;
+        b.version = "ignored".into();
+        b.pattern_data = vec![vec![1]];
+
+        let diffs = loop_config_diff(&a, &b);
+        assert_eq!(diffs, vec![
+            LoopDiff { index: 2, field: "end_addrs", old: 0, new: 40 },
+            LoopDiff { index: 2, field: "loop_counts", old: 0, new: 3 },
+            LoopDiff { index: 7, field: "start_addrs", old: 0, new: 5 },
+        ]);
+        assert!(loop_config_diff(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn first_diff_cycle_stops_at_the_earliest_change() {
+        let a = PatternFileData::builder().pattern_file_length(10).build().unwrap();
+        let mut b = a.clone();
+        assert_eq!(first_diff_cycle(&a, &b), None);
+
+        b.pattern_data[4][12] = 1;
+        b.pattern_data[0][25] = 1;
+        assert_eq!(first_diff_cycle(&a, &b), Some(12));
+        b.pattern_data[4][12] = 0x80;
+        assert_eq!(first_diff_cycle(&a, &b), Some(12));
+
+        let longer = PatternFileData::builder().pattern_file_length(11).build().unwrap();
+        assert_eq!(first_diff_cycle(&a, &longer), Some(30));
+    }
+
+    #[test]
+    fn active_bits_diff_splits_gained_and_lost() {
+        let mut a = PatternFileData::new();
+        let mut b = PatternFileData::new();
+
+        a.pattern_data[1][0] = 1;
+        a.pattern_data[2][0] = 1;
+        b.pattern_data[2][5] = 1;
+        b.pattern_data[17][19] = 1;
+
+        let diff = active_bits_diff(&a, &b);
+        assert_eq!(diff, ActiveBitsDiff { gained: vec![17], lost: vec![1] });
+        assert!(active_bits_diff(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn bit_diff_counts_per_bit_and_checks_size() {
+        let mut a = PatternFileData::default();
+        a.clear_pattern();
+        let mut b = a.clone();
+        b.pattern_data[0][3] = 1;
+        b.pattern_data[4][0] = 1;
+        b.pattern_data[4][19] = 1;
+        a.pattern_data[9][7] = 2;
+        b.pattern_data[9][7] = 1;
+
+        let diff = bit_diff(&a, &b).unwrap();
+        assert_eq!(diff.total, 3);
+        assert_eq!((diff.per_bit[0], diff.per_bit[4], diff.per_bit[9]), (1, 2, 0));
+        assert_eq!(bit_diff_count(&a, &a), Ok(0));
+
+        b.pattern_file_length = 1;
+        b.clear_pattern();
+        let err = bit_diff_count(&a, &b).unwrap_err();
+        assert!(err.contains("20 vs 21 cycles"), "{}", err);
+    }
+}
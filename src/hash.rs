@@ -0,0 +1,161 @@
+//! Stable 64-bit FNV-1a hashing and CRC-32 checksums.
+//!
+//! Unlike `std`'s `DefaultHasher`, the output never changes between runs or
+//! compiler versions, so hashes can be stored and compared later. The CRC is
+//! the common IEEE CRC-32 (as in zip, PNG and `crc32` tools), for checksums
+//! that other tools must be able to reproduce.
+
+use core::hash::Hasher;
+
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a [`Hasher`].
+#[derive(Debug, Clone, Copy)]
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(OFFSET_BASIS)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// FNV-1a hash of `bytes`.
+pub fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut h = Fnv1a::default();
+    h.write(bytes);
+    h.finish()
+}
+
+/// Byte-at-a-time lookup table for the reflected IEEE polynomial.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            k += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Incremental CRC-32: feed bytes with [`update`](Self::update) in any
+/// number of pieces, then read [`value`](Self::value).
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32(!0)
+    }
+}
+
+impl Crc32 {
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = CRC_TABLE[((self.0 ^ b as u32) & 0xFF) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    /// CRC of every byte fed so far.
+    pub fn value(&self) -> u32 {
+        !self.0
+    }
+}
+
+/// CRC-32 of `bytes`.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::default();
+    crc.update(bytes);
+    crc.value()
+}
+
+/// [`std::io::Write`] adapter that passes bytes through to `inner` and keeps
+/// a [`Crc32`] of those it accepted.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct CrcWriter<W> {
+    inner: W,
+    crc: Crc32,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> CrcWriter<W> {
+    pub fn new(inner: W) -> Self {
+        CrcWriter { inner, crc: Crc32::default() }
+    }
+
+    /// CRC-32 of the bytes written so far.
+    pub fn crc(&self) -> u32 {
+        self.crc.value()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> std::io::Write for CrcWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.crc.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_reference_vectors() {
+        assert_eq!(fnv1a64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a64(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn crc32_matches_reference_vectors() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let mut crc = Crc32::default();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.value(), 0xCBF4_3926);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn crc_writer_checksums_what_it_passes_on() {
+        use std::io::Write;
+
+        let mut w = CrcWriter::new(Vec::new());
+        w.write_all(b"12345").unwrap();
+        w.write_all(b"6789").unwrap();
+        assert_eq!(w.crc(), 0xCBF4_3926);
+        assert_eq!(w.into_inner(), b"123456789");
+    }
+}
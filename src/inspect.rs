@@ -0,0 +1,248 @@
+//! Byte-level view of individual header fields.
+//!
+//! [`HeaderField`] names every fixed-width field and knows where it sits in
+//! the file, so tools can locate and decode a field without repeating the
+//! layout arithmetic.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use serde::Serialize;
+
+use crate::codec::trim_padding;
+use crate::layout::{header_size, PatternEncoding, FIELD_WIDTH, NUM_BITS, NUM_CLK_SOURCES, NUM_LOOPS, NUM_TIMINGS, PATTERN_PAD_COLS};
+
+/// One fixed-width header field. Indexed variants use the in-memory index of
+/// the corresponding [`PatternFileData`](crate::PatternFileData) array
+/// (`ClkSource` takes the slot, 1–64).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeaderField {
+    /// The `{flag} {version}` record.
+    CompiledFlag,
+    SourceComboIndex,
+    PclkSourceIndex(usize),
+    VtimeReqd(usize),
+    CycleTime(usize),
+    PulseTime(usize),
+    ClkSource(usize),
+    StartAddr(usize),
+    EndAddr(usize),
+    LoopCount(usize),
+    PatternFileLength,
+}
+
+impl HeaderField {
+    /// Every field in file order.
+    pub fn all() -> impl Iterator<Item = HeaderField> {
+        use HeaderField::*;
+        // timing tables store entry 8 first
+        let timing_order = || core::iter::once(NUM_TIMINGS - 1).chain(0..NUM_TIMINGS - 1);
+        [CompiledFlag, SourceComboIndex]
+            .into_iter()
+            .chain((0..NUM_LOOPS).map(PclkSourceIndex))
+            .chain(timing_order().map(VtimeReqd))
+            .chain(timing_order().map(CycleTime))
+            .chain(timing_order().map(PulseTime))
+            .chain((1..=NUM_CLK_SOURCES).map(ClkSource))
+            .chain((0..NUM_LOOPS).flat_map(|i| [StartAddr(i), EndAddr(i), LoopCount(i)]))
+            .chain([PatternFileLength])
+    }
+
+    /// Position of the field in the header, counted in fields.
+    ///
+    /// # Panics
+    /// If an indexed variant's index is out of range.
+    pub fn position(self) -> usize {
+        use HeaderField::*;
+        let check = |i: usize, len: usize| {
+            assert!(i < len, "{:?} is out of range", self);
+            i
+        };
+        // entry 8 of a timing table comes first on disk
+        let timing = |i: usize| (check(i, NUM_TIMINGS) + 1) % NUM_TIMINGS;
+        let timings = 2 + NUM_LOOPS;
+        let clocks = timings + 3 * NUM_TIMINGS;
+        let loops = clocks + NUM_CLK_SOURCES;
+        match self {
+            CompiledFlag => 0,
+            SourceComboIndex => 1,
+            PclkSourceIndex(i) => 2 + check(i, NUM_LOOPS),
+            VtimeReqd(i) => timings + timing(i),
+            CycleTime(i) => timings + NUM_TIMINGS + timing(i),
+            PulseTime(i) => timings + 2 * NUM_TIMINGS + timing(i),
+            ClkSource(slot) => {
+                assert!(slot >= 1, "{:?} is out of range", self);
+                clocks + check(slot - 1, NUM_CLK_SOURCES)
+            }
+            StartAddr(i) => loops + 3 * check(i, NUM_LOOPS),
+            EndAddr(i) => loops + 3 * check(i, NUM_LOOPS) + 1,
+            LoopCount(i) => loops + 3 * check(i, NUM_LOOPS) + 2,
+            PatternFileLength => loops + 3 * NUM_LOOPS,
+        }
+    }
+
+    /// File offsets occupied by the field.
+    pub fn byte_range(self) -> Range<usize> {
+        let start = self.position() * FIELD_WIDTH;
+        start..start + FIELD_WIDTH
+    }
+
+    /// Name as used by the JSON form, with the in-memory index, e.g. `cycle_time[3]`.
+    pub fn name(self) -> String {
+        use HeaderField::*;
+        match self {
+            CompiledFlag => "compiled_flag".into(),
+            SourceComboIndex => "source_combo_index".into(),
+            PclkSourceIndex(i) => format!("pclk_source_indices[{}]", i),
+            VtimeReqd(i) => format!("vtime_reqd[{}]", i),
+            CycleTime(i) => format!("cycle_time[{}]", i),
+            PulseTime(i) => format!("pulse_time[{}]", i),
+            ClkSource(i) => format!("clk_sources[{}]", i),
+            StartAddr(i) => format!("start_addrs[{}]", i),
+            EndAddr(i) => format!("end_addrs[{}]", i),
+            LoopCount(i) => format!("loop_counts[{}]", i),
+            PatternFileLength => "pattern_file_length".into(),
+        }
+    }
+
+    /// True for the fields the parser reads as integers.
+    pub fn is_numeric(self) -> bool {
+        use HeaderField::*;
+        matches!(
+            self,
+            SourceComboIndex | PclkSourceIndex(_) | StartAddr(_) | EndAddr(_) | LoopCount(_) | PatternFileLength
+        )
+    }
+}
+
+/// A field's location, raw bytes and decoded text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldView {
+    pub name: String,
+    pub byte_range: Range<usize>,
+    /// Bytes present in the input; shorter than the field if the input ends early.
+    pub raw: Vec<u8>,
+    /// The value as the parser sees it: padding trimmed, integers normalized
+    /// (text that is not an integer is shown as-is).
+    pub value: String,
+}
+
+/// Locates `field` in `bytes` (a whole file or at least its header).
+///
+/// # Panics
+/// If an indexed variant's index is out of range.
+pub fn inspect_field(bytes: &[u8], field: HeaderField) -> FieldView {
+    let byte_range = field.byte_range();
+    let raw = bytes[byte_range.start.min(bytes.len())..byte_range.end.min(bytes.len())].to_vec();
+    let text = String::from_utf8_lossy(&raw);
+    let text = trim_padding(&text);
+    let value = if field.is_numeric() {
+        text.trim().parse::<i32>().map_or_else(|_| text.to_string(), |v| v.to_string())
+    } else {
+        text.to_string()
+    };
+    FieldView { name: field.name(), byte_range, raw, value }
+}
+
+/// Cheap test of whether `bytes` hold one PCF record in the default layout:
+/// the first field starts with a `True`/`False` flag, every numeric field
+/// is an integer and the size matches `pattern_file_length` in either
+/// encoding. Lets batch tools skip other files without attempting a parse.
+pub fn looks_like_pcf(bytes: &[u8]) -> bool {
+    header_looks_like_pcf(bytes, bytes.len())
+}
+
+/// [`looks_like_pcf`] given only the start of a `total_len`-byte file (at
+/// least the header).
+pub(crate) fn header_looks_like_pcf(header: &[u8], total_len: usize) -> bool {
+    let header_len = header_size();
+    if header.len() < header_len || total_len < header_len {
+        return false;
+    }
+    let text = |field: HeaderField| core::str::from_utf8(&header[field.byte_range()]).ok().map(trim_padding);
+    let int = |field: HeaderField| text(field).and_then(|t| t.trim().parse::<i32>().ok());
+
+    let flag = text(HeaderField::CompiledFlag).and_then(|t| t.split_whitespace().next());
+    if !flag.is_some_and(|f| f.eq_ignore_ascii_case("true") || f.eq_ignore_ascii_case("false")) {
+        return false;
+    }
+    if !HeaderField::all().filter(|f| f.is_numeric()).all(|f| int(f).is_some()) {
+        return false;
+    }
+
+    let Some(length) = int(HeaderField::PatternFileLength) else { return false };
+    let cols = length as i64 + PATTERN_PAD_COLS as i64;
+    let region = (total_len - header_len) as i64;
+    cols >= 0
+        && [PatternEncoding::BytesPerBit, PatternEncoding::PackedBits]
+            .iter()
+            .any(|e| cols * e.column_bytes(NUM_BITS) as i64 == region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::encode_to_vec;
+    use crate::layout::{header_size, HEADER_FIELDS};
+    use crate::pattern::PatternFileData;
+
+    #[test]
+    fn fields_tile_the_header_in_order() {
+        let fields: Vec<HeaderField> = HeaderField::all().collect();
+        assert_eq!(fields.len(), HEADER_FIELDS);
+        for (i, f) in fields.iter().enumerate() {
+            assert_eq!(f.position(), i, "{:?}", f);
+        }
+        assert_eq!(HeaderField::PatternFileLength.byte_range().end, header_size());
+        assert_eq!(HeaderField::VtimeReqd(8).byte_range(), 100..110);
+    }
+
+    #[test]
+    fn inspect_reads_encoded_values() {
+        let mut cycle_time: [String; NUM_TIMINGS] = Default::default();
+        cycle_time[8] = "4000".into();
+        let data = PatternFileData::builder()
+            .version("V3")
+            .cycle_time(cycle_time)
+            .clk_source(64, "MCLK")
+            .loop_region(7, 0, 0, -2)
+            .build()
+            .unwrap();
+        let bytes = encode_to_vec(&data).unwrap();
+
+        let view = inspect_field(&bytes, HeaderField::CompiledFlag);
+        assert_eq!((view.name.as_str(), view.value.as_str()), ("compiled_flag", "False V3"));
+        assert_eq!(view.raw, b"False V3  ");
+
+        assert_eq!(inspect_field(&bytes, HeaderField::CycleTime(8)).value, "4000");
+        assert_eq!(inspect_field(&bytes, HeaderField::ClkSource(64)).value, "MCLK");
+        assert_eq!(inspect_field(&bytes, HeaderField::LoopCount(7)).value, "-2");
+
+        let short = inspect_field(&bytes[..15], HeaderField::SourceComboIndex);
+        assert_eq!(short.raw.len(), 5);
+    }
+
+    #[test]
+    fn recognises_pcf_bytes() {
+        let mut bytes = encode_to_vec(&PatternFileData::builder().version("V3").pattern_file_length(4).build().unwrap()).unwrap();
+        assert!(looks_like_pcf(&bytes));
+        assert!(!looks_like_pcf(&bytes[..bytes.len() - 1]));
+        assert!(!looks_like_pcf(&bytes[..100]));
+        assert!(!looks_like_pcf(b"not a pattern file at all"));
+
+        let len = HeaderField::StartAddr(2).byte_range();
+        bytes[len.clone()].copy_from_slice(b"x         ");
+        assert!(!looks_like_pcf(&bytes));
+        bytes[len].copy_from_slice(b"7         ");
+        bytes[..5].copy_from_slice(b"Maybe");
+        assert!(!looks_like_pcf(&bytes));
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range_slot_panics() {
+        HeaderField::ClkSource(0).position();
+    }
+}
@@ -0,0 +1,245 @@
+//! On-disk layout of a PCF file.
+//!
+//! The header is a run of space-padded, fixed-width text fields followed by
+//! the binary pattern region, which stores one byte per bit for each column
+//! or, in the packed variant, the column's bits in three bytes. One variant
+//! ends each header field with a newline instead of padding it; see
+//! [`FieldDelimiter`].
+
+use serde::{Deserialize, Serialize};
+
+/// Width in bytes of every header field.
+pub const FIELD_WIDTH: usize = 10;
+
+/// Number of bits (rows) stored per pattern column.
+pub const NUM_BITS: usize = 18;
+
+/// Number of loop regions described in the header.
+pub const NUM_LOOPS: usize = 8;
+
+/// Number of entries in each timing table (`vtime_reqd`, `cycle_time`, `pulse_time`).
+pub const NUM_TIMINGS: usize = 9;
+
+/// Number of named clock sources stored on disk (slot 0 is unused in memory).
+pub const NUM_CLK_SOURCES: usize = 64;
+
+/// Extra columns stored after the `pattern_file_length` declared in the header.
+pub const PATTERN_PAD_COLS: usize = 20;
+
+/// Total number of fixed-width fields preceding the pattern region.
+pub const HEADER_FIELDS: usize = 1            // compiled flag + version
+    + 1                                       // source_combo_index
+    + NUM_LOOPS                               // pclk_source_indices
+    + 3 * NUM_TIMINGS                         // vtime_reqd, cycle_time, pulse_time
+    + NUM_CLK_SOURCES                         // clk_sources
+    + 3 * NUM_LOOPS                           // start/end/count per loop
+    + 1;                                      // pattern_file_length
+
+/// Header fields holding text: the flag/version record, the three timing
+/// tables and the clock source names. The rest are integers.
+pub const STRING_FIELDS: usize = 1 + 3 * NUM_TIMINGS + NUM_CLK_SOURCES;
+
+/// Header fields holding integers.
+pub const NUMERIC_FIELDS: usize = HEADER_FIELDS - STRING_FIELDS;
+
+/// Size of the header in bytes, i.e. the offset at which the pattern region starts.
+pub const fn header_size() -> usize {
+    HEADER_FIELDS * FIELD_WIDTH
+}
+
+/// How a pattern column is stored on disk. In memory `pattern_data` is always
+/// one byte per bit, whichever encoding the file used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PatternEncoding {
+    /// One byte (0 or 1) per bit: 18 bytes per column.
+    #[default]
+    BytesPerBit,
+    /// Bits packed LSB-first, bit `n` in byte `n / 8`: 3 bytes per column.
+    PackedBits,
+}
+
+impl PatternEncoding {
+    /// Bytes occupied by one column of `num_bits` bits.
+    pub const fn column_bytes(self, num_bits: usize) -> usize {
+        match self {
+            PatternEncoding::BytesPerBit => num_bits,
+            PatternEncoding::PackedBits => num_bits.div_ceil(8),
+        }
+    }
+
+    pub fn is_bytes_per_bit(&self) -> bool {
+        *self == PatternEncoding::BytesPerBit
+    }
+}
+
+/// How header fields are separated on disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FieldDelimiter {
+    /// Each field padded to its width in [`PcfLayout`].
+    #[default]
+    FixedWidth,
+    /// Each field ends with `\n` (a preceding `\r` is dropped on read), so
+    /// the header size depends on the values and the field widths are unused.
+    Newline,
+}
+
+/// Geometry of a PCF header. [`PcfLayout::default()`] is the standard format
+/// described by the constants in this module, with every field
+/// [`FIELD_WIDTH`] bytes wide. The pattern region's [`PatternEncoding`] is a
+/// property of each record, not of the layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcfLayout {
+    /// Width in bytes of each integer header field ([`NUMERIC_FIELDS`] of them).
+    pub numeric_width: usize,
+    /// Width in bytes of each text header field ([`STRING_FIELDS`] of them).
+    pub string_width: usize,
+    /// How header fields are separated.
+    pub delimiter: FieldDelimiter,
+}
+
+impl Default for PcfLayout {
+    fn default() -> Self {
+        PcfLayout {
+            numeric_width: FIELD_WIDTH,
+            string_width: FIELD_WIDTH,
+            delimiter: FieldDelimiter::FixedWidth,
+        }
+    }
+}
+
+impl PcfLayout {
+    /// Offset at which the pattern region starts. With
+    /// [`FieldDelimiter::Newline`] this is the fixed-width size; the real
+    /// offset is only known once the header has been read.
+    pub const fn header_size(&self) -> usize {
+        NUMERIC_FIELDS * self.numeric_width + STRING_FIELDS * self.string_width
+    }
+}
+
+/// File offset of the first byte of `cycle` in a pattern stored as `encoding`.
+pub const fn cycle_byte_offset(layout: &PcfLayout, encoding: PatternEncoding, cycle: usize) -> usize {
+    layout.header_size() + cycle * encoding.column_bytes(NUM_BITS)
+}
+
+/// The cycle containing file byte `offset` in a pattern stored as
+/// `encoding`, or `None` if it lies in the header.
+pub const fn byte_offset_to_cycle(layout: &PcfLayout, encoding: PatternEncoding, offset: usize) -> Option<usize> {
+    if offset < layout.header_size() {
+        None
+    } else {
+        Some((offset - layout.header_size()) / encoding.column_bytes(NUM_BITS))
+    }
+}
+
+/// Shortest printable run [`guess_header_size`] accepts as a header, in fields.
+const MIN_GUESS_FIELDS: usize = 4;
+
+/// How many fields [`guess_header_size`] looks back from the end of the
+/// printable run for a consistent length field.
+const MAX_GUESS_OVERSHOOT: usize = 4;
+
+/// Guesses where a PCF-like file's text header ends and its binary pattern
+/// begins, for files whose layout is not known up front.
+///
+/// Assumes the header is made of printable ASCII fields [`FIELD_WIDTH`] bytes
+/// wide and that the pattern opens with a non-printable byte within a few
+/// fields (0/1 bytes always are; packed bytes may not be). The printable run is
+/// rounded down to a field boundary; then, since pattern bytes can happen to
+/// be printable, the few boundaries before it are tried and the first whose
+/// last field is a `pattern_file_length` matching the remaining bytes (in
+/// either encoding) wins. Otherwise the rounded end of the run is returned.
+///
+/// Returns `None` when there is no printable run of at least a few fields
+/// or nothing binary follows it.
+pub fn guess_header_size(bytes: &[u8]) -> Option<usize> {
+    let first_binary = bytes.iter().position(|b| !(0x20..=0x7E).contains(b))?;
+    let end = first_binary / FIELD_WIDTH * FIELD_WIDTH;
+    let min = MIN_GUESS_FIELDS * FIELD_WIDTH;
+    if end < min {
+        return None;
+    }
+
+    let length_fits = |at: usize| {
+        let field = core::str::from_utf8(&bytes[at - FIELD_WIDTH..at]).unwrap_or_default();
+        let Ok(len) = field.trim().parse::<i64>() else { return false };
+        let cols = len + PATTERN_PAD_COLS as i64;
+        let remaining = (bytes.len() - at) as i64;
+        cols >= 0
+            && [PatternEncoding::BytesPerBit, PatternEncoding::PackedBits]
+                .iter()
+                .any(|e| cols * e.column_bytes(NUM_BITS) as i64 == remaining)
+    };
+    (0..=MAX_GUESS_OVERSHOOT)
+        .map(|k| end - k * FIELD_WIDTH)
+        .take_while(|&at| at >= min)
+        .find(|&at| length_fits(at))
+        .or(Some(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_size_matches_field_sequence() {
+        assert_eq!(HEADER_FIELDS, 126);
+        assert_eq!(header_size(), 1260);
+        assert_eq!(PcfLayout::default().header_size(), header_size());
+    }
+
+    #[test]
+    fn mixed_widths_size_the_header() {
+        assert_eq!((STRING_FIELDS, NUMERIC_FIELDS), (92, 34));
+        let layout = PcfLayout { string_width: 12, ..Default::default() };
+        assert_eq!(layout.header_size(), 92 * 12 + 34 * 10);
+        assert_eq!(cycle_byte_offset(&layout, PatternEncoding::BytesPerBit, 1), layout.header_size() + 18);
+    }
+
+    #[test]
+    fn cycle_offsets_round_trip() {
+        let layout = PcfLayout::default();
+        let bytes = PatternEncoding::BytesPerBit;
+        assert_eq!(cycle_byte_offset(&layout, bytes, 0), 1260);
+        assert_eq!(cycle_byte_offset(&layout, bytes, 3), 1260 + 54);
+
+        assert_eq!(byte_offset_to_cycle(&layout, bytes, 0), None);
+        assert_eq!(byte_offset_to_cycle(&layout, bytes, 1259), None);
+        assert_eq!(byte_offset_to_cycle(&layout, bytes, 1260), Some(0));
+        assert_eq!(byte_offset_to_cycle(&layout, bytes, 1260 + 17), Some(0));
+        assert_eq!(byte_offset_to_cycle(&layout, bytes, 1260 + 18), Some(1));
+
+        for cycle in [0, 1, 17, 1000] {
+            assert_eq!(byte_offset_to_cycle(&layout, bytes, cycle_byte_offset(&layout, bytes, cycle)), Some(cycle));
+        }
+    }
+
+    fn fake_file(pattern_file_length: usize, column_bytes: usize) -> alloc::vec::Vec<u8> {
+        let mut bytes = b"False v1  ".repeat(HEADER_FIELDS - 1);
+        bytes.extend_from_slice(alloc::format!("{:<10}", pattern_file_length).as_bytes());
+        bytes.resize(header_size() + (pattern_file_length + PATTERN_PAD_COLS) * column_bytes, 0);
+        bytes
+    }
+
+    #[test]
+    fn guesses_the_header_boundary() {
+        assert_eq!(guess_header_size(&fake_file(5, 18)), Some(1260));
+        assert_eq!(guess_header_size(&fake_file(5, 3)), Some(1260));
+
+        // printable pattern bytes overshoot the run; the length field pulls it back
+        let mut bytes = fake_file(5, 18);
+        bytes[1260..1285].fill(b'A');
+        assert_eq!(guess_header_size(&bytes), Some(1260));
+
+        assert_eq!(guess_header_size(&fake_file(5, 18)[..1260]), None);
+        assert_eq!(guess_header_size(&[0u8; 100]), None);
+        assert_eq!(guess_header_size(b"short\0\0\0"), None);
+    }
+
+    #[test]
+    fn packed_encoding_uses_three_bytes_per_column() {
+        let (layout, packed) = (PcfLayout::default(), PatternEncoding::PackedBits);
+        assert_eq!(packed.column_bytes(NUM_BITS), 3);
+        assert_eq!(cycle_byte_offset(&layout, packed, 4), 1260 + 12);
+        assert_eq!(byte_offset_to_cycle(&layout, packed, 1260 + 5), Some(1));
+    }
+}
@@ -1,5 +1,52 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod bitgrid;
+pub mod builder;
+pub mod codec;
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod generate;
+pub mod hash;
+pub mod inspect;
+pub mod layout;
+#[cfg(feature = "packed")]
+pub mod packed;
+#[cfg(feature = "std")]
+pub mod patch;
 pub mod pattern;
+pub mod stats;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod timing;
+#[cfg(feature = "std")]
 pub mod utils;
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod vcd;
+pub mod waveform;
 
-pub use pattern::{parse_pcf_file, write_pcf_file, PatternFileData};
-pub use utils::{hex_dump_file, diff_files, diff_blocks};
+pub use builder::PatternFileDataBuilder;
+pub use compare::{active_bits_diff, bit_diff, bit_diff_count, first_diff_cycle, loop_config_diff, ActiveBitsDiff, BitDiff, LoopDiff};
+pub use codec::{decode, decode_with, encode, encode_to_vec, encode_with, parse_pattern_region, ByteRead, ByteWrite, CodecError, DecodeOptions};
+pub use inspect::{inspect_field, looks_like_pcf, FieldView, HeaderField};
+pub use layout::{guess_header_size, FieldDelimiter, PatternEncoding};
+#[cfg(feature = "packed")]
+pub use packed::{decode_packed_with, encode_packed_with, PackedPattern, PackedPatternFile};
+#[cfg(all(feature = "packed", feature = "std"))]
+pub use packed::{parse_pcf_file_packed, write_pcf_file_packed};
+pub use pattern::{FileOrder, HeaderOnly, PatternFileData, Projection, Summary, FIELD_NAMES};
+#[cfg(feature = "std")]
+pub use pattern::{
+    parse_pcf_file, parse_pcf_file_limited, parse_pcf_file_strict, parse_pcf_file_with, parse_pcf_multi, parse_pcf_multi_with, parse_pcf_reader, parse_pcf_reader_strict,
+    write_file_atomic, write_pcf_file, write_pcf_file_checked, write_pcf_file_with, write_pcf_writer,
+};
+pub use stats::{LoopActivity, LoopInfo, PatternSummary};
+pub use validate::ValidationIssue;
+pub use waveform::render_waveform;
+#[cfg(feature = "std")]
+pub use utils::{diff_buffers, diff_buffers_blocks, hex_dump_file, hex_dump_file_with, hex_dump_with, hex_dump_html, diff_files, diff_files_with, diff_files_streaming, diff_files_streaming_with, diff_readers, diff_buffers_with, diff_blocks, diff_blocks_with, diff_blocks_report, diff_buffers_blocks_with, diff_buffers_lines, diff_side_by_side, diff_side_by_side_with, diff_summary, diff_pattern_blocks_report, dump_merged, lcs_diff, row_checksum, BlockDiff, BlockDiffOptions, DiffOp, DiffSummary, DumpColumns, DumpOptions, IntFormat, OffsetBase, SideBySideOptions};
@@ -1,8 +1,4 @@
-use crate::pattern::{parse_pcf_file, write_pcf_file};
-use crate::utils::{diff_blocks, diff_files, hex_dump_file};
-
-mod utils;
-mod pattern;
+use pcf_parser::{parse_pcf_file, write_pcf_file, hex_dump_file};
 
 fn main() -> std::io::Result<()> {
     let input_path = "TEST1.pcf";
@@ -13,10 +9,13 @@ fn main() -> std::io::Result<()> {
 
     let reloaded = parse_pcf_file(output_path)?;
 
-    if original == reloaded {
-        println!("Roundtrip success: data matches");
-    } else {
-        println!("Roundtrip mismatch!");
+    match original.first_diff_field(&reloaded) {
+        None => println!("Roundtrip success: data matches"),
+        Some("pattern_data") => {
+            let (bit, col) = original.first_pattern_diff(&reloaded).unwrap_or_default();
+            println!("Roundtrip mismatch in pattern_data at bit {}, column {}", bit, col);
+        }
+        Some(field) => println!("Roundtrip mismatch in {}", field),
     }
 
     let file1 = "TEST1.pcf";
@@ -0,0 +1,327 @@
+//! Bit-packed storage for pattern data, behind the `packed` feature.
+//!
+//! [`PatternFileData::pattern_data`] keeps one byte per bit value, which is
+//! simple to index and serialize but takes eight times the memory the 0/1
+//! values need. [`PackedPattern`] holds the same `[bit][col]` grid at one bit
+//! per value in a [`BitVec`], for tools that keep very large patterns in
+//! memory. [`decode_packed_with`] reads a record straight into one without
+//! ever building the byte grid and [`encode_packed_with`] writes from it;
+//! convert an already decoded record with [`PatternFileData::to_packed`] and
+//! [`PatternFileData::set_packed`].
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use bitvec::order::Lsb0;
+use bitvec::vec::BitVec;
+
+use crate::codec::{self, ByteRead, ByteWrite, CodecError, DecodeOptions, PatternStore};
+use crate::layout::{PcfLayout, NUM_BITS, PATTERN_PAD_COLS};
+use crate::pattern::PatternFileData;
+
+/// [`NUM_BITS`] rows of `cols` values, stored row after row at one bit each.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedPattern {
+    cols: usize,
+    bits: BitVec<u64, Lsb0>,
+}
+
+impl PackedPattern {
+    /// An all-zero pattern of `cols` columns.
+    pub fn new(cols: usize) -> Self {
+        PackedPattern { cols, bits: BitVec::repeat(false, cols * NUM_BITS) }
+    }
+
+    /// Packs `[bit][col]` rows, failing unless there are [`NUM_BITS`] rows of
+    /// equal length holding only 0 and 1.
+    pub fn from_rows(rows: &[Vec<u8>]) -> Result<Self, String> {
+        if rows.len() != NUM_BITS {
+            return Err(format!("pattern must have {} rows (found {})", NUM_BITS, rows.len()));
+        }
+        let cols = rows[0].len();
+        let mut packed = PackedPattern::new(cols);
+        for (bit, row) in rows.iter().enumerate() {
+            if row.len() != cols {
+                return Err(format!("row {} has {} columns, expected {}", bit, row.len(), cols));
+            }
+            for (col, &v) in row.iter().enumerate() {
+                match v {
+                    0 => {}
+                    1 => packed.set(bit, col, true),
+                    _ => return Err(format!("pattern_data[{}][{}] is {}; only 0 and 1 can be packed", bit, col, v)),
+                }
+            }
+        }
+        Ok(packed)
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Value of `bit` in column `col`, or `None` if either is out of range.
+    pub fn get(&self, bit: usize, col: usize) -> Option<u8> {
+        if bit >= NUM_BITS || col >= self.cols {
+            return None;
+        }
+        Some(u8::from(self.bits[bit * self.cols + col]))
+    }
+
+    /// Sets or clears `bit` in column `col`.
+    ///
+    /// # Panics
+    /// If `bit` or `col` is out of range.
+    pub fn set(&mut self, bit: usize, col: usize, value: bool) {
+        assert!(bit < NUM_BITS && col < self.cols, "bit {} / column {} is out of range", bit, col);
+        self.bits.set(bit * self.cols + col, value);
+    }
+
+    /// The 18 bit values of a single column, as
+    /// [`PatternFileData::column`] returns them.
+    pub fn column(&self, col: usize) -> Option<[u8; NUM_BITS]> {
+        if col >= self.cols {
+            return None;
+        }
+        Some(core::array::from_fn(|bit| self.get(bit, col).unwrap_or(0)))
+    }
+
+    /// Iterates over every column in file order.
+    pub fn columns(&self) -> impl Iterator<Item = [u8; NUM_BITS]> + '_ {
+        (0..self.cols).filter_map(move |col| self.column(col))
+    }
+
+    /// Columns in which `bit` is set, counted a word at a time.
+    pub fn count_ones(&self, bit: usize) -> Option<usize> {
+        self.bits.get(bit * self.cols..(bit + 1) * self.cols).map(|row| row.count_ones())
+    }
+
+    /// Unpacks into one byte per value, the layout of
+    /// [`PatternFileData::pattern_data`].
+    pub fn to_rows(&self) -> Vec<Vec<u8>> {
+        (0..NUM_BITS)
+            .map(|bit| (0..self.cols).map(|col| self.get(bit, col).unwrap_or(0)).collect())
+            .collect()
+    }
+
+    /// Heap bytes used by the packed words.
+    pub fn heap_bytes(&self) -> usize {
+        core::mem::size_of_val(self.bits.as_raw_slice())
+    }
+}
+
+impl PatternStore for PackedPattern {
+    fn zeroed(cols: usize) -> Self {
+        PackedPattern::new(cols)
+    }
+
+    fn bytes_for(cols: usize) -> usize {
+        cols.saturating_mul(NUM_BITS).div_ceil(u64::BITS as usize) * core::mem::size_of::<u64>()
+    }
+
+    fn put(&mut self, bit: usize, col: usize, value: u8) -> Result<(), CodecError> {
+        match value {
+            0 => Ok(()),
+            1 => {
+                self.set(bit, col, true);
+                Ok(())
+            }
+            _ => Err(CodecError::InvalidData(format!(
+                "bit {} of column {} is {}; only 0 and 1 can be packed", bit, col, value
+            ))),
+        }
+    }
+
+    fn value(&self, bit: usize, col: usize) -> u8 {
+        u8::from(self.bits[bit * self.cols + col])
+    }
+
+    fn check_cols(&self, cols: usize) -> Result<(), String> {
+        if self.cols != cols {
+            return Err(format!("the header implies {} columns, but the pattern has {}", cols, self.cols));
+        }
+        Ok(())
+    }
+}
+
+/// A record whose pattern lives in a [`PackedPattern`]. `header` carries
+/// every other field; its `pattern_data` stays empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedPatternFile {
+    pub header: PatternFileData,
+    pub pattern: PackedPattern,
+}
+
+impl PackedPatternFile {
+    /// Splits a decoded record into its header and packed pattern; fails as
+    /// [`PackedPattern::from_rows`] does.
+    pub fn from_unpacked(data: &PatternFileData) -> Result<Self, String> {
+        let pattern = data.to_packed()?;
+        let header = PatternFileData { pattern_data: Vec::new(), ..data.clone() };
+        Ok(PackedPatternFile { header, pattern })
+    }
+
+    /// The record with its pattern unpacked into `pattern_data`.
+    pub fn unpack(&self) -> PatternFileData {
+        let mut data = self.header.clone();
+        data.pattern_data = self.pattern.to_rows();
+        data
+    }
+
+    pub fn cols(&self) -> usize {
+        self.pattern.cols()
+    }
+
+    pub fn column(&self, col: usize) -> Option<[u8; NUM_BITS]> {
+        self.pattern.column(col)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = [u8; NUM_BITS]> + '_ {
+        self.pattern.columns()
+    }
+}
+
+/// [`decode_with`](codec::decode_with) into a [`PackedPatternFile`]. The
+/// `max_pattern_bytes` cap applies to the packed size, and a pattern value
+/// other than 0 or 1 is an `InvalidData` error.
+pub fn decode_packed_with<R: ByteRead + ?Sized>(reader: &mut R, opts: &DecodeOptions) -> Result<PackedPatternFile, CodecError> {
+    let (header, pattern) = codec::decode_into::<R, PackedPattern>(reader, opts)?;
+    Ok(PackedPatternFile { header, pattern })
+}
+
+/// [`encode_with`](codec::encode_with) for a [`PackedPatternFile`]; the
+/// output is byte for byte what the unpacked record encodes to. Fails if the
+/// header's `pattern_file_length` does not match the pattern's columns.
+pub fn encode_packed_with<W: ByteWrite + ?Sized>(writer: &mut W, file: &PackedPatternFile, layout: &PcfLayout) -> Result<(), CodecError> {
+    codec::encode_from(writer, &file.header, &file.pattern, layout)
+}
+
+#[cfg(feature = "std")]
+pub use self::std_io::*;
+
+/// File entry points for packed records, mirroring
+/// [`parse_pcf_file_with`](crate::pattern::parse_pcf_file_with) and
+/// [`write_pcf_file`](crate::pattern::write_pcf_file).
+#[cfg(feature = "std")]
+mod std_io {
+    use std::fs::File;
+    use std::io::{self, BufReader};
+    use std::path::Path;
+
+    use super::{decode_packed_with, encode_packed_with, PackedPatternFile};
+    use crate::codec::DecodeOptions;
+    use crate::layout::PcfLayout;
+    use crate::pattern::write_atomic;
+
+    /// Parses a PCF file straight into packed storage; `total_len` is
+    /// always taken from the file.
+    pub fn parse_pcf_file_packed<P: AsRef<Path>>(filename: P, opts: &DecodeOptions) -> io::Result<PackedPatternFile> {
+        let file = File::open(filename)?;
+        let opts = DecodeOptions { total_len: Some(file.metadata()?.len() as usize), ..opts.clone() };
+        Ok(decode_packed_with(&mut BufReader::new(file), &opts)?)
+    }
+
+    /// Writes a packed record, replacing `filename` atomically as
+    /// [`write_pcf_file`](crate::pattern::write_pcf_file) does.
+    pub fn write_pcf_file_packed<P: AsRef<Path>>(filename: P, file: &PackedPatternFile) -> io::Result<()> {
+        write_atomic(filename.as_ref(), |writer| Ok(encode_packed_with(writer, file, &PcfLayout::default())?))
+    }
+}
+
+impl PatternFileData {
+    /// The pattern in packed form; fails if any value is other than 0 or 1
+    /// (see [`PackedPattern::from_rows`]).
+    pub fn to_packed(&self) -> Result<PackedPattern, String> {
+        PackedPattern::from_rows(&self.pattern_data)
+    }
+
+    /// Replaces the pattern with `packed` and updates `pattern_file_length`
+    /// to match, like [`set_columns`](Self::set_columns).
+    pub fn set_packed(&mut self, packed: &PackedPattern) {
+        self.pattern_data = packed.to_rows();
+        self.pattern_file_length = packed.cols().saturating_sub(PATTERN_PAD_COLS) as i32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_and_unpacks_the_same_grid() {
+        let mut data = PatternFileData::builder().pattern_file_length(130).build().unwrap();
+        data.pattern_data[0][0] = 1;
+        data.pattern_data[5][63] = 1;
+        data.pattern_data[5][64] = 1;
+        data.pattern_data[17][149] = 1;
+
+        let packed = data.to_packed().unwrap();
+        assert_eq!(packed.cols(), 150);
+        assert_eq!(packed.heap_bytes(), (NUM_BITS * 150).div_ceil(64) * 8);
+        assert_eq!(packed.column(64), data.column(64));
+        assert_eq!(packed.get(17, 149), Some(1));
+        assert_eq!(packed.get(17, 150), None);
+        assert_eq!(packed.count_ones(5), Some(2));
+        assert!(packed.columns().eq(data.columns()));
+
+        let mut copy = PatternFileData::new();
+        copy.set_packed(&packed);
+        assert_eq!(copy.pattern_data, data.pattern_data);
+        assert_eq!(copy.pattern_file_length, 130);
+    }
+
+    #[test]
+    fn rejects_values_it_cannot_pack() {
+        let mut data = PatternFileData::new();
+        data.pattern_data[2][7] = 0x41;
+        let err = data.to_packed().unwrap_err();
+        assert!(err.starts_with("pattern_data[2][7] is 65"), "{}", err);
+        assert!(PackedPattern::from_rows(&[]).is_err());
+    }
+
+    #[test]
+    fn decodes_into_and_encodes_from_packed_storage() {
+        use crate::layout::PatternEncoding;
+
+        for encoding in [PatternEncoding::BytesPerBit, PatternEncoding::PackedBits] {
+            let mut data = PatternFileData::builder().pattern_file_length(50).build().unwrap();
+            data.encoding = encoding;
+            data.trailer = b"END".to_vec();
+            data.pattern_data[3][0] = 1;
+            data.pattern_data[17][69] = 1;
+            let bytes = crate::codec::encode_to_vec(&data).unwrap();
+
+            let opts = DecodeOptions { total_len: Some(bytes.len()), keep_trailer: true, encoding: Some(encoding), ..Default::default() };
+            let file = decode_packed_with(&mut &bytes[..], &opts).unwrap();
+            assert!(file.header.pattern_data.is_empty());
+            assert_eq!(file, PackedPatternFile::from_unpacked(&data).unwrap());
+            assert_eq!(file.unpack(), data);
+
+            let mut out = Vec::new();
+            encode_packed_with(&mut out, &file, &PcfLayout::default()).unwrap();
+            assert_eq!(out, bytes);
+        }
+    }
+
+    #[test]
+    fn packed_decode_rejects_non_binary_values_and_applies_the_cap() {
+        let mut data = PatternFileData::new();
+        data.pattern_data[2][7] = 0x41;
+        let bytes = crate::codec::encode_to_vec(&data).unwrap();
+        let err = decode_packed_with(&mut &bytes[..], &DecodeOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("bit 2 of column 7 is 65"), "{}", err);
+
+        // 20 columns fit in 48 packed bytes but not in 360 unpacked ones.
+        let bytes = crate::codec::encode_to_vec(&PatternFileData::new()).unwrap();
+        let opts = DecodeOptions { max_pattern_bytes: Some(48), ..Default::default() };
+        assert!(decode_packed_with(&mut &bytes[..], &opts).is_ok());
+        assert!(crate::codec::decode_with(&mut &bytes[..], &opts).is_err());
+    }
+
+    #[test]
+    fn encode_rejects_a_length_that_disagrees_with_the_pattern() {
+        let mut file = PackedPatternFile::from_unpacked(&PatternFileData::new()).unwrap();
+        file.header.pattern_file_length = 5;
+        assert!(encode_packed_with(&mut Vec::new(), &file, &PcfLayout::default()).is_err());
+    }
+}
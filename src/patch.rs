@@ -0,0 +1,198 @@
+//! Scripted edits of a [`PatternFileData`] through its JSON form.
+//!
+//! [`PatternFileData::apply_patch`] takes either an RFC 6902 JSON Patch (an
+//! array of `{"op", "path", ...}` operations) or a plain object mapping
+//! fields to new values:
+//!
+//! ```json
+//! {"version": "v2", "/loop_counts/3": 5}
+//! ```
+//!
+//! Keys starting with `/` are JSON Pointers; any other key names a top-level
+//! field. Each entry replaces an existing value, except that a field from
+//! [`FIELD_NAMES`] is set even when the JSON form leaves it out because it
+//! holds its default (`encoding`, `trailer`).
+
+use serde_json::Value;
+
+use crate::pattern::{PatternFileData, FIELD_NAMES};
+
+impl PatternFileData {
+    /// Applies `patch` (see the [module docs](self)) and checks the result
+    /// with [`check_shape`](Self::check_shape). On error `self` is unchanged.
+    pub fn apply_patch(&mut self, patch: &Value) -> Result<(), String> {
+        let mut doc = serde_json::to_value(&*self).map_err(|e| e.to_string())?;
+        match patch {
+            Value::Array(ops) => {
+                for (i, op) in ops.iter().enumerate() {
+                    apply_op(&mut doc, op).map_err(|msg| format!("operation {}: {}", i, msg))?;
+                }
+            }
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    let result = if key.starts_with('/') {
+                        replace(&mut doc, key, value.clone())
+                    } else if FIELD_NAMES.contains(&key.as_str()) {
+                        add(&mut doc, &format!("/{}", key), value.clone())
+                    } else {
+                        replace(&mut doc, &format!("/{}", key), value.clone())
+                    };
+                    result.map_err(|msg| format!("`{}`: {}", key, msg))?;
+                }
+            }
+            _ => return Err("patch must be an array of operations or an object of fields".into()),
+        }
+
+        let patched: PatternFileData = serde_json::from_value(doc).map_err(|e| format!("patched record is invalid: {}", e))?;
+        patched.check_shape()?;
+        *self = patched;
+        Ok(())
+    }
+}
+
+fn apply_op(doc: &mut Value, op: &Value) -> Result<(), String> {
+    let str_member = |name: &str| {
+        op.get(name)
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("missing string member `{}`", name))
+    };
+    let value = || op.get("value").cloned().ok_or_else(|| "missing member `value`".to_string());
+    let path = str_member("path")?;
+
+    match str_member("op")? {
+        "add" => add(doc, path, value()?),
+        "remove" => remove(doc, path).map(drop),
+        "replace" => replace(doc, path, value()?),
+        "move" => {
+            let taken = remove(doc, str_member("from")?)?;
+            add(doc, path, taken)
+        }
+        "copy" => {
+            let from = str_member("from")?;
+            let copied = doc.pointer(from).cloned().ok_or_else(|| format!("`{}` does not exist", from))?;
+            add(doc, path, copied)
+        }
+        "test" => match doc.pointer(path) {
+            Some(actual) if *actual == value()? => Ok(()),
+            Some(actual) => Err(format!("test failed: `{}` is {}", path, actual)),
+            None => Err(format!("test failed: `{}` does not exist", path)),
+        },
+        other => Err(format!("unknown op `{}`", other)),
+    }
+}
+
+/// Splits a JSON Pointer into its parent pointer and unescaped last token.
+fn split_pointer(path: &str) -> Result<(&str, String), String> {
+    let (parent, last) = path.rsplit_once('/').ok_or_else(|| format!("`{}` is not a JSON Pointer", path))?;
+    Ok((parent, last.replace("~1", "/").replace("~0", "~")))
+}
+
+fn parent_mut<'a>(doc: &'a mut Value, parent: &str) -> Result<&'a mut Value, String> {
+    doc.pointer_mut(parent).ok_or_else(|| format!("`{}` does not exist", parent))
+}
+
+fn array_index(token: &str, len: usize) -> Result<usize, String> {
+    token
+        .parse::<usize>()
+        .ok()
+        .filter(|&i| i < len)
+        .ok_or_else(|| format!("`{}` is not an index below {}", token, len))
+}
+
+fn add(doc: &mut Value, path: &str, value: Value) -> Result<(), String> {
+    if path.is_empty() {
+        *doc = value;
+        return Ok(());
+    }
+    let (parent, token) = split_pointer(path)?;
+    match parent_mut(doc, parent)? {
+        Value::Object(map) => {
+            map.insert(token, value);
+        }
+        Value::Array(items) if token == "-" => items.push(value),
+        Value::Array(items) => {
+            let i = array_index(&token, items.len() + 1)?;
+            items.insert(i, value);
+        }
+        _ => return Err(format!("`{}` is not an object or array", parent)),
+    }
+    Ok(())
+}
+
+fn remove(doc: &mut Value, path: &str) -> Result<Value, String> {
+    let (parent, token) = split_pointer(path)?;
+    match parent_mut(doc, parent)? {
+        Value::Object(map) => map.remove(&token).ok_or_else(|| format!("`{}` does not exist", path)),
+        Value::Array(items) => {
+            let i = array_index(&token, items.len())?;
+            Ok(items.remove(i))
+        }
+        _ => Err(format!("`{}` is not an object or array", parent)),
+    }
+}
+
+fn replace(doc: &mut Value, path: &str, value: Value) -> Result<(), String> {
+    let slot = doc.pointer_mut(path).ok_or_else(|| format!("`{}` does not exist", path))?;
+    *slot = value;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> PatternFileData {
+        PatternFileData::new()
+    }
+
+    #[test]
+    fn applies_json_patch_operations() {
+        let mut data = sample();
+        let patch = json!([
+            {"op": "replace", "path": "/version", "value": "v2"},
+            {"op": "replace", "path": "/loop_counts/3", "value": 5},
+            // `add` into an array inserts, so drop the last element to keep eight
+            {"op": "copy", "from": "/loop_counts/3", "path": "/start_addrs/0"},
+            {"op": "remove", "path": "/start_addrs/8"},
+            {"op": "move", "from": "/end_addrs/7", "path": "/end_addrs/0"},
+            {"op": "test", "path": "/version", "value": "v2"},
+        ]);
+        data.apply_patch(&patch).unwrap();
+        assert_eq!(data.version, "v2");
+        assert_eq!(data.loop_counts[3], 5);
+        assert_eq!(data.start_addrs, [5, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn applies_field_map() {
+        let mut data = sample();
+        data.apply_patch(&json!({"version": "v3", "/cycle_time/8": "4000"})).unwrap();
+        assert_eq!(data.version, "v3");
+        assert_eq!(data.cycle_time[8], "4000");
+    }
+
+    #[test]
+    fn field_map_sets_fields_serialized_only_when_not_default() {
+        let mut data = sample();
+        data.apply_patch(&json!({"encoding": "PackedBits"})).unwrap();
+        assert_eq!(data.encoding, crate::layout::PatternEncoding::PackedBits);
+
+        data.apply_patch(&json!({"trailer": [69, 78, 68]})).unwrap();
+        assert_eq!(data.trailer, b"END");
+    }
+
+    #[test]
+    fn failed_patch_leaves_record_unchanged() {
+        let mut data = sample();
+        let before = data.clone();
+
+        let err = data.apply_patch(&json!({"version": "v2", "pattern_file_length": 5})).unwrap_err();
+        assert!(err.contains("pattern_data[0]"), "{}", err);
+        let err = data.apply_patch(&json!([{"op": "test", "path": "/version", "value": "nope"}])).unwrap_err();
+        assert!(err.starts_with("operation 0: test failed"), "{}", err);
+        assert!(data.apply_patch(&json!({"colour": "red"})).is_err());
+        assert!(data.apply_patch(&json!([{"op": "remove", "path": "/loop_counts/0"}])).is_err());
+        assert_eq!(data, before);
+    }
+}
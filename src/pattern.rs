@@ -1,11 +1,25 @@
-use std::fs::File;
-use std::io::{self, BufReader, BufWriter, Read, Write};
-use std::path::Path;
-use std::str::SplitN;
-use byteorder::ReadBytesExt;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+use crate::inspect::HeaderField;
+use crate::layout::{FieldDelimiter, PatternEncoding, PcfLayout, FIELD_WIDTH, NUM_BITS, NUM_CLK_SOURCES, NUM_LOOPS, NUM_TIMINGS, PATTERN_PAD_COLS};
+
+#[cfg(feature = "std")]
+pub use self::std_io::*;
+
+/// One decoded PCF record.
+///
+/// Fields stay public for reading and in-place edits, but the struct is
+/// `#[non_exhaustive]`: outside this crate, start from
+/// [`PatternFileData::new`] or [`PatternFileData::builder`] (or decode a
+/// file) and prefer the accessor methods, so fields can be added without
+/// breaking callers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct PatternFileData {
     pub compiled_flag: bool,
     pub version: String,
@@ -20,6 +34,796 @@ pub struct PatternFileData {
     pub loop_counts: [i32; 8],
     pub pattern_file_length: i32,
     pub pattern_data: Vec<Vec<u8>>, // [bit][col]
+    /// On-disk encoding of the pattern region; written back the same way.
+    #[serde(default, skip_serializing_if = "PatternEncoding::is_bytes_per_bit")]
+    pub encoding: PatternEncoding,
+    /// Bytes found after the pattern region (a footer or checksum), kept only
+    /// when decoding with [`DecodeOptions::keep_trailer`](crate::codec::DecodeOptions::keep_trailer)
+    /// and written back after the pattern.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trailer: Vec<u8>,
+}
+
+impl PatternFileData {
+    /// An empty record that can be written as is: 65 blank clock sources and
+    /// a zero-length pattern (just the pad columns, all zero).
+    pub fn new() -> Self {
+        let mut data = PatternFileData { clk_sources: vec![String::new(); NUM_CLK_SOURCES + 1], ..Default::default() };
+        data.clear_pattern();
+        data
+    }
+
+    pub fn compiled_flag(&self) -> bool {
+        self.compiled_flag
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn source_combo_index(&self) -> i32 {
+        self.source_combo_index
+    }
+
+    pub fn pclk_source_indices(&self) -> &[i32; NUM_LOOPS] {
+        &self.pclk_source_indices
+    }
+
+    pub fn vtime_reqd(&self) -> &[String; NUM_TIMINGS] {
+        &self.vtime_reqd
+    }
+
+    pub fn cycle_time(&self) -> &[String; NUM_TIMINGS] {
+        &self.cycle_time
+    }
+
+    pub fn pulse_time(&self) -> &[String; NUM_TIMINGS] {
+        &self.pulse_time
+    }
+
+    /// Clock-source names by slot; index 0 is unused.
+    pub fn clk_sources(&self) -> &[String] {
+        &self.clk_sources
+    }
+
+    pub fn start_addrs(&self) -> &[i32; NUM_LOOPS] {
+        &self.start_addrs
+    }
+
+    pub fn end_addrs(&self) -> &[i32; NUM_LOOPS] {
+        &self.end_addrs
+    }
+
+    pub fn loop_counts(&self) -> &[i32; NUM_LOOPS] {
+        &self.loop_counts
+    }
+
+    /// Stored length, which excludes the trailing pad columns; see [`cols`](Self::cols).
+    pub fn pattern_file_length(&self) -> i32 {
+        self.pattern_file_length
+    }
+
+    /// The pattern as `[bit][col]`.
+    pub fn pattern_data(&self) -> &[Vec<u8>] {
+        &self.pattern_data
+    }
+
+    pub fn encoding(&self) -> PatternEncoding {
+        self.encoding
+    }
+
+    pub fn trailer(&self) -> &[u8] {
+        &self.trailer
+    }
+
+    /// Number of pattern columns implied by `pattern_file_length`.
+    pub fn cols(&self) -> usize {
+        (self.pattern_file_length + PATTERN_PAD_COLS as i32).max(0) as usize
+    }
+
+    /// Sets every pattern byte to zero, sized to the header's column count.
+    pub fn clear_pattern(&mut self) {
+        self.fill_pattern(0);
+    }
+
+    /// Sets every pattern byte to `value`, sized to the header's column count.
+    pub fn fill_pattern(&mut self, value: u8) {
+        self.pattern_data = vec![vec![value; self.cols()]; NUM_BITS];
+    }
+
+    /// True when every pattern byte is zero, which usually means the generator
+    /// never populated it. A pattern with `pattern_file_length == 0` is never blank.
+    pub fn is_pattern_blank(&self) -> bool {
+        self.pattern_file_length > 0
+            && self.pattern_data.iter().all(|row| row.iter().all(|&b| b == 0))
+    }
+
+    /// Slot (1–64) of the first clock source named `name`.
+    pub fn clk_slot(&self, name: &str) -> Option<usize> {
+        (1..self.clk_sources.len()).find(|&i| self.clk_sources[i] == name)
+    }
+
+    /// Renames clock source `slot` (1–64) and returns its previous name.
+    ///
+    /// `pclk_source_indices` refer to slots by number, so they keep pointing
+    /// at the renamed entry and are left unchanged.
+    pub fn rename_clk_source(&mut self, slot: usize, name: &str) -> Result<String, String> {
+        if !(1..=NUM_CLK_SOURCES).contains(&slot) || slot >= self.clk_sources.len() {
+            return Err(format!("clock slot {} is out of range 1..={}", slot, NUM_CLK_SOURCES));
+        }
+        if name.len() > FIELD_WIDTH {
+            return Err(format!("clock name `{}` is longer than {} bytes", name, FIELD_WIDTH));
+        }
+        Ok(core::mem::replace(&mut self.clk_sources[slot], name.into()))
+    }
+
+    /// Sets `bit` to 0 in every column, leaving other bits and the header alone.
+    pub fn clear_bit(&mut self, bit: usize) -> Result<(), String> {
+        self.fill_bit(bit, 0)
+    }
+
+    /// Sets `bit` to 1 in every column, leaving other bits and the header alone.
+    pub fn set_bit_all(&mut self, bit: usize) -> Result<(), String> {
+        self.fill_bit(bit, 1)
+    }
+
+    fn fill_bit(&mut self, bit: usize, value: u8) -> Result<(), String> {
+        if bit >= NUM_BITS {
+            return Err(format!("bit {} is out of range 0..{}", bit, NUM_BITS));
+        }
+        let row = self
+            .pattern_data
+            .get_mut(bit)
+            .ok_or_else(|| format!("pattern has no row for bit {}", bit))?;
+        row.fill(value);
+        Ok(())
+    }
+
+    /// Row-major view of one bit's values across every column.
+    pub fn row_major(&self, bit: usize) -> Option<&[u8]> {
+        self.pattern_data.get(bit).map(Vec::as_slice)
+    }
+
+    /// The 18 bit values of a single column (cycle).
+    pub fn column(&self, col: usize) -> Option<[u8; NUM_BITS]> {
+        let mut out = [0u8; NUM_BITS];
+        for (bit, slot) in out.iter_mut().enumerate() {
+            *slot = *self.pattern_data.get(bit)?.get(col)?;
+        }
+        Some(out)
+    }
+
+    /// Iterates over every column in file order.
+    pub fn columns(&self) -> impl Iterator<Item = [u8; NUM_BITS]> + '_ {
+        let cols = self.pattern_data.iter().map(Vec::len).min().unwrap_or(0);
+        (0..cols).filter_map(move |col| self.column(col))
+    }
+
+    /// Replaces the pattern with `columns` and updates `pattern_file_length`
+    /// to match (the stored length excludes the trailing pad columns).
+    pub fn set_columns(&mut self, columns: &[[u8; NUM_BITS]]) {
+        let mut pattern_data: Vec<Vec<u8>> = (0..NUM_BITS)
+            .map(|_| Vec::with_capacity(columns.len()))
+            .collect();
+        for col in columns {
+            for (row, &v) in pattern_data.iter_mut().zip(col.iter()) {
+                row.push(v);
+            }
+        }
+        self.pattern_data = pattern_data;
+        self.pattern_file_length = columns.len().saturating_sub(PATTERN_PAD_COLS) as i32;
+    }
+
+    /// Inclusive cycle range of loop `i`, or `None` if the loop is unused
+    /// (both addresses zero) or its range is empty or negative.
+    pub fn loop_range(&self, i: usize) -> Option<(usize, usize)> {
+        let start = *self.start_addrs.get(i)?;
+        let end = *self.end_addrs.get(i)?;
+        if (start == 0 && end == 0) || start < 0 || end < start {
+            return None;
+        }
+        Some((start as usize, end as usize))
+    }
+
+    /// Index of the loop whose [`loop_range`](Self::loop_range) contains
+    /// `cycle`, or `None` if no loop does. With overlapping loops the lowest
+    /// index wins.
+    pub fn loop_of_cycle(&self, cycle: usize) -> Option<usize> {
+        (0..NUM_LOOPS).find(|&i| self.loop_range(i).is_some_and(|(s, e)| (s..=e).contains(&cycle)))
+    }
+
+    /// A copy holding only columns `range`, followed by [`PATTERN_PAD_COLS`]
+    /// zero pad columns so the result can be written. Loops lying wholly
+    /// inside `range` are shifted to match; every other loop is cleared.
+    pub fn slice_columns(&self, range: core::ops::Range<usize>) -> Result<PatternFileData, String> {
+        let cols = self.cols();
+        if range.start > range.end || range.end > cols {
+            return Err(format!("columns {}..{} are outside the pattern ({} columns)", range.start, range.end, cols));
+        }
+
+        let mut columns: Vec<[u8; NUM_BITS]> = Vec::with_capacity(range.len() + PATTERN_PAD_COLS);
+        for col in range.clone() {
+            columns.push(self.column(col).ok_or_else(|| format!("pattern has no column {}", col))?);
+        }
+        columns.resize(range.len() + PATTERN_PAD_COLS, [0; NUM_BITS]);
+
+        let mut out = self.clone();
+        out.set_columns(&columns);
+        for i in 0..NUM_LOOPS {
+            match self.loop_range(i) {
+                Some((s, e)) if s >= range.start && e < range.end => {
+                    out.start_addrs[i] = (s - range.start) as i32;
+                    out.end_addrs[i] = (e - range.start) as i32;
+                }
+                _ => (out.start_addrs[i], out.end_addrs[i], out.loop_counts[i]) = (0, 0, 0),
+            }
+        }
+        Ok(out)
+    }
+
+    /// Loop `i`'s body as a pattern of its own (see
+    /// [`slice_columns`](Self::slice_columns)), with loop `i` covering all of
+    /// it and keeping its repeat count. The slot is kept so the loop's
+    /// `pclk_source_indices` entry still applies.
+    ///
+    /// Fails for a one-cycle body: it would become a loop from 0 to 0, which
+    /// [`loop_range`](Self::loop_range) reads as unused.
+    pub fn extract_loop(&self, i: usize) -> Result<PatternFileData, String> {
+        if i >= NUM_LOOPS {
+            return Err(format!("loop index {} is out of range (0-{})", i, NUM_LOOPS - 1));
+        }
+        let (start, end) = self.loop_range(i).ok_or_else(|| {
+            format!("loop {} is unused or empty (start {}, end {})", i, self.start_addrs[i], self.end_addrs[i])
+        })?;
+        if start == end {
+            return Err(format!(
+                "loop {} covers only cycle {}; a one-cycle loop at the start of a pattern reads back as unused", i, start
+            ));
+        }
+        let mut out = self.slice_columns(start..end + 1)?;
+        out.start_addrs = [0; NUM_LOOPS];
+        out.end_addrs = [0; NUM_LOOPS];
+        out.loop_counts = [0; NUM_LOOPS];
+        out.end_addrs[i] = (end - start) as i32;
+        out.loop_counts[i] = self.loop_counts[i];
+        Ok(out)
+    }
+
+    /// Pairs of loop indices `(i, j)` with `i < j` whose ranges intersect.
+    pub fn overlapping_loops(&self) -> Vec<(usize, usize)> {
+        let mut out = Vec::new();
+        for i in 0..NUM_LOOPS {
+            let Some((s1, e1)) = self.loop_range(i) else { continue };
+            for j in (i + 1)..NUM_LOOPS {
+                let Some((s2, e2)) = self.loop_range(j) else { continue };
+                if s1 <= e2 && s2 <= e1 {
+                    out.push((i, j));
+                }
+            }
+        }
+        out
+    }
+
+    /// Loops in execution order as `(index, start, end, repeat)`. A loop whose start
+    /// falls inside an earlier loop, or which runs past the pattern, is ignored;
+    /// a repeat count below 1 executes the body once.
+    pub(crate) fn execution_loops(&self) -> Vec<(usize, usize, usize, usize)> {
+        let cols = self.cols();
+        let mut loops: Vec<(usize, usize, usize, usize)> = (0..NUM_LOOPS)
+            .filter_map(|i| {
+                let (s, e) = self.loop_range(i)?;
+                (e < cols).then(|| (i, s, e, self.loop_counts[i].max(1) as usize))
+            })
+            .collect();
+        loops.sort_by_key(|&(_, s, _, _)| s);
+
+        let mut out: Vec<(usize, usize, usize, usize)> = Vec::new();
+        for l in loops {
+            if out.last().is_none_or(|&(_, _, e, _)| l.1 > e) {
+                out.push(l);
+            }
+        }
+        out
+    }
+
+    /// Number of cycles [`expand_loops`](Self::expand_loops) would produce,
+    /// computed without materializing them.
+    pub fn executed_cycles(&self) -> u64 {
+        let mut total = self.cols() as u64;
+        for (_, s, e, n) in self.execution_loops() {
+            total += (e - s + 1) as u64 * (n as u64 - 1);
+        }
+        total
+    }
+
+    /// Flattens the pattern into its executed cycle sequence, repeating each
+    /// loop body `loop_counts[i]` times.
+    pub fn expand_loops(&self) -> Vec<[u8; NUM_BITS]> {
+        let columns: Vec<[u8; NUM_BITS]> = self.columns().collect();
+        let mut out = Vec::with_capacity(self.executed_cycles() as usize);
+        let mut col = 0;
+
+        for (_, s, e, n) in self.execution_loops() {
+            out.extend_from_slice(&columns[col.min(s)..s]);
+            for _ in 0..n {
+                out.extend_from_slice(&columns[s..=e]);
+            }
+            col = e + 1;
+        }
+        out.extend_from_slice(&columns[col.min(columns.len())..]);
+        out
+    }
+
+    /// Bits that are set in at least one column.
+    pub fn active_bits(&self) -> [bool; NUM_BITS] {
+        let mut active = [false; NUM_BITS];
+        for (bit, slot) in active.iter_mut().enumerate() {
+            *slot = self.row_major(bit).is_some_and(|row| row.iter().any(|&v| v != 0));
+        }
+        active
+    }
+
+    /// Run-length encodes one bit's timeline as `(value, run_length)` pairs.
+    pub fn bit_runs(&self, bit: usize) -> Option<Vec<(u8, usize)>> {
+        let row = self.row_major(bit)?;
+        let mut runs: Vec<(u8, usize)> = Vec::new();
+        for &v in row {
+            match runs.last_mut() {
+                Some((last, n)) if *last == v => *n += 1,
+                _ => runs.push((v, 1)),
+            }
+        }
+        Some(runs)
+    }
+
+    /// How often each distinct column value occurs in the pattern.
+    #[cfg(feature = "std")]
+    pub fn column_frequencies(&self) -> std::collections::HashMap<[u8; NUM_BITS], usize> {
+        let mut counts = std::collections::HashMap::new();
+        for col in self.columns() {
+            *counts.entry(col).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Canonical copy for logical comparison: `a.normalize() == b.normalize()`
+    /// when both would write the same header and pattern.
+    ///
+    /// Applies what a write/parse round trip does: text fields (and the
+    /// flag/version record) are cut to [`FIELD_WIDTH`] bytes and lose trailing
+    /// padding, integers are re-read from their written text, `clk_sources` is
+    /// sized to 65 with the unused slot 0 cleared, and the pattern is sized to
+    /// [`NUM_BITS`] rows of [`cols`](Self::cols) columns, zero-filled. Packed
+    /// files store only bits, so their pattern bytes become 0/1.
+    pub fn normalize(&self) -> PatternFileData {
+        fn text(s: &str) -> String {
+            let bytes = &s.as_bytes()[..s.len().min(FIELD_WIDTH)];
+            crate::codec::trim_padding(&String::from_utf8_lossy(bytes)).into()
+        }
+        fn int(v: i32) -> i32 {
+            text(&format!("{}", v)).trim().parse().unwrap_or(0)
+        }
+
+        let record = text(&crate::codec::format_flag_version(self.compiled_flag, &self.version));
+        let version = record.split_once(' ').map_or("", |(_, v)| v).trim().into();
+
+        let mut clk_sources: Vec<String> = self.clk_sources.iter().map(|s| text(s)).collect();
+        clk_sources.resize(NUM_CLK_SOURCES + 1, String::new());
+        clk_sources[0].clear();
+
+        let pattern_file_length = int(self.pattern_file_length);
+        let cols = (pattern_file_length + PATTERN_PAD_COLS as i32).max(0) as usize;
+        let packed = !self.encoding.is_bytes_per_bit();
+        let pattern_data = (0..NUM_BITS)
+            .map(|bit| {
+                let mut row = self.pattern_data.get(bit).cloned().unwrap_or_default();
+                row.resize(cols, 0);
+                if packed {
+                    row.iter_mut().for_each(|v| *v = u8::from(*v != 0));
+                }
+                row
+            })
+            .collect();
+
+        PatternFileData {
+            compiled_flag: self.compiled_flag,
+            version,
+            source_combo_index: int(self.source_combo_index),
+            pclk_source_indices: self.pclk_source_indices.map(int),
+            vtime_reqd: self.vtime_reqd.each_ref().map(|s| text(s)),
+            cycle_time: self.cycle_time.each_ref().map(|s| text(s)),
+            pulse_time: self.pulse_time.each_ref().map(|s| text(s)),
+            clk_sources,
+            start_addrs: self.start_addrs.map(int),
+            end_addrs: self.end_addrs.map(int),
+            loop_counts: self.loop_counts.map(int),
+            pattern_file_length,
+            pattern_data,
+            encoding: self.encoding,
+            trailer: self.trailer.clone(),
+        }
+    }
+
+    /// Checks that the record can be written without losing anything:
+    /// `clk_sources` has 65 entries, the pattern has [`NUM_BITS`] rows of
+    /// [`cols`](Self::cols) bytes, `pattern_file_length` is at least -20, and
+    /// every field's text fits its [`FIELD_WIDTH`]-byte slot.
+    pub fn check_shape(&self) -> Result<(), String> {
+        self.check_shape_with(&PcfLayout::default())
+    }
+
+    /// Like [`check_shape`](Self::check_shape), with the field widths of
+    /// `layout`. Newline-delimited fields have no width limit, but their text
+    /// may not contain a newline.
+    pub fn check_shape_with(&self, layout: &PcfLayout) -> Result<(), String> {
+        use crate::codec::PatternStore;
+
+        self.check_header_with(layout)?;
+        self.pattern_data.check_cols(self.cols())
+    }
+
+    /// The checks of [`check_shape_with`](Self::check_shape_with) that do
+    /// not involve `pattern_data`.
+    pub(crate) fn check_header_with(&self, layout: &PcfLayout) -> Result<(), String> {
+        if self.clk_sources.len() != NUM_CLK_SOURCES + 1 {
+            return Err(format!("clk_sources must have exactly {} elements (found {})", NUM_CLK_SOURCES + 1, self.clk_sources.len()));
+        }
+        if self.pattern_file_length < -(PATTERN_PAD_COLS as i32) {
+            return Err(format!("pattern_file_length {} is below -{}", self.pattern_file_length, PATTERN_PAD_COLS));
+        }
+
+        let fixed = layout.delimiter == FieldDelimiter::FixedWidth;
+        let record = crate::codec::format_flag_version(self.compiled_flag, &self.version);
+        let texts = [("compiled_flag/version", &record)]
+            .into_iter()
+            .chain(self.vtime_reqd.iter().map(|s| ("vtime_reqd", s)))
+            .chain(self.cycle_time.iter().map(|s| ("cycle_time", s)))
+            .chain(self.pulse_time.iter().map(|s| ("pulse_time", s)))
+            .chain(self.clk_sources.iter().map(|s| ("clk_sources", s)));
+        for (name, text) in texts {
+            if fixed && text.len() > layout.string_width {
+                return Err(format!("{} value `{}` is longer than {} bytes", name, text, layout.string_width));
+            }
+            if !fixed && text.contains('\n') {
+                return Err(format!("{} value {:?} contains a newline", name, text));
+            }
+        }
+        let ints = [("source_combo_index", self.source_combo_index), ("pattern_file_length", self.pattern_file_length)]
+            .into_iter()
+            .chain(self.pclk_source_indices.iter().map(|&v| ("pclk_source_indices", v)))
+            .chain(self.start_addrs.iter().map(|&v| ("start_addrs", v)))
+            .chain(self.end_addrs.iter().map(|&v| ("end_addrs", v)))
+            .chain(self.loop_counts.iter().map(|&v| ("loop_counts", v)));
+        for (name, v) in ints {
+            if fixed && format!("{}", v).len() > layout.numeric_width {
+                return Err(format!("{} value {} is longer than {} digits", name, v, layout.numeric_width));
+            }
+        }
+        Ok(())
+    }
+
+    /// Name of the first field (in declaration order) that differs from
+    /// `other`, or `None` if the two are equal. See [`Self::first_pattern_diff`]
+    /// for the location within `pattern_data`.
+    pub fn first_diff_field(&self, other: &Self) -> Option<&'static str> {
+        self.diff_fields(other).into_iter().next()
+    }
+
+    /// Names of every field that differs from `other`, in declaration order.
+    pub fn diff_fields(&self, other: &Self) -> Vec<&'static str> {
+        let checks: [(&'static str, bool); 15] = [
+            ("compiled_flag", self.compiled_flag == other.compiled_flag),
+            ("version", self.version == other.version),
+            ("source_combo_index", self.source_combo_index == other.source_combo_index),
+            ("pclk_source_indices", self.pclk_source_indices == other.pclk_source_indices),
+            ("vtime_reqd", self.vtime_reqd == other.vtime_reqd),
+            ("cycle_time", self.cycle_time == other.cycle_time),
+            ("pulse_time", self.pulse_time == other.pulse_time),
+            ("clk_sources", self.clk_sources == other.clk_sources),
+            ("start_addrs", self.start_addrs == other.start_addrs),
+            ("end_addrs", self.end_addrs == other.end_addrs),
+            ("loop_counts", self.loop_counts == other.loop_counts),
+            ("pattern_file_length", self.pattern_file_length == other.pattern_file_length),
+            ("pattern_data", self.pattern_data == other.pattern_data),
+            ("encoding", self.encoding == other.encoding),
+            ("trailer", self.trailer == other.trailer),
+        ];
+        checks.iter().filter(|(_, same)| !same).map(|(name, _)| *name).collect()
+    }
+
+    /// Hash of the pattern contents alone, so files that differ only in
+    /// version, timing or clock metadata hash alike. Stable across runs (see
+    /// [`crate::hash`]); the on-disk encoding is not included.
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash_with(false)
+    }
+
+    /// [`content_hash`](Self::content_hash), also covering the loop
+    /// configuration when `include_loops` is set.
+    pub fn content_hash_with(&self, include_loops: bool) -> u64 {
+        use core::hash::Hasher;
+
+        let mut h = crate::hash::Fnv1a::default();
+        h.write_u64(self.pattern_data.len() as u64);
+        for row in &self.pattern_data {
+            h.write_u64(row.len() as u64);
+            h.write(row);
+        }
+        if include_loops {
+            for v in self.start_addrs.iter().chain(&self.end_addrs).chain(&self.loop_counts) {
+                h.write_i32(*v);
+            }
+        }
+        h.finish()
+    }
+
+    /// True when every field except `pattern_data`, `pattern_file_length` and
+    /// `trailer` matches `other`, i.e. both files share one configuration regardless of
+    /// their pattern contents.
+    pub fn header_eq(&self, other: &Self) -> bool {
+        self.compiled_flag == other.compiled_flag
+            && self.version == other.version
+            && self.source_combo_index == other.source_combo_index
+            && self.pclk_source_indices == other.pclk_source_indices
+            && self.vtime_reqd == other.vtime_reqd
+            && self.cycle_time == other.cycle_time
+            && self.pulse_time == other.pulse_time
+            && self.clk_sources == other.clk_sources
+            && self.start_addrs == other.start_addrs
+            && self.end_addrs == other.end_addrs
+            && self.loop_counts == other.loop_counts
+            && self.encoding == other.encoding
+    }
+
+    /// First `(bit, col)` where the pattern data differs from `other`; a
+    /// position present on only one side counts as a difference.
+    pub fn first_pattern_diff(&self, other: &Self) -> Option<(usize, usize)> {
+        let bits = self.pattern_data.len().max(other.pattern_data.len());
+        (0..bits).find_map(|bit| {
+            let a = self.pattern_data.get(bit).map_or(&[][..], |r| &r[..]);
+            let b = other.pattern_data.get(bit).map_or(&[][..], |r| &r[..]);
+            (0..a.len().max(b.len()))
+                .find(|&col| a.get(col) != b.get(col))
+                .map(|col| (bit, col))
+        })
+    }
+}
+
+/// JSON names of the serialized fields, in declaration order.
+pub const FIELD_NAMES: [&str; 15] = [
+    "compiled_flag",
+    "version",
+    "source_combo_index",
+    "pclk_source_indices",
+    "vtime_reqd",
+    "cycle_time",
+    "pulse_time",
+    "clk_sources",
+    "start_addrs",
+    "end_addrs",
+    "loop_counts",
+    "pattern_file_length",
+    "pattern_data",
+    "encoding",
+    "trailer",
+];
+
+/// Serializes the fields of `d` named in `names` (entries of [`FIELD_NAMES`]).
+fn serialize_fields<S: serde::Serializer>(d: &PatternFileData, names: &[&'static str], serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+
+    let mut s = serializer.serialize_struct("PatternFileData", names.len())?;
+    for &name in names {
+        match name {
+            "compiled_flag" => s.serialize_field(name, &d.compiled_flag)?,
+            "version" => s.serialize_field(name, &d.version)?,
+            "source_combo_index" => s.serialize_field(name, &d.source_combo_index)?,
+            "pclk_source_indices" => s.serialize_field(name, &d.pclk_source_indices)?,
+            "vtime_reqd" => s.serialize_field(name, &d.vtime_reqd)?,
+            "cycle_time" => s.serialize_field(name, &d.cycle_time)?,
+            "pulse_time" => s.serialize_field(name, &d.pulse_time)?,
+            "clk_sources" => s.serialize_field(name, &d.clk_sources)?,
+            "start_addrs" => s.serialize_field(name, &d.start_addrs)?,
+            "end_addrs" => s.serialize_field(name, &d.end_addrs)?,
+            "loop_counts" => s.serialize_field(name, &d.loop_counts)?,
+            "pattern_file_length" => s.serialize_field(name, &d.pattern_file_length)?,
+            "pattern_data" => s.serialize_field(name, &d.pattern_data)?,
+            "encoding" => s.serialize_field(name, &d.encoding)?,
+            "trailer" => s.serialize_field(name, &d.trailer)?,
+            _ => unreachable!("`{}` is not in FIELD_NAMES", name),
+        }
+    }
+    s.end()
+}
+
+/// Serializes every field of the wrapped data except `pattern_data`, for
+/// metadata-only consumers that do not want the (large) pattern array.
+/// `encoding` and `trailer` are left out while they hold their defaults,
+/// as in the derived form.
+pub struct HeaderOnly<'a>(pub &'a PatternFileData);
+
+impl Serialize for HeaderOnly<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let d = self.0;
+        let names: Vec<&'static str> = FIELD_NAMES
+            .into_iter()
+            .filter(|&name| match name {
+                "pattern_data" => false,
+                "encoding" => !d.encoding.is_bytes_per_bit(),
+                "trailer" => !d.trailer.is_empty(),
+                _ => true,
+            })
+            .collect();
+        serialize_fields(d, &names, serializer)
+    }
+}
+
+/// Serializes only the chosen fields, in the order requested. Built by
+/// [`PatternFileData::project`].
+pub struct Projection<'a> {
+    data: &'a PatternFileData,
+    fields: Vec<&'static str>,
+}
+
+impl PatternFileData {
+    /// A view that serializes just `names` (see [`FIELD_NAMES`]); errors on
+    /// the first unknown name.
+    pub fn project<S: AsRef<str>>(&self, names: &[S]) -> Result<Projection<'_>, String> {
+        let fields = names
+            .iter()
+            .map(|name| {
+                let name = name.as_ref().trim();
+                FIELD_NAMES
+                    .iter()
+                    .find(|&&f| f == name)
+                    .copied()
+                    .ok_or_else(|| format!("unknown field `{}` (expected one of {})", name, FIELD_NAMES.join(", ")))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Projection { data: self, fields })
+    }
+}
+
+impl Serialize for Projection<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_fields(self.data, &self.fields, serializer)
+    }
+}
+
+/// Serializes the record in file order: one entry per header field as
+/// [`HeaderField::all`] lists them, keyed by [`HeaderField::name`] (the flag
+/// record gives `compiled_flag` and `version`), then `encoding` when packed,
+/// `pattern_data` and `trailer` when present. Unlike the derived form, the
+/// order does not follow the struct's field order, so reordering the struct
+/// cannot change it.
+pub struct FileOrder<'a>(pub &'a PatternFileData);
+
+impl Serialize for FileOrder<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        use crate::inspect::HeaderField::*;
+
+        let d = self.0;
+        let mut m = serializer.serialize_map(None)?;
+        for field in HeaderField::all() {
+            let name = field.name();
+            match field {
+                CompiledFlag => {
+                    m.serialize_entry("compiled_flag", &d.compiled_flag)?;
+                    m.serialize_entry("version", &d.version)?;
+                }
+                SourceComboIndex => m.serialize_entry(&name, &d.source_combo_index)?,
+                PclkSourceIndex(i) => m.serialize_entry(&name, &d.pclk_source_indices[i])?,
+                VtimeReqd(i) => m.serialize_entry(&name, &d.vtime_reqd[i])?,
+                CycleTime(i) => m.serialize_entry(&name, &d.cycle_time[i])?,
+                PulseTime(i) => m.serialize_entry(&name, &d.pulse_time[i])?,
+                ClkSource(slot) => m.serialize_entry(&name, &d.clk_sources.get(slot))?,
+                StartAddr(i) => m.serialize_entry(&name, &d.start_addrs[i])?,
+                EndAddr(i) => m.serialize_entry(&name, &d.end_addrs[i])?,
+                LoopCount(i) => m.serialize_entry(&name, &d.loop_counts[i])?,
+                PatternFileLength => m.serialize_entry(&name, &d.pattern_file_length)?,
+            }
+        }
+        if !d.encoding.is_bytes_per_bit() {
+            m.serialize_entry("encoding", &d.encoding)?;
+        }
+        m.serialize_entry("pattern_data", &d.pattern_data)?;
+        if !d.trailer.is_empty() {
+            m.serialize_entry("trailer", &d.trailer)?;
+        }
+        m.end()
+    }
+}
+
+#[cfg(feature = "std")]
+impl PatternFileData {
+    /// Pretty JSON of [`FileOrder`]: every header field in on-disk order.
+    pub fn to_ordered_json(&self) -> String {
+        serde_json::to_string_pretty(&FileOrder(self)).expect("serializing to a String cannot fail")
+    }
+}
+
+/// Human-readable header summary, one `name: value` line per field; the
+/// pattern itself is reduced to its column count. Built by
+/// [`PatternFileData::summary`].
+pub struct Summary<'a> {
+    data: &'a PatternFileData,
+    max_width: Option<usize>,
+}
+
+impl PatternFileData {
+    /// Summary whose lines are cut to `max_width` characters (values that do
+    /// not fit end in `…`); `None` never truncates.
+    pub fn summary(&self, max_width: Option<usize>) -> Summary<'_> {
+        Summary { data: self, max_width }
+    }
+}
+
+/// `label` is padded to this many characters before the value.
+const SUMMARY_LABEL_WIDTH: usize = 21;
+
+impl Summary<'_> {
+    fn line(&self, f: &mut fmt::Formatter<'_>, label: &str, value: &str) -> fmt::Result {
+        let label = format!("{}:", label);
+        let room = self.max_width.map(|w| w.saturating_sub(SUMMARY_LABEL_WIDTH.max(label.len() + 1)));
+        match room {
+            Some(room) if value.chars().count() > room => {
+                let cut: String = value.chars().take(room.saturating_sub(1)).collect();
+                writeln!(f, "{:<w$}{}…", label, cut, w = SUMMARY_LABEL_WIDTH)
+            }
+            _ => writeln!(f, "{:<w$}{}", label, value, w = SUMMARY_LABEL_WIDTH),
+        }
+    }
+}
+
+/// Space-separated values; blank entries show as `-` so positions stay countable.
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|v| format!("{}", v))
+        .map(|v| if v.is_empty() { "-".into() } else { v })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = self.data;
+        self.line(f, "compiled_flag", if d.compiled_flag { "True" } else { "False" })?;
+        self.line(f, "version", &d.version)?;
+        self.line(f, "source_combo_index", &format!("{}", d.source_combo_index))?;
+        self.line(f, "pclk_source_indices", &join(&d.pclk_source_indices))?;
+        self.line(f, "vtime_reqd", &join(&d.vtime_reqd))?;
+        self.line(f, "cycle_time", &join(&d.cycle_time))?;
+        self.line(f, "pulse_time", &join(&d.pulse_time))?;
+
+        let named: Vec<String> = d.clk_sources.iter().enumerate().skip(1)
+            .filter(|(_, name)| !name.is_empty())
+            .map(|(slot, name)| format!("{}={}", slot, name))
+            .collect();
+        self.line(f, "clk_sources", &join(&named))?;
+
+        let loops: Vec<String> = (0..NUM_LOOPS)
+            .filter_map(|i| d.loop_range(i).map(|(s, e)| format!("{}:{}..={}×{}", i, s, e, d.loop_counts[i])))
+            .collect();
+        let loops = if loops.is_empty() { "none".into() } else { join(&loops) };
+        self.line(f, "loops", &loops)?;
+
+        self.line(f, "pattern_file_length", &format!("{} ({} columns)", d.pattern_file_length, d.cols()))?;
+        if !d.encoding.is_bytes_per_bit() {
+            self.line(f, "encoding", &format!("{:?}", d.encoding))?;
+        }
+        if !d.trailer.is_empty() {
+            self.line(f, "trailer", &format!("{} bytes", d.trailer.len()))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PatternFileData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.summary(None), f)
+    }
 }
 
 /*impl Default for PatternFileData {
@@ -42,229 +846,261 @@ pub struct PatternFileData {
     }
 }*/
 
-pub fn parse_pcf_file<P: AsRef<Path>>(filename: P) -> io::Result<PatternFileData> {
+/// `File`- and `std::io`-based entry points layered over [`crate::codec`].
+#[cfg(feature = "std")]
+mod std_io {
+    use std::fs::{self, File};
+    use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+    use std::path::{Path, PathBuf};
 
-    let file = File::open(filename)?;
-    let mut reader = BufReader::new(file);
+    use super::PatternFileData;
+    use crate::codec::{self, CodecError, DecodeOptions, DEFAULT_MAX_PATTERN_BYTES};
+    use crate::hash::CrcWriter;
+    use crate::layout::PcfLayout;
 
-    // Read a fixed length in as a string.
-    fn read_fixed(reader: &mut BufReader<File>, len: usize) -> io::Result<String> {
-        let mut buf = vec![0u8; len];
-        reader.read_exact(&mut buf)?;
-        Ok(String::from_utf8_lossy(&buf).trim_end().to_string())
+    /// Parses a PCF file. If the header's `pattern_file_length` disagrees with
+    /// the file size, the size wins and the length is corrected.
+    ///
+    /// Patterns larger than [`DEFAULT_MAX_PATTERN_BYTES`] in memory are
+    /// rejected; see [`parse_pcf_file_limited`] to choose the cap.
+    pub fn parse_pcf_file<P: AsRef<Path>>(filename: P) -> io::Result<PatternFileData> {
+        parse_file_with(filename.as_ref(), false, DEFAULT_MAX_PATTERN_BYTES)
     }
 
-    let compiled: String = read_fixed(&mut reader, 10)?;
-    let mut parts: SplitN<char> = compiled.splitn(2, ' ');
-
-    let flag: bool = parts
-        .next()
-        .unwrap_or("False")
-        .to_lowercase()
-        .parse()
-        .unwrap_or(false);
-
-    let version: String = parts
-        .next()
-        .unwrap_or("")
-        .to_string();
-
-    let source_combo_index: i32 = read_fixed(&mut reader, 10)?
-        .parse()
-        .unwrap_or(0);
-
-    let mut pclk_source_indices: [i32; 8] = [0; 8];
-    for i in 0..8 {
-        pclk_source_indices[i] = read_fixed(&mut reader, 10)?
-            .parse()
-            .unwrap_or(0);
+    /// Like [`parse_pcf_file`], but fails with `InvalidData` instead of
+    /// allocating a pattern region larger than `max_bytes` (one byte per bit).
+    /// Use this for files from untrusted sources.
+    pub fn parse_pcf_file_limited<P: AsRef<Path>>(filename: P, max_bytes: usize) -> io::Result<PatternFileData> {
+        parse_file_with(filename.as_ref(), false, max_bytes)
     }
 
-    let mut vtime_reqd: [String; 9] = Default::default();
-    vtime_reqd[8] = read_fixed(&mut reader, 10)?;
-    for i in 0..8 {
-        vtime_reqd[i] = read_fixed(&mut reader, 10)?;
+    /// Like [`parse_pcf_file`], but malformed numeric fields, an unrecognised
+    /// compiled flag, or a header that does not end at
+    /// [`header_size()`](crate::layout::header_size) are reported as
+    /// `InvalidData` errors instead of being defaulted.
+    /// A `pattern_file_length` that disagrees with the file size is also an error.
+    pub fn parse_pcf_file_strict<P: AsRef<Path>>(filename: P) -> io::Result<PatternFileData> {
+        parse_file_with(filename.as_ref(), true, DEFAULT_MAX_PATTERN_BYTES)
     }
 
-    let mut cycle_time: [String; 9] = Default::default();
-    cycle_time[8] = read_fixed(&mut reader, 10)?;
-    for i in 0..8 {
-        cycle_time[i] = read_fixed(&mut reader, 10)?;
+    /// Parses a PCF file with explicit [`DecodeOptions`]; `total_len` is
+    /// always taken from the file. Set
+    /// [`keep_trailer`](DecodeOptions::keep_trailer) to round-trip files that
+    /// carry a footer.
+    pub fn parse_pcf_file_with<P: AsRef<Path>>(filename: P, opts: &DecodeOptions) -> io::Result<PatternFileData> {
+        let file = File::open(filename)?;
+        let opts = DecodeOptions { total_len: Some(file.metadata()?.len() as usize), ..opts.clone() };
+        Ok(codec::decode_with(&mut BufReader::new(file), &opts)?)
     }
 
-    let mut pulse_time: [String; 9] = Default::default();
-    pulse_time[8] = read_fixed(&mut reader, 10)?;
-    for i in 0..8 {
-        pulse_time[i] = read_fixed(&mut reader, 10)?;
+    fn parse_file_with(path: &Path, strict: bool, max_bytes: usize) -> io::Result<PatternFileData> {
+        let opts = DecodeOptions { strict, max_pattern_bytes: Some(max_bytes), ..Default::default() };
+        parse_pcf_file_with(path, &opts)
     }
 
-    let mut clk_sources = vec![String::new(); 65];
-    for i in 1..=64 {
-        clk_sources[i] = read_fixed(&mut reader, 10)?;
+    fn parse_reader_with<R: Read>(mut reader: R, strict: bool) -> io::Result<PatternFileData> {
+        let opts = DecodeOptions { strict, max_pattern_bytes: Some(DEFAULT_MAX_PATTERN_BYTES), ..Default::default() };
+        Ok(codec::decode_with(&mut reader, &opts)?)
     }
 
-    let mut start_addrs: [i32; 8] = [0; 8];
-    let mut end_addrs: [i32; 8] = [0; 8];
-    let mut loop_counts: [i32; 8] = [0; 8];
-
-    for i in 0..8 {
-        start_addrs[i] = read_fixed(&mut reader, 10)?
-            .parse()
-            .unwrap_or(0);
-
-        end_addrs[i] = read_fixed(&mut reader, 10)?
-            .parse()
-            .unwrap_or(0);
+    pub fn parse_pcf_reader<R: Read>(reader: R) -> io::Result<PatternFileData> {
+        parse_reader_with(reader, false)
+    }
 
-        loop_counts[i] = read_fixed(&mut reader, 10)?
-            .parse()
-            .unwrap_or(0);
+    pub fn parse_pcf_reader_strict<R: Read>(reader: R) -> io::Result<PatternFileData> {
+        parse_reader_with(reader, true)
     }
 
-    let pattern_file_length = read_fixed(&mut reader, 10)?
-        .parse()
-        .unwrap_or(0);
-    let cols: usize = (pattern_file_length + 20) as usize;
+    /// Parses records stored back to back until the input ends. Each record's
+    /// `pattern_file_length` says where the next one starts. Input that ends
+    /// part-way through a record is an `UnexpectedEof` error naming the record
+    /// and its starting offset.
+    pub fn parse_pcf_multi<R: Read>(reader: R) -> io::Result<Vec<PatternFileData>> {
+        parse_pcf_multi_with(reader, &DecodeOptions { max_pattern_bytes: Some(DEFAULT_MAX_PATTERN_BYTES), ..Default::default() })
+    }
 
-    let mut pattern_data: Vec<Vec<u8>> = vec![vec![0u8; cols]; 18];
+    /// Like [`parse_pcf_multi`] with explicit [`DecodeOptions`], applied to
+    /// every record. `total_len` is ignored: each record's length comes from
+    /// its own header. With `keep_trailer`, input after the last record that
+    /// is too short to hold another header becomes that record's
+    /// [`trailer`](PatternFileData::trailer) instead of a truncation error.
+    pub fn parse_pcf_multi_with<R: Read>(reader: R, opts: &DecodeOptions) -> io::Result<Vec<PatternFileData>> {
+        let mut reader = BufReader::new(reader);
+        let opts = DecodeOptions { total_len: None, ..opts.clone() };
+        let mut records: Vec<PatternFileData> = Vec::new();
+        let mut offset = 0;
 
-    for col in 0..cols {
-        for bit in 0..18 {
-            pattern_data[bit][col] = reader.read_u8()?;
+        while !reader.fill_buf()?.is_empty() {
+            let mut head = Vec::new();
+            if opts.keep_trailer {
+                (&mut reader).take(opts.layout.header_size() as u64).read_to_end(&mut head)?;
+                if let Some(last) = records.last_mut().filter(|_| head.len() < opts.layout.header_size()) {
+                    last.trailer = head;
+                    break;
+                }
+            }
+            let mut record = Counted { inner: (&head[..]).chain(&mut reader), count: 0 };
+            let data = codec::decode_with(&mut record, &opts).map_err(|e| match e {
+                CodecError::UnexpectedEof => io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("record {} starting at byte {} is truncated", records.len(), offset),
+                ),
+                e => e.into(),
+            })?;
+            offset += record.count;
+            records.push(data);
         }
+        Ok(records)
     }
 
-    Ok(PatternFileData{
-        compiled_flag: flag,
-        version,
-        source_combo_index,
-        pclk_source_indices,
-        vtime_reqd,
-        cycle_time,
-        pulse_time,
-        clk_sources,
-        start_addrs,
-        end_addrs,
-        loop_counts,
-        pattern_file_length,
-        pattern_data,
-    })
-}
-
-pub fn write_pcf_file<P: AsRef<Path>>(filename: P, data: &PatternFileData) -> io::Result<()> {
-    let file: File = File::create(filename)?;
-    let mut writer: BufWriter<File> = BufWriter::new(file);
-
-    fn write_fixed(writer: &mut BufWriter<File>, val: &str, len: usize) -> io::Result<()> {
-        let mut bytes: Vec<u8> = val
-            .as_bytes()
-            .to_vec();
-
-        bytes.resize(len, b' ');
-
-        writer.write_all(&bytes[..len])
+    /// Counts the bytes read through it, so a record's size is known for
+    /// any layout.
+    struct Counted<R> {
+        inner: R,
+        count: usize,
     }
 
-    let flag_str = if data.compiled_flag { "True" } else { "False" };
-    let header   = format!("{} {}", flag_str, data.version);
-    write_fixed(&mut writer, &header, 10)?;
-
-    write_fixed(&mut writer, &data.source_combo_index.to_string(), 10)?;
-
-    for v in &data.pclk_source_indices {
-        write_fixed(&mut writer, &v.to_string(), 10)?;
+    impl<R: Read> Read for Counted<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.count += n;
+            Ok(n)
+        }
     }
 
-    write_fixed(&mut writer, &data.vtime_reqd[8], 10)?;
-    for i in 0..8 {
-        write_fixed(&mut writer, &data.vtime_reqd[i], 10)?;
+    /// Writes `data` to `filename`, replacing it atomically: the record goes
+    /// to a temporary file in the same directory that is renamed over the
+    /// target only once fully written, so a failure part-way (or a panic)
+    /// leaves any existing file untouched. The replacement keeps the old
+    /// file's permissions, and a symlink is written through, not replaced.
+    pub fn write_pcf_file<P: AsRef<Path>>(filename: P, data: &PatternFileData) -> io::Result<()> {
+        write_atomic(filename.as_ref(), |writer| write_pcf_writer(writer, data))
     }
 
-    write_fixed(&mut writer, &data.cycle_time[8], 10)?;
-    for i in 0..8 {
-        write_fixed(&mut writer, &data.cycle_time[i], 10)?;
+    /// Like [`write_pcf_file`], with the header field widths and delimiter
+    /// of `layout`.
+    pub fn write_pcf_file_with<P: AsRef<Path>>(filename: P, data: &PatternFileData, layout: &PcfLayout) -> io::Result<()> {
+        write_atomic(filename.as_ref(), |writer| Ok(codec::encode_with(writer, data, layout)?))
     }
 
-    write_fixed(&mut writer, &data.pulse_time[8], 10)?;
-    for i in 0..8 {
-        write_fixed(&mut writer, &data.pulse_time[i], 10)?;
+    /// Like [`write_pcf_file`], returning the [`crc32`](crate::hash::crc32)
+    /// of the bytes written, computed as they are written rather than by
+    /// reading the file back.
+    pub fn write_pcf_file_checked<P: AsRef<Path>>(filename: P, data: &PatternFileData) -> io::Result<u32> {
+        write_atomic(filename.as_ref(), |writer| {
+            let mut writer = CrcWriter::new(writer);
+            write_pcf_writer(&mut writer, data)?;
+            Ok(writer.crc())
+        })
     }
 
-    assert_eq!(data.clk_sources.len(), 65, "clk_sources must have 65 entries");
-    for i in 1..=64 {
-        write_fixed(&mut writer, &data.clk_sources[i], 10)?;
+    /// Replaces `path` with `bytes` the way [`write_pcf_file`] replaces a
+    /// record: through a temporary file renamed into place, keeping the old
+    /// file's permissions and any symlink. For editors that hold raw bytes.
+    pub fn write_file_atomic<P: AsRef<Path>>(path: P, bytes: &[u8]) -> io::Result<()> {
+        write_atomic(path.as_ref(), |writer| writer.write_all(bytes))
     }
 
-    for i in 0..8 {
-        write_fixed(&mut writer, &data.start_addrs[i].to_string(), 10)?;
-        write_fixed(&mut writer, &data.end_addrs[i].to_string(), 10)?;
-        write_fixed(&mut writer, &data.loop_counts[i].to_string(), 10)?;
-    }
+    /// Runs `write` against a buffered temporary file beside `path`, then
+    /// renames it over `path` once flushed and synced.
+    ///
+    /// A symlinked `path` is followed, so the link's target is replaced and
+    /// the link kept. The new file takes the permissions of the one it
+    /// replaces; a new file gets `0666` less the umask, as `File::create`
+    /// would give it.
+    pub(crate) fn write_atomic<T>(path: &Path, write: impl FnOnce(&mut BufWriter<&File>) -> io::Result<T>) -> io::Result<T> {
+        let path = resolve_symlinks(path)?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let existing = fs::metadata(&path).ok().map(|m| m.permissions());
 
-    write_fixed(&mut writer, &data.pattern_file_length.to_string(), 10)?;
+        let mut builder = tempfile::Builder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if existing.is_none() {
+                builder.permissions(fs::Permissions::from_mode(0o666));
+            }
+        }
+        let tmp = builder.tempfile_in(dir)?;
+        if let Some(permissions) = existing {
+            tmp.as_file().set_permissions(permissions)?;
+        }
 
-    let cols: usize = (data.pattern_file_length + 20) as usize;
+        let mut writer: BufWriter<&File> = BufWriter::new(tmp.as_file());
+        let out = write(&mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        tmp.as_file().sync_all()?;
+        tmp.persist(&path).map_err(|e| e.error)?;
+        Ok(out)
+    }
 
-    for col in 0..cols {
-        for bit in 0..18 {
-            writer.write_all(&[data.pattern_data[bit][col]])?;
+    /// The file `path` finally names once every symlink is followed; a
+    /// dangling link resolves to the missing target.
+    fn resolve_symlinks(path: &Path) -> io::Result<PathBuf> {
+        const MAX_LINKS: usize = 40;
+        let mut path = path.to_path_buf();
+        for _ in 0..MAX_LINKS {
+            match fs::symlink_metadata(&path) {
+                Ok(meta) if meta.file_type().is_symlink() => {
+                    let target = fs::read_link(&path)?;
+                    path = match path.parent() {
+                        Some(dir) => dir.join(target),
+                        None => target,
+                    };
+                }
+                _ => return Ok(path),
+            }
         }
+        Err(io::Error::other(format!("{}: too many levels of symbolic links", path.display())))
     }
 
-    writer.flush()?;
-    Ok(())
-
+    pub fn write_pcf_writer<W: Write>(mut writer: W, data: &PatternFileData) -> io::Result<()> {
+        Ok(codec::encode(&mut writer, data)?)
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Unit tests for PatternFileData parsing, writing, and JSON round-trip.
 // ─────────────────────────────────────────────────────────────────────────────
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
+    use std::io;
     use tempfile::NamedTempFile;
-    use std::io::Write;
-    use serde_json;
 
     /// Build a sample PatternFileData with non-trivial content.
     fn sample_pattern_data() -> PatternFileData {
-        let mut data = PatternFileData {
-            compiled_flag: true,
-            version: "v1.2".into(),
-            source_combo_index: 3,
-            pclk_source_indices: [1,2,3,4,5,6,7,8],
-            vtime_reqd: Default::default(),
-            cycle_time: Default::default(),
-            pulse_time: Default::default(),
-            clk_sources: vec![String::new(); 65],
-            start_addrs: [10;8],
-            end_addrs: [20;8],
-            loop_counts: [2;8],
-            pattern_file_length: 5,
-            pattern_data: vec![vec![0u8; 25]; 18],
-        };
-
-        // fill textual arrays
-        for i in 0..9 {
-            data.vtime_reqd[i] = format!("VT{}", i);
-            data.cycle_time[i] = format!("CT{}", i);
-            data.pulse_time[i] = format!("PT{}", i);
-        }
-
         // fill clk_sources 1..=64
-        for i in 1..=64 {
-            data.clk_sources[i] = format!("CLK{:02}", i);
-        }
+        let clk_sources = std::iter::once(String::new())
+            .chain((1..=64).map(|i| format!("CLK{:02}", i)))
+            .collect();
 
         // fill pattern_data with varying bytes
-        for bit in 0..18 {
-            for col in 0..25 {
-                data.pattern_data[bit][col] = ((bit + col) % 256) as u8;
-            }
-        }
+        let pattern = (0..18)
+            .map(|bit| (0..25).map(|col| ((bit + col) % 256) as u8).collect())
+            .collect();
 
-        data
+        PatternFileData::builder()
+            .compiled_flag(true)
+            .version("v1.2")
+            .source_combo_index(3)
+            .pclk_source_indices([1,2,3,4,5,6,7,8])
+            .vtime_reqd(std::array::from_fn(|i| format!("VT{}", i)))
+            .cycle_time(std::array::from_fn(|i| format!("CT{}", i)))
+            .pulse_time(std::array::from_fn(|i| format!("PT{}", i)))
+            .clk_sources(clk_sources)
+            .start_addrs([10;8])
+            .end_addrs([20;8])
+            .loop_counts([2;8])
+            .pattern_file_length(5)
+            .pattern_data(pattern)
+            .build()
+            .unwrap()
     }
 
     #[test]
@@ -278,6 +1114,15 @@ mod tests {
         assert_eq!(original, parsed, "original vs parsed mismatch");
     }
 
+    #[test]
+    fn checked_write_returns_the_file_crc() {
+        let data = sample_pattern_data();
+        let tmp = NamedTempFile::new().unwrap();
+        let crc = write_pcf_file_checked(tmp.path(), &data).unwrap();
+        assert_eq!(crc, crate::hash::crc32(&std::fs::read(tmp.path()).unwrap()));
+        assert_eq!(parse_pcf_file(tmp.path()).unwrap(), data);
+    }
+
     #[test]
     fn json_round_trip() {
         let original = sample_pattern_data();
@@ -287,4 +1132,527 @@ mod tests {
         let parsed: PatternFileData = serde_json::from_str(&json).unwrap();
         assert_eq!(original, parsed, "JSON round-trip mismatch");
     }
+
+    #[test]
+    fn bitgrid_round_trip() {
+        use crate::bitgrid::{read_bitgrid, write_bitgrid};
+
+        let mut original = sample_pattern_data();
+        for row in original.pattern_data.iter_mut() {
+            row.iter_mut().for_each(|v| *v %= 2);
+        }
+        let mut text = Vec::new();
+        write_bitgrid(&original, &mut text).unwrap();
+        let parsed = read_bitgrid(&text[..]).unwrap();
+        assert_eq!(original, parsed, "bit grid round-trip mismatch");
+    }
+
+    #[test]
+    fn fill_and_clear_pattern_keep_dimensions() {
+        let mut data = sample_pattern_data();
+        data.fill_pattern(1);
+        assert_eq!(data.pattern_data.len(), NUM_BITS);
+        assert!(data.pattern_data.iter().all(|row| row.len() == 25 && row.iter().all(|&b| b == 1)));
+
+        data.clear_pattern();
+        assert!(data.pattern_data.iter().all(|row| row.len() == 25 && row.iter().all(|&b| b == 0)));
+    }
+
+    #[test]
+    fn header_only_omits_pattern_data() {
+        let data = sample_pattern_data();
+        let full = serde_json::to_value(&data).unwrap();
+        let header = serde_json::to_value(HeaderOnly(&data)).unwrap();
+
+        let mut expected = full.as_object().unwrap().clone();
+        expected.remove("pattern_data");
+        assert_eq!(header.as_object().unwrap(), &expected);
+    }
+
+    #[test]
+    fn header_only_keeps_encoding_and_trailer() {
+        let mut data = sample_pattern_data();
+        data.fill_pattern(1);
+        data.encoding = PatternEncoding::PackedBits;
+        data.trailer = vec![0xDE, 0xAD];
+        let header = serde_json::to_value(HeaderOnly(&data)).unwrap();
+        assert_eq!(header["encoding"], "PackedBits");
+        assert_eq!(header["trailer"], serde_json::json!([0xDE, 0xAD]));
+
+        let mut expected = serde_json::to_value(&data).unwrap().as_object().unwrap().clone();
+        expected.remove("pattern_data");
+        assert_eq!(header.as_object().unwrap(), &expected);
+    }
+
+    #[test]
+    fn ordered_json_follows_the_file_layout() {
+        let mut data = sample_pattern_data();
+        data.trailer = vec![0xAA];
+        let json = data.to_ordered_json();
+
+        let keys: Vec<&str> = json
+            .lines()
+            .filter_map(|l| l.strip_prefix("  \"")?.split_once('"').map(|(k, _)| k))
+            .collect();
+        let mut expected = vec!["compiled_flag".to_string(), "version".to_string()];
+        expected.extend(HeaderField::all().skip(1).map(HeaderField::name));
+        expected.extend(["pattern_data".to_string(), "trailer".to_string()]);
+        assert_eq!(keys, expected);
+        assert_eq!(keys[2..5], ["source_combo_index", "pclk_source_indices[0]", "pclk_source_indices[1]"]);
+        assert_eq!(keys[11], "vtime_reqd[8]");
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], "v1.2");
+        assert_eq!(value["clk_sources[64]"], "CLK64");
+        assert_eq!(value["loop_counts[7]"], 2);
+        assert_eq!(value["pattern_data"], serde_json::json!(data.pattern_data));
+    }
+
+    #[test]
+    fn projection_keeps_requested_fields() {
+        let mut data = sample_pattern_data();
+        data.encoding = PatternEncoding::PackedBits;
+        data.trailer = vec![0xAA];
+        let full = serde_json::to_value(&data).unwrap();
+        let names: Vec<&str> = full.as_object().unwrap().keys().map(String::as_str).collect();
+        let mut expected = FIELD_NAMES.to_vec();
+        expected.sort();
+        assert_eq!(names, expected, "FIELD_NAMES is out of step with the struct");
+
+        let projected = serde_json::to_value(data.project(&["version", "loop_counts"]).unwrap()).unwrap();
+        assert_eq!(projected, serde_json::json!({ "version": "v1.2", "loop_counts": data.loop_counts }));
+
+        let err = data.project(&["version", "colour"]).err().unwrap();
+        assert!(err.starts_with("unknown field `colour`"));
+    }
+
+    #[test]
+    fn summary_truncates_long_values() {
+        let data = sample_pattern_data();
+        let full = data.to_string();
+        assert!(full.contains("version:             v1.2\n"));
+        assert!(full.contains("pattern_file_length: 5 (25 columns)\n"));
+        assert!(full.contains("64=CLK64"));
+
+        let narrow = data.summary(Some(40)).to_string();
+        assert!(narrow.lines().all(|l| l.chars().count() <= 40));
+        let clk = narrow.lines().find(|l| l.starts_with("clk_sources:")).unwrap();
+        assert!(clk.ends_with('…'));
+        assert!(narrow.contains("version:             v1.2\n"));
+    }
+
+    #[test]
+    fn blank_pattern_detection() {
+        let mut data = sample_pattern_data();
+        assert!(!data.is_pattern_blank());
+        data.clear_pattern();
+        assert!(data.is_pattern_blank());
+        data.pattern_file_length = 0;
+        data.clear_pattern();
+        assert!(!data.is_pattern_blank());
+    }
+
+    #[test]
+    fn rename_clk_source_checks_slot_and_width() {
+        let mut data = sample_pattern_data();
+        assert_eq!(data.clk_slot("CLK12"), Some(12));
+        assert_eq!(data.rename_clk_source(12, "NEWCLK").unwrap(), "CLK12");
+        assert_eq!(data.clk_slot("NEWCLK"), Some(12));
+        assert_eq!(data.pclk_source_indices, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        assert!(data.rename_clk_source(0, "X").is_err());
+        assert!(data.rename_clk_source(65, "X").is_err());
+        assert!(data.rename_clk_source(1, "ELEVEN_CHAR").is_err());
+    }
+
+    #[test]
+    fn column_accessors_agree_with_pattern_data() {
+        let data = sample_pattern_data();
+        let col = data.column(3).unwrap();
+        assert_eq!(col[0], 3);
+        assert_eq!(col[17], 20);
+        assert!(data.column(25).is_none());
+        assert_eq!(data.columns().count(), 25);
+        assert_eq!(data.row_major(2).unwrap()[4], 6);
+        assert!(data.row_major(NUM_BITS).is_none());
+    }
+
+    #[test]
+    fn bit_runs_collapses_repeats() {
+        let mut data = sample_pattern_data();
+        data.clear_pattern();
+        data.pattern_data[5][10] = 1;
+        data.pattern_data[5][11] = 1;
+        assert_eq!(data.bit_runs(5).unwrap(), vec![(0, 10), (1, 2), (0, 13)]);
+    }
+
+    #[test]
+    fn overlapping_loops_ignores_unused_regions() {
+        let mut data = sample_pattern_data();
+        data.start_addrs = [0, 10, 15, 30, 0, 0, 0, 0];
+        data.end_addrs   = [0, 20, 25, 40, 0, 0, 0, 0];
+        assert_eq!(data.overlapping_loops(), vec![(1, 2)]);
+
+        data.start_addrs[4] = 40;
+        data.end_addrs[4] = 39; // empty range
+        assert_eq!(data.overlapping_loops(), vec![(1, 2)]);
+    }
+
+    #[test]
+    fn loop_of_cycle_finds_the_enclosing_loop() {
+        let mut data = sample_pattern_data();
+        data.start_addrs = [0, 10, 15, 30, 0, 0, 0, 40];
+        data.end_addrs   = [0, 20, 25, 35, 0, 0, 0, 39];
+        assert_eq!(data.loop_of_cycle(0), None);
+        assert_eq!(data.loop_of_cycle(10), Some(1));
+        assert_eq!(data.loop_of_cycle(17), Some(1)); // overlap: lowest index
+        assert_eq!(data.loop_of_cycle(25), Some(2));
+        assert_eq!(data.loop_of_cycle(35), Some(3));
+        assert_eq!(data.loop_of_cycle(36), None);
+        assert_eq!(data.loop_of_cycle(40), None); // empty range
+    }
+
+    #[test]
+    fn expand_loops_repeats_bodies() {
+        let mut data = sample_pattern_data();
+        data.start_addrs = [2, 10, 0, 0, 0, 0, 0, 0];
+        data.end_addrs   = [3, 10, 0, 0, 0, 0, 0, 0];
+        data.loop_counts = [3, 2, 0, 0, 0, 0, 0, 0];
+
+        let expanded = data.expand_loops();
+        assert_eq!(expanded.len() as u64, data.executed_cycles());
+        assert_eq!(expanded.len(), 25 + 2 * 2 + 1);
+
+        let firsts: Vec<u8> = expanded.iter().take(9).map(|c| c[0]).collect();
+        assert_eq!(firsts, vec![0, 1, 2, 3, 2, 3, 2, 3, 4]);
+    }
+
+    #[test]
+    fn set_columns_updates_length() {
+        let mut data = sample_pattern_data();
+        data.set_columns(&vec![[1u8; NUM_BITS]; 30]);
+        assert_eq!(data.pattern_file_length, 10);
+        assert_eq!(data.cols(), 30);
+        assert_eq!(data.column(29), Some([1u8; NUM_BITS]));
+    }
+
+    #[test]
+    fn active_bits_flags_bits_set_anywhere() {
+        let mut data = sample_pattern_data();
+        data.clear_pattern();
+        assert_eq!(data.active_bits(), [false; NUM_BITS]);
+
+        data.pattern_data[4][24] = 1;
+        let active = data.active_bits();
+        assert!(active[4]);
+        assert_eq!(active.iter().filter(|&&a| a).count(), 1);
+    }
+
+    #[test]
+    fn column_frequencies_counts_distinct_columns() {
+        let mut data = sample_pattern_data();
+        data.clear_pattern();
+        data.pattern_data[1][3] = 1;
+        data.pattern_data[1][4] = 1;
+
+        let counts = data.column_frequencies();
+        let mut high = [0u8; NUM_BITS];
+        high[1] = 1;
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&[0u8; NUM_BITS]], 23);
+        assert_eq!(counts[&high], 2);
+    }
+
+    #[test]
+    fn first_diff_field_names_earliest_mismatch() {
+        let data = sample_pattern_data();
+        let mut other = data.clone();
+        assert_eq!(data.first_diff_field(&other), None);
+        assert_eq!(data.first_pattern_diff(&other), None);
+
+        other.pattern_data[7][12] ^= 1;
+        assert_eq!(data.first_diff_field(&other), Some("pattern_data"));
+        assert_eq!(data.first_pattern_diff(&other), Some((7, 12)));
+
+        other.loop_counts[0] += 1;
+        other.version.push('x');
+        assert_eq!(data.first_diff_field(&other), Some("version"));
+        assert_eq!(data.diff_fields(&other), ["version", "loop_counts", "pattern_data"]);
+
+        other.pattern_data[0].pop();
+        assert_eq!(data.first_pattern_diff(&other), Some((0, 24)));
+    }
+
+    #[test]
+    fn normalize_matches_a_round_trip() {
+        let data = sample_pattern_data();
+        let mut sloppy = data.clone();
+        sloppy.version = "v1.2   ".into();
+        sloppy.cycle_time[3] = "CT3       overflow".into();
+        sloppy.clk_sources[0] = "unused".into();
+        sloppy.pattern_data[0].push(9);
+        assert_ne!(data, sloppy);
+        assert_eq!(sloppy.normalize(), data);
+
+        let mut short = data.clone();
+        short.clk_sources.truncate(1);
+        assert_eq!(short.normalize().clk_sources.len(), 65);
+
+        let tmp = NamedTempFile::new().unwrap();
+        write_pcf_file(tmp.path(), &data).unwrap();
+        assert_eq!(parse_pcf_file(tmp.path()).unwrap(), data.normalize());
+        assert_eq!(data.normalize(), data);
+    }
+
+    #[test]
+    fn failed_write_keeps_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keep.pcf");
+        let good = sample_pattern_data();
+        write_pcf_file(&path, &good).unwrap();
+
+        let mut bad = good.clone();
+        bad.clk_sources.pop();
+        let err = write_pcf_file(&path, &bad).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        assert_eq!(parse_pcf_file(&path).unwrap(), good);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_keeps_mode_and_follows_symlinks() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+        let mode = |p: &std::path::Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        let dir = tempfile::tempdir().unwrap();
+
+        // A new file gets the same mode File::create would give it.
+        let plain = dir.path().join("plain");
+        std::fs::File::create(&plain).unwrap();
+        let path = dir.path().join("a.pcf");
+        write_pcf_file(&path, &sample_pattern_data()).unwrap();
+        assert_eq!(mode(&path), mode(&plain));
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        write_pcf_file(&path, &PatternFileData::new()).unwrap();
+        assert_eq!(mode(&path), 0o640);
+
+        let link = dir.path().join("link.pcf");
+        symlink("a.pcf", &link).unwrap();
+        let data = sample_pattern_data();
+        write_pcf_file_checked(&link, &data).unwrap();
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(parse_pcf_file(&path).unwrap(), data);
+        assert_eq!(mode(&path), 0o640);
+
+        write_file_atomic(&link, b"raw").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"raw");
+        assert_eq!(mode(&path), 0o640);
+    }
+
+    #[test]
+    fn clear_and_set_touch_one_bit() {
+        let data = sample_pattern_data();
+        let mut edited = data.clone();
+        edited.clear_bit(5).unwrap();
+        assert!(edited.row_major(5).unwrap().iter().all(|&v| v == 0));
+        edited.set_bit_all(17).unwrap();
+        assert!(edited.row_major(17).unwrap().iter().all(|&v| v == 1));
+
+        assert!(edited.header_eq(&data));
+        for bit in (0..NUM_BITS).filter(|b| ![5, 17].contains(b)) {
+            assert_eq!(edited.row_major(bit), data.row_major(bit));
+        }
+        assert!(edited.clear_bit(18).is_err());
+    }
+
+    #[test]
+    fn check_shape_catches_unwritable_records() {
+        let data = sample_pattern_data();
+        assert_eq!(data.check_shape(), Ok(()));
+
+        let mut bad = data.clone();
+        bad.pattern_data[3].pop();
+        assert!(bad.check_shape().unwrap_err().contains("pattern_data[3]"));
+
+        let mut bad = data.clone();
+        bad.clk_sources[9] = "ELEVEN_CHAR".into();
+        assert!(bad.check_shape().unwrap_err().contains("clk_sources"));
+
+        let mut bad = data.clone();
+        bad.loop_counts[0] = i32::MIN;
+        assert!(bad.check_shape().unwrap_err().contains("loop_counts"));
+    }
+
+    #[test]
+    fn check_shape_with_uses_the_layout_widths() {
+        let mut data = sample_pattern_data();
+        data.clk_sources[9] = "TWELVE_CHARS".into();
+        assert!(data.check_shape().is_err());
+        let wide = PcfLayout { string_width: 12, ..Default::default() };
+        assert_eq!(data.check_shape_with(&wide), Ok(()));
+
+        data.loop_counts[0] = i32::MIN;
+        assert!(data.check_shape_with(&wide).unwrap_err().contains("loop_counts"));
+        let newline = PcfLayout { delimiter: FieldDelimiter::Newline, ..Default::default() };
+        assert_eq!(data.check_shape_with(&newline), Ok(()));
+
+        data.version = "v1\nv2".into();
+        assert!(data.check_shape_with(&newline).unwrap_err().contains("newline"));
+    }
+
+    #[test]
+    fn content_hash_ignores_metadata() {
+        let data = sample_pattern_data();
+        let mut other = data.clone();
+        other.version = "v9".into();
+        other.cycle_time[0] = "1us".into();
+        other.start_addrs[0] = 3;
+        assert_eq!(data.content_hash(), other.content_hash());
+        assert_ne!(data.content_hash_with(true), other.content_hash_with(true));
+
+        other.pattern_data[4][4] ^= 1;
+        assert_ne!(data.content_hash(), other.content_hash());
+    }
+
+    #[test]
+    fn header_eq_ignores_pattern_only() {
+        let data = sample_pattern_data();
+        let mut other = data.clone();
+        other.set_columns(&vec![[1u8; NUM_BITS]; 40]);
+        assert_ne!(data, other);
+        assert!(data.header_eq(&other));
+
+        other.pulse_time[2] = "PTx".into();
+        assert!(!data.header_eq(&other));
+    }
+
+    #[test]
+    fn strict_parse_accepts_well_formed_file() {
+        let original = sample_pattern_data();
+        let tmp = NamedTempFile::new().unwrap();
+        write_pcf_file(tmp.path(), &original).unwrap();
+        let parsed = parse_pcf_file_strict(tmp.path()).expect("strict parse failed");
+        assert_eq!(original, parsed);
+    }
+
+    #[test]
+    fn limited_parse_refuses_large_patterns() {
+        let tmp = NamedTempFile::new().unwrap();
+        write_pcf_file(tmp.path(), &sample_pattern_data()).unwrap();
+        assert!(parse_pcf_file_limited(tmp.path(), 25 * NUM_BITS).is_ok());
+        let err = parse_pcf_file_limited(tmp.path(), 25 * NUM_BITS - 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_multi_splits_concatenated_records() {
+        let first = sample_pattern_data();
+        let mut second = sample_pattern_data();
+        second.version = "v2".into();
+        second.set_columns(&vec![[1u8; NUM_BITS]; 30]);
+
+        let mut bundle = Vec::new();
+        write_pcf_writer(&mut bundle, &first).unwrap();
+        write_pcf_writer(&mut bundle, &second).unwrap();
+        assert_eq!(parse_pcf_multi(&bundle[..]).unwrap(), vec![first.clone(), second]);
+        assert!(parse_pcf_multi(&[][..]).unwrap().is_empty());
+
+        let err = parse_pcf_multi(&bundle[..bundle.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let first_len = crate::codec::encode_to_vec(&first).unwrap().len();
+        assert_eq!(err.to_string(), format!("record 1 starting at byte {} is truncated", first_len));
+    }
+
+    #[test]
+    fn parse_multi_with_applies_the_options() {
+        use crate::codec::DecodeOptions;
+        let data = sample_pattern_data();
+        let mut bundle = Vec::new();
+        write_pcf_writer(&mut bundle, &data).unwrap();
+        write_pcf_writer(&mut bundle, &data).unwrap();
+        bundle.extend_from_slice(b"CRC1");
+
+        let err = parse_pcf_multi(&bundle[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let keep = DecodeOptions { keep_trailer: true, ..Default::default() };
+        let records = parse_pcf_multi_with(&bundle[..], &keep).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], data);
+        assert_eq!(records[1].trailer, b"CRC1");
+
+        let mut garbled = Vec::new();
+        write_pcf_writer(&mut garbled, &data).unwrap();
+        garbled[FIELD_WIDTH..FIELD_WIDTH + 3].copy_from_slice(b"1x2");
+        assert!(parse_pcf_multi(&garbled[..]).is_ok());
+        let strict = DecodeOptions { strict: true, ..Default::default() };
+        assert!(parse_pcf_multi_with(&garbled[..], &strict).is_err());
+    }
+
+    #[test]
+    fn parse_multi_with_reports_offsets_in_the_given_layout() {
+        use crate::codec::{encode_with, DecodeOptions};
+        use crate::layout::FieldDelimiter;
+
+        let data = sample_pattern_data();
+        for layout in [
+            PcfLayout { string_width: 12, ..Default::default() },
+            PcfLayout { delimiter: FieldDelimiter::Newline, ..Default::default() },
+        ] {
+            let mut bundle = Vec::new();
+            encode_with(&mut bundle, &data, &layout).unwrap();
+            let first = bundle.len();
+            encode_with(&mut bundle, &data, &layout).unwrap();
+            bundle.truncate(bundle.len() - 1);
+
+            let opts = DecodeOptions { layout, ..Default::default() };
+            let err = parse_pcf_multi_with(&bundle[..], &opts).unwrap_err();
+            let expected = format!("record 1 starting at byte {} is truncated", first);
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn slices_columns_and_extracts_loops() {
+        let mut data = sample_pattern_data();
+        data.start_addrs[1] = 2;
+        (data.start_addrs[2], data.end_addrs[2]) = (0, 0);
+        (data.start_addrs[3], data.end_addrs[3], data.loop_counts[3]) = (12, 14, 5);
+
+        let slice = data.slice_columns(5..25).unwrap();
+        assert_eq!(slice.cols(), 40);
+        assert_eq!(slice.column(0), data.column(5));
+        assert_eq!(slice.column(20), Some([0; NUM_BITS]));
+        assert_eq!((slice.start_addrs[0], slice.end_addrs[0], slice.loop_counts[0]), (5, 15, 2));
+        assert_eq!((slice.start_addrs[1], slice.end_addrs[1], slice.loop_counts[1]), (0, 0, 0));
+        assert!(data.slice_columns(20..30).is_err());
+
+        let body = data.extract_loop(3).unwrap();
+        assert_eq!((body.pattern_file_length, body.cols()), (3, 23));
+        assert_eq!(body.column(2), data.column(14));
+        assert_eq!((body.start_addrs[3], body.end_addrs[3], body.loop_counts[3]), (0, 2, 5));
+        assert_eq!(body.loop_range(0), None);
+        assert!(body.check_shape().is_ok());
+
+        assert!(data.extract_loop(2).unwrap_err().contains("unused or empty"));
+        assert!(data.extract_loop(8).is_err());
+
+        (data.start_addrs[4], data.end_addrs[4]) = (7, 7);
+        assert!(data.extract_loop(4).unwrap_err().contains("covers only cycle 7"));
+    }
+
+    #[test]
+    fn strict_parse_rejects_garbage_integer() {
+        let tmp = NamedTempFile::new().unwrap();
+        write_pcf_file(tmp.path(), &sample_pattern_data()).unwrap();
+        let mut bytes = std::fs::read(tmp.path()).unwrap();
+        // source_combo_index is the second field
+        bytes[FIELD_WIDTH..FIELD_WIDTH * 2].copy_from_slice(b"abc       ");
+
+        assert!(parse_pcf_reader(&bytes[..]).is_ok());
+        let err = parse_pcf_reader_strict(&bytes[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("source_combo_index at byte 10"));
+    }
 }
@@ -0,0 +1,204 @@
+//! Whole-pattern statistics gathered in one pass.
+//!
+//! [`PatternFileData::summarize`] collects the figures the `stats` command
+//! prints, so other tools can reuse them without parsing CLI output.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use serde::Serialize;
+
+use crate::layout::{NUM_BITS, NUM_LOOPS};
+use crate::pattern::PatternFileData;
+
+/// One loop's header settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LoopInfo {
+    pub start: i32,
+    pub end: i32,
+    pub count: i32,
+    /// False when the loop is unused or its range is empty or negative (see
+    /// [`PatternFileData::loop_range`]).
+    pub used: bool,
+    /// Cycles the loop runs for, `(end - start + 1) * count`, with a count
+    /// below 1 running the body once; 0 for an unused loop.
+    pub executed_cycles: u64,
+}
+
+/// Statistics returned by [`PatternFileData::summarize`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PatternSummary {
+    /// Pattern columns, as given by [`PatternFileData::cols`].
+    pub cycles: usize,
+    /// Columns in which each bit is non-zero.
+    pub set_bits_per_bit: [usize; NUM_BITS],
+    /// Times each bit changes value between consecutive columns.
+    pub transition_counts: [usize; NUM_BITS],
+    /// Clock-source slots (1–64) holding a non-blank name.
+    pub used_clk_slots: Vec<usize>,
+    pub loops: [LoopInfo; NUM_LOOPS],
+    /// Sum of every used loop's [`LoopInfo::executed_cycles`].
+    pub loop_cycles: u64,
+    /// Cycles the whole pattern runs for with its loops repeated, as given by
+    /// [`PatternFileData::executed_cycles`].
+    pub executed_cycles: u64,
+    /// See [`PatternFileData::is_pattern_blank`].
+    pub blank: bool,
+}
+
+/// Bit activity inside one loop body, from [`PatternFileData::loop_activity`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LoopActivity {
+    /// Loop index (0–7).
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    /// Times each bit changes value between consecutive cycles of the body;
+    /// the jump from the last cycle back to the first is not counted.
+    pub transitions: [usize; NUM_BITS],
+}
+
+impl LoopActivity {
+    /// Bits that change at least once in the body.
+    pub fn toggling_bits(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..NUM_BITS).filter(|&bit| self.transitions[bit] != 0)
+    }
+}
+
+impl PatternFileData {
+    /// Times each bit changes value between consecutive columns of `cols`
+    /// (clamped to the pattern). Missing rows count as all zero.
+    pub fn bit_transition_counts(&self, cols: Range<usize>) -> [usize; NUM_BITS] {
+        core::array::from_fn(|bit| {
+            let Some(row) = self.row_major(bit) else { return 0 };
+            let row = &row[cols.start.min(row.len())..cols.end.min(row.len())];
+            row.windows(2).filter(|w| (w[0] != 0) != (w[1] != 0)).count()
+        })
+    }
+
+    /// Per-bit transition counts within each used loop's body, in loop order.
+    pub fn loop_activity(&self) -> Vec<LoopActivity> {
+        (0..NUM_LOOPS)
+            .filter_map(|index| {
+                let (start, end) = self.loop_range(index)?;
+                let transitions = self.bit_transition_counts(start..end + 1);
+                Some(LoopActivity { index, start, end, transitions })
+            })
+            .collect()
+    }
+
+    /// Counts set bits and transitions per bit and collects the clock and
+    /// loop settings. Missing pattern rows count as all zero.
+    pub fn summarize(&self) -> PatternSummary {
+        let set_bits_per_bit = core::array::from_fn(|bit| {
+            self.row_major(bit).map_or(0, |row| row.iter().filter(|&&v| v != 0).count())
+        });
+        let transition_counts = self.bit_transition_counts(0..self.cols());
+
+        let used_clk_slots = (1..self.clk_sources.len())
+            .filter(|&slot| !self.clk_sources[slot].trim().is_empty())
+            .collect();
+
+        let loops: [LoopInfo; NUM_LOOPS] = core::array::from_fn(|i| {
+            let range = self.loop_range(i);
+            LoopInfo {
+                start: self.start_addrs[i],
+                end: self.end_addrs[i],
+                count: self.loop_counts[i],
+                used: range.is_some(),
+                executed_cycles: range
+                    .map_or(0, |(s, e)| (e - s + 1) as u64 * self.loop_counts[i].max(1) as u64),
+            }
+        });
+
+        PatternSummary {
+            cycles: self.cols(),
+            set_bits_per_bit,
+            transition_counts,
+            used_clk_slots,
+            loops,
+            loop_cycles: loops.iter().map(|l| l.executed_cycles).sum(),
+            executed_cycles: self.executed_cycles(),
+            blank: self.is_pattern_blank(),
+        }
+    }
+}
+
+impl fmt::Display for PatternSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "cycles: {}{}", self.cycles, if self.blank { " (blank)" } else { "" })?;
+        writeln!(f, "bit       set  transitions")?;
+        for bit in 0..NUM_BITS {
+            writeln!(f, "{:>3}  {:>8}  {:>11}", bit, self.set_bits_per_bit[bit], self.transition_counts[bit])?;
+        }
+        write!(f, "clock slots in use:")?;
+        if self.used_clk_slots.is_empty() {
+            write!(f, " none")?;
+        }
+        for slot in &self.used_clk_slots {
+            write!(f, " {}", slot)?;
+        }
+        writeln!(f)?;
+        for (i, l) in self.loops.iter().enumerate().filter(|(_, l)| l.used) {
+            writeln!(f, "loop {}  cycles {}..={} x{} = {} executed", i, l.start, l.end, l.count, l.executed_cycles)?;
+        }
+        writeln!(f, "loop cycles: {}", self.loop_cycles)?;
+        writeln!(f, "executed cycles: {}", self.executed_cycles)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_bits_transitions_and_settings() {
+        let mut data = PatternFileData::builder()
+            .clk_source(2, "MCLK")
+            .clk_source(9, "  ")
+            .loop_region(1, 1, 2, 3)
+            .build()
+            .unwrap();
+        data.set_columns(&[[0; NUM_BITS], [1; NUM_BITS], [1; NUM_BITS], [0; NUM_BITS]]);
+        data.pattern_data[5][3] = 1;
+
+        let s = data.summarize();
+        assert_eq!(s.cycles, data.cols());
+        assert_eq!((s.set_bits_per_bit[0], s.transition_counts[0]), (2, 2));
+        assert_eq!((s.set_bits_per_bit[5], s.transition_counts[5]), (3, 1));
+        assert_eq!(s.used_clk_slots, [2]);
+        assert_eq!(s.loops[1], LoopInfo { start: 1, end: 2, count: 3, used: true, executed_cycles: 6 });
+        assert!(!s.loops[0].used);
+        assert_eq!(s.loops[0].executed_cycles, 0);
+        assert_eq!(s.loop_cycles, 6);
+        assert_eq!(s.executed_cycles, s.cycles as u64 + 4);
+        assert!(!s.blank);
+    }
+
+    #[test]
+    fn loop_activity_counts_transitions_inside_each_body() {
+        let mut data = PatternFileData::builder()
+            .loop_region(0, 4, 7, 2)
+            .loop_region(3, 1, 2, 5)
+            .loop_region(5, 9, 8, 1)
+            .pattern_file_length(0)
+            .build()
+            .unwrap();
+        for col in [2, 4, 6] {
+            data.pattern_data[1][col] = 1;
+        }
+        data.pattern_data[9][3] = 1;
+
+        assert_eq!(data.bit_transition_counts(0..data.cols())[1], 6);
+        assert_eq!(data.bit_transition_counts(18..100), [0; NUM_BITS]);
+
+        let activity = data.loop_activity();
+        assert_eq!(activity.iter().map(|a| (a.index, a.start, a.end)).collect::<Vec<_>>(), [(0, 4, 7), (3, 1, 2)]);
+        assert_eq!(activity[0].toggling_bits().collect::<Vec<_>>(), [1]);
+        assert_eq!(activity[0].transitions[1], 3);
+        assert_eq!(activity[1].transitions[1], 1);
+        assert_eq!(activity[1].transitions[9], 0);
+    }
+}
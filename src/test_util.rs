@@ -0,0 +1,94 @@
+//! Assertions for crates that build [`PatternFileData`] themselves and want
+//! to check their output survives the PCF format.
+//!
+//! Built for this crate's own tests and, outside them, with the `test-util`
+//! feature:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! pcf_parser = { version = "0.1", features = ["test-util"] }
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+
+use crate::codec::{decode_with, encode_to_vec, DecodeOptions};
+use crate::pattern::PatternFileData;
+
+/// Encodes `data`, decodes the bytes strictly and asserts the result equals
+/// `data`.
+///
+/// # Panics
+/// If `data` cannot be written (see [`PatternFileData::check_shape`]), the
+/// bytes do not decode, or the decoded record differs; the message names
+/// the first differing field and, for `pattern_data`, the bit and column.
+#[track_caller]
+pub fn assert_roundtrip(data: &PatternFileData) {
+    if let Err(e) = roundtrip_error(data) {
+        panic!("round trip failed: {}", e);
+    }
+}
+
+/// The check behind [`assert_roundtrip`], returning the failure instead of
+/// panicking.
+pub fn roundtrip_error(data: &PatternFileData) -> Result<(), String> {
+    data.check_shape().map_err(|e| format!("record cannot be written: {}", e))?;
+    let bytes = encode_to_vec(data).map_err(|e| format!("record cannot be written: {}", e))?;
+    let opts = DecodeOptions {
+        strict: true,
+        total_len: Some(bytes.len()),
+        encoding: Some(data.encoding),
+        keep_trailer: !data.trailer.is_empty(),
+        ..Default::default()
+    };
+    let decoded = decode_with(&mut &bytes[..], &opts)
+        .map_err(|e| format!("encoded {} bytes do not decode: {}", bytes.len(), e))?;
+
+    match data.first_diff_field(&decoded) {
+        None => Ok(()),
+        Some("pattern_data") => {
+            let (bit, col) = data.first_pattern_diff(&decoded).unwrap_or_default();
+            Err(format!("pattern_data differs at bit {}, column {}", bit, col))
+        }
+        Some(field) => Err(format!("{} differs after decoding", field)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::PatternEncoding;
+
+    #[test]
+    fn accepts_records_that_survive_encoding() {
+        let mut data = PatternFileData::builder().version("V3").clk_source(4, "MCLK").loop_region(1, 2, 5, 3).build().unwrap();
+        data.pattern_data[7][3] = 1;
+        assert_roundtrip(&data);
+
+        data.encoding = PatternEncoding::PackedBits;
+        data.trailer = alloc::vec![0xAA, 0x55];
+        assert_roundtrip(&data);
+    }
+
+    #[test]
+    fn names_the_field_that_changes() {
+        let mut data = PatternFileData::new();
+        data.clk_sources[2] = "MCLK ".into();
+        assert_eq!(roundtrip_error(&data), Err("clk_sources differs after decoding".into()));
+
+        let mut data = PatternFileData::new();
+        data.encoding = PatternEncoding::PackedBits;
+        data.pattern_data[5][9] = 2;
+        assert_eq!(roundtrip_error(&data), Err("pattern_data differs at bit 5, column 9".into()));
+
+        data.pattern_data.pop();
+        assert!(roundtrip_error(&data).unwrap_err().starts_with("record cannot be written"));
+    }
+
+    #[test]
+    #[should_panic(expected = "round trip failed: version differs")]
+    fn assert_panics_with_the_field() {
+        let data = PatternFileData::builder().version(" V3").build().unwrap();
+        assert_roundtrip(&data);
+    }
+}
@@ -0,0 +1,257 @@
+//! Parsing of the textual timing fields (`vtime_reqd`, `cycle_time`, `pulse_time`).
+//!
+//! Values are a decimal number with an optional unit suffix, e.g. `20`,
+//! `488.28ns` or `100us`. A bare number carries no unit; it can only be
+//! compared with other bare numbers.
+//!
+//! [`PatternFileData::timeline`] turns the `cycle_time` table into run time.
+//! Its nine entries are read as one period per loop slot plus a base period:
+//! cycles inside loop `i` run at `cycle_time[i]` when that entry is set, and
+//! every other cycle at `cycle_time[8]`.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use serde::Serialize;
+
+use crate::layout::NUM_TIMINGS;
+use crate::pattern::PatternFileData;
+
+/// Time unit suffix of a timing field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Ps,
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+
+impl TimeUnit {
+    fn nanos(self) -> f64 {
+        match self {
+            TimeUnit::Ps => 1e-3,
+            TimeUnit::Ns => 1.0,
+            TimeUnit::Us => 1e3,
+            TimeUnit::Ms => 1e6,
+            TimeUnit::S => 1e9,
+        }
+    }
+}
+
+/// A parsed timing value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    pub value: f64,
+    pub unit: Option<TimeUnit>,
+}
+
+impl Timing {
+    /// Value in nanoseconds, if the field carried a unit.
+    pub fn as_nanos(&self) -> Option<f64> {
+        self.unit.map(|u| self.value * u.nanos())
+    }
+
+    /// Orders two timings when they are comparable: both with units, or both bare.
+    pub fn compare(&self, other: &Timing) -> Option<Ordering> {
+        match (self.as_nanos(), other.as_nanos()) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            (None, None) => self.value.partial_cmp(&other.value),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a timing field; `None` for blank or unparseable text.
+pub fn parse_timing(text: &str) -> Option<Timing> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '+' | '-')))
+        .unwrap_or(text.len());
+    let (num, suffix) = text.split_at(split);
+
+    let value: f64 = num.trim().parse().ok()?;
+    if !value.is_finite() {
+        return None;
+    }
+
+    let unit = match suffix.trim().to_ascii_lowercase().as_str() {
+        "" => None,
+        "ps" => Some(TimeUnit::Ps),
+        "ns" => Some(TimeUnit::Ns),
+        // the micro sign or the Greek letter mu
+        "us" | "µs" | "μs" => Some(TimeUnit::Us),
+        "ms" => Some(TimeUnit::Ms),
+        "s" => Some(TimeUnit::S),
+        _ => return None,
+    };
+
+    Some(Timing { value, unit })
+}
+
+/// Formats a duration in nanoseconds with the largest unit that keeps it at
+/// or above 1, e.g. `1.5us`.
+pub fn format_nanos(ns: f64) -> String {
+    let (value, unit) = [(1e9, "s"), (1e6, "ms"), (1e3, "us")]
+        .into_iter()
+        .find(|&(scale, _)| ns.abs() >= scale)
+        .map_or((ns, "ns"), |(scale, unit)| (ns / scale, unit));
+    let digits = format!("{:.3}", value);
+    format!("{}{}", digits.trim_end_matches('0').trim_end_matches('.'), unit)
+}
+
+/// One executed cycle, as listed by [`PatternFileData::timeline`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TimelineStep {
+    /// Pattern column the cycle replays.
+    pub column: usize,
+    /// Loop slot the cycle runs in, if any.
+    pub loop_index: Option<usize>,
+    /// Time from the start of the pattern, in ns; `None` once a cycle of
+    /// unknown period has run.
+    pub start_ns: Option<f64>,
+    /// Length of this cycle in ns, if its `cycle_time` parses with a unit.
+    pub period_ns: Option<f64>,
+}
+
+impl PatternFileData {
+    /// Period in ns of a cycle in loop `slot`, or outside every loop for
+    /// `None`. A blank loop entry falls back to the base period; a blank,
+    /// unparseable or unitless one gives `None`.
+    pub fn cycle_period(&self, slot: Option<usize>) -> Option<f64> {
+        let base = &self.cycle_time[NUM_TIMINGS - 1];
+        let text = match slot.and_then(|i| self.cycle_time.get(i)) {
+            Some(t) if !t.trim().is_empty() => t,
+            _ => base,
+        };
+        parse_timing(text)?.as_nanos()
+    }
+
+    /// Time in ns to run the whole pattern once, loops included, or `None`
+    /// if any executed cycle's period is unknown. Computed without expanding
+    /// the loops.
+    pub fn execution_time(&self) -> Option<f64> {
+        let mut total = 0.0;
+        let mut col = 0;
+        for (i, s, e, n) in self.execution_loops() {
+            if s > col {
+                total += (s - col) as f64 * self.cycle_period(None)?;
+            }
+            total += ((e - s + 1) * n) as f64 * self.cycle_period(Some(i))?;
+            col = e + 1;
+        }
+        if self.cols() > col {
+            total += (self.cols() - col) as f64 * self.cycle_period(None)?;
+        }
+        Some(total)
+    }
+
+    /// Every executed cycle in the order
+    /// [`expand_loops`](Self::expand_loops) produces them, with its start
+    /// time. Fails if that is more than `max_cycles` cycles.
+    pub fn timeline(&self, max_cycles: u64) -> Result<Vec<TimelineStep>, String> {
+        let cycles = self.executed_cycles();
+        if cycles > max_cycles {
+            return Err(format!("pattern executes {} cycles (limit {})", cycles, max_cycles));
+        }
+
+        let mut out = Vec::with_capacity(cycles as usize);
+        let mut now = Some(0.0);
+        let mut push = |column, loop_index| {
+            let period_ns = self.cycle_period(loop_index);
+            out.push(TimelineStep { column, loop_index, start_ns: now, period_ns });
+            now = now.zip(period_ns).map(|(t, p)| t + p);
+        };
+        let mut col = 0;
+        for (i, s, e, n) in self.execution_loops() {
+            (col..s).for_each(|c| push(c, None));
+            for _ in 0..n {
+                (s..=e).for_each(|c| push(c, Some(i)));
+            }
+            col = e + 1;
+        }
+        (col..self.cols()).for_each(|c| push(c, None));
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_units_and_bare_numbers() {
+        assert_eq!(parse_timing("20"), Some(Timing { value: 20.0, unit: None }));
+        assert_eq!(parse_timing("488.28ns").unwrap().as_nanos(), Some(488.28));
+        assert_eq!(parse_timing("100us").unwrap().as_nanos(), Some(100_000.0));
+        assert_eq!(parse_timing("10µs").unwrap().as_nanos(), Some(10_000.0));
+        assert_eq!(parse_timing("10μs").unwrap().as_nanos(), Some(10_000.0));
+        assert_eq!(parse_timing("2.5 ms").unwrap().as_nanos(), Some(2_500_000.0));
+        assert_eq!(parse_timing(""), None);
+        assert_eq!(parse_timing("fast"), None);
+        assert_eq!(parse_timing("10 parsecs"), None);
+    }
+
+    #[test]
+    fn compares_only_like_with_like() {
+        let bare = parse_timing("10").unwrap();
+        let ns = parse_timing("10ns").unwrap();
+        let us = parse_timing("1us").unwrap();
+        assert_eq!(ns.compare(&us), Some(Ordering::Less));
+        assert_eq!(bare.compare(&parse_timing("4").unwrap()), Some(Ordering::Greater));
+        assert_eq!(bare.compare(&ns), None);
+    }
+
+    fn looped(base: &str, loop_time: &str) -> PatternFileData {
+        let mut cycle_time: [String; NUM_TIMINGS] = Default::default();
+        cycle_time[8] = base.into();
+        cycle_time[1] = loop_time.into();
+        PatternFileData::builder()
+            .cycle_time(cycle_time)
+            .loop_region(1, 2, 3, 3)
+            .pattern_file_length(0)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn timeline_follows_loop_periods() {
+        let data = looped("100ns", "1us");
+        // 20 pad columns; 2..=3 runs three times at 1us, the other 18 at 100ns.
+        assert_eq!(data.execution_time(), Some(18.0 * 100.0 + 6.0 * 1000.0));
+
+        let steps = data.timeline(1000).unwrap();
+        assert_eq!(steps.len() as u64, data.executed_cycles());
+        let columns: Vec<usize> = steps[..9].iter().map(|s| s.column).collect();
+        assert_eq!(columns, [0, 1, 2, 3, 2, 3, 2, 3, 4]);
+        assert_eq!(steps[2].loop_index, Some(1));
+        assert_eq!(steps[3].start_ns, Some(1200.0));
+        assert_eq!(steps[8].start_ns, Some(6200.0));
+        let last = steps.last().unwrap();
+        assert_eq!(last.start_ns.zip(last.period_ns).map(|(t, p)| t + p), data.execution_time());
+
+        assert!(data.timeline(10).is_err());
+        assert_eq!(looped("100ns", "").execution_time(), Some(24.0 * 100.0));
+    }
+
+    #[test]
+    fn unknown_periods_stay_unknown() {
+        let data = looped("100ns", "fast");
+        assert_eq!(data.execution_time(), None);
+        let steps = data.timeline(1000).unwrap();
+        assert_eq!(steps[2].start_ns, Some(200.0));
+        assert_eq!(steps[2].period_ns, None);
+        assert_eq!(steps[3].start_ns, None);
+        assert_eq!(looped("20", "").execution_time(), None);
+    }
+
+    #[test]
+    fn formats_with_the_largest_unit() {
+        assert_eq!(format_nanos(488.28), "488.28ns");
+        assert_eq!(format_nanos(1500.0), "1.5us");
+        assert_eq!(format_nanos(7_800_000.0), "7.8ms");
+        assert_eq!(format_nanos(2e9), "2s");
+    }
+}
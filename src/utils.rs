@@ -1,38 +1,654 @@
-use std::fs;
-use std::io;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
 use std::path::Path;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::layout::header_size;
+
+/// Radix used when printing byte offsets in dumps and diffs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OffsetBase {
+    #[default]
+    Hex,
+    Dec,
+}
+
+impl OffsetBase {
+    /// Formats `offset` zero-padded to `width` digits.
+    pub fn format(self, offset: usize, width: usize) -> String {
+        match self {
+            OffsetBase::Hex => format!("{:0width$X}", offset, width = width),
+            OffsetBase::Dec => format!("{:0width$}", offset, width = width),
+        }
+    }
+}
+
+/// Which columns [`hex_dump_with`] prints after the offset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DumpColumns {
+    #[default]
+    Both,
+    /// Only the hex bytes, for the densest byte view.
+    HexOnly,
+    /// Only the ASCII column, for reading embedded text.
+    AsciiOnly,
+}
+
+/// Formatting knobs for [`hex_dump_with`].
+#[derive(Debug, Clone)]
+pub struct DumpOptions {
+    pub bytes_per_line: usize,
+    /// Print only every Nth line (0 or 1 prints every line). Offsets stay absolute.
+    pub every: usize,
+    pub offset_base: OffsetBase,
+    /// Append each line's [`row_checksum`] after the ASCII column.
+    pub rowsum: bool,
+    /// Insert an extra space after every N hex bytes (0 disables grouping).
+    pub group: usize,
+    /// Print hex digits in upper case.
+    pub uppercase: bool,
+    /// Print a column ruler before the first line and again every N printed
+    /// lines (0 disables it).
+    pub repeat_header: usize,
+    pub columns: DumpColumns,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        DumpOptions { bytes_per_line: 16, every: 1, offset_base: OffsetBase::Hex, rowsum: false, group: 0, uppercase: true, repeat_header: 0, columns: DumpColumns::Both }
+    }
+}
+
+/// Knobs for [`diff_buffers_blocks_with`].
+#[derive(Debug, Clone)]
+pub struct BlockDiffOptions {
+    pub block_size: usize,
+    pub max_blocks: usize,
+    /// Show each side's [`row_checksum`] in the block heading.
+    pub rowsum: bool,
+    /// Print the blocks with the most differing bytes first (ties in file
+    /// order) instead of in file order.
+    pub sort_by_changes: bool,
+    /// Skip the header and compare only the pattern region, labelling each
+    /// block by its cycle (see [`diff_pattern_blocks_report`]).
+    pub pattern_only: bool,
+}
+
+impl Default for BlockDiffOptions {
+    fn default() -> Self {
+        BlockDiffOptions { block_size: 18, max_blocks: 10, rowsum: false, sort_by_changes: false, pattern_only: false }
+    }
+}
+
+/// One block that differs between two buffers, from [`diff_blocks_report`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockDiff {
+    /// Block index (offset / block size).
+    pub block: usize,
+    /// Offset of the block's first byte.
+    pub start: usize,
+    /// Number of byte positions in the block that differ.
+    pub changed: usize,
+}
+
+/// Every differing `block_size`-byte block, in file order. A trailing partial
+/// block is compared too, and a byte present on only one side differs, so
+/// the blocks agree with [`diff_summary`].
+pub fn diff_blocks_report(a: &[u8], b: &[u8], block_size: usize) -> Vec<BlockDiff> {
+    let block_size = block_size.max(1);
+    let len = a.len().max(b.len());
+    (0..len.div_ceil(block_size))
+        .filter_map(|block| {
+            let start = block * block_size;
+            let changed = (start..(start + block_size).min(len))
+                .filter(|&i| a.get(i) != b.get(i))
+                .count();
+            (changed > 0).then_some(BlockDiff { block, start, changed })
+        })
+        .collect()
+}
+
+/// [`diff_blocks_report`] over the pattern regions only, which start at
+/// [`header_size`]. `block` is the cycle index when `block_size` is the
+/// column size; `start` stays a file offset.
+pub fn diff_pattern_blocks_report(a: &[u8], b: &[u8], block_size: usize) -> Vec<BlockDiff> {
+    let (a, b) = (a.get(header_size()..).unwrap_or_default(), b.get(header_size()..).unwrap_or_default());
+    let mut report = diff_blocks_report(a, b, block_size);
+    for d in &mut report {
+        d.start += header_size();
+    }
+    report
+}
+
+/// Totals for a whole comparison, from [`diff_summary`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffSummary {
+    /// Length of the longer input.
+    pub bytes_compared: usize,
+    /// Differing bytes; a byte present on only one side differs.
+    pub bytes_differing: usize,
+    pub percent_differing: f64,
+    pub block_size: usize,
+    /// Blocks, a trailing partial block included, holding at least one
+    /// difference: the length of [`diff_blocks_report`].
+    pub blocks_differing: usize,
+}
+
+/// Counts every difference between `a` and `b`, grouped into `block_size`-byte
+/// blocks; the totals of [`diff_blocks_report`].
+pub fn diff_summary(a: &[u8], b: &[u8], block_size: usize) -> DiffSummary {
+    let block_size = block_size.max(1);
+    let bytes_compared = a.len().max(b.len());
+    let report = diff_blocks_report(a, b, block_size);
+    let bytes_differing = report.iter().map(|d| d.changed).sum();
+    let blocks_differing = report.len();
+    let percent_differing = if bytes_compared == 0 { 0.0 } else { 100.0 * bytes_differing as f64 / bytes_compared as f64 };
+    DiffSummary { bytes_compared, bytes_differing, percent_differing, block_size, blocks_differing }
+}
+
+/// `n` with `,` between groups of three digits.
+fn with_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+impl fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} bytes differ ({:.2}%), {} block{} of {} bytes",
+            with_thousands(self.bytes_differing),
+            with_thousands(self.bytes_compared),
+            self.percent_differing,
+            with_thousands(self.blocks_differing),
+            if self.blocks_differing == 1 { "" } else { "s" },
+            self.block_size,
+        )
+    }
+}
+
+/// Which differences in header text [`equate_header_fields`] disregards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextNormalize {
+    /// Ignore padding and runs of ASCII whitespace (and NULs): leading and
+    /// trailing runs are dropped, inner runs compare as one space.
+    pub ignore_ws: bool,
+    /// Ignore ASCII case.
+    pub ignore_case: bool,
+}
+
+impl TextNormalize {
+    pub fn is_active(&self) -> bool {
+        self.ignore_ws || self.ignore_case
+    }
+
+    fn apply(&self, field: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = if self.ignore_case { field.to_ascii_lowercase() } else { field.to_vec() };
+        if self.ignore_ws {
+            let is_ws = |b: &u8| b.is_ascii_whitespace() || *b == 0;
+            out = out
+                .split(is_ws)
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join(&b' ');
+        }
+        out
+    }
+}
+
+/// Copy of `b` in which every `field_width`-byte header field (within the
+/// first `header_len` bytes) that matches `a`'s field once normalized by
+/// `norm` is replaced by `a`'s bytes. A byte diff of `a` against the result
+/// then reports only meaningful header changes; the pattern region is
+/// compared exactly as before.
+pub fn equate_header_fields(a: &[u8], b: &[u8], header_len: usize, field_width: usize, norm: TextNormalize) -> Vec<u8> {
+    let mut out = b.to_vec();
+    let end = header_len.min(a.len()).min(b.len());
+    for start in (0..end / field_width.max(1)).map(|i| i * field_width) {
+        let range = start..start + field_width;
+        if norm.apply(&a[range.clone()]) == norm.apply(&b[range.clone()]) {
+            out[range.clone()].copy_from_slice(&a[range]);
+        }
+    }
+    out
+}
+
+/// One run of an [`lcs_diff`] edit script. `a` and `b` are where the run
+/// starts in the first and second input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum DiffOp {
+    /// `len` bytes common to both inputs.
+    Equal { a: usize, b: usize, len: usize },
+    /// `len` bytes found only in the second input.
+    Insert { a: usize, b: usize, len: usize },
+    /// `len` bytes found only in the first input.
+    Delete { a: usize, b: usize, len: usize },
+}
+
+/// Longest-common-subsequence diff: the runs that turn `a` into `b`. Unlike
+/// the positional diffs, an inserted or deleted run does not make everything
+/// after it differ.
+///
+/// Needs O(n·m) time and memory once the common prefix and suffix are
+/// stripped, so use it on small regions such as the header.
+pub fn lcs_diff(a: &[u8], b: &[u8]) -> Vec<DiffOp> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Kind { Equal, Insert, Delete }
+
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    // lcs[i][j]: length of the LCS of mid_a[i..] and mid_b[j..]
+    let (n, m) = (mid_a.len(), mid_b.len());
+    let width = m + 1;
+    let mut lcs = vec![0u32; (n + 1) * width];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i * width + j] = if mid_a[i] == mid_b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut runs: Vec<(Kind, usize, usize, usize)> = Vec::new();
+    let mut step = |kind: Kind, i: usize, j: usize, len: usize| match runs.last_mut() {
+        Some((k, _, _, n)) if *k == kind => *n += len,
+        _ if len > 0 => runs.push((kind, i, j, len)),
+        _ => {}
+    };
+    step(Kind::Equal, 0, 0, prefix);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        let (ai, bj) = (prefix + i, prefix + j);
+        if i < n && j < m && mid_a[i] == mid_b[j] {
+            step(Kind::Equal, ai, bj, 1);
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+            step(Kind::Delete, ai, bj, 1);
+            i += 1;
+        } else {
+            step(Kind::Insert, ai, bj, 1);
+            j += 1;
+        }
+    }
+    step(Kind::Equal, prefix + n, prefix + m, suffix);
+
+    runs.into_iter()
+        .map(|(kind, a, b, len)| match kind {
+            Kind::Equal => DiffOp::Equal { a, b, len },
+            Kind::Insert => DiffOp::Insert { a, b, len },
+            Kind::Delete => DiffOp::Delete { a, b, len },
+        })
+        .collect()
+}
+
+/// Multi-byte unsigned integer interpretation for reading values out of a dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntFormat {
+    U16Le,
+    U16Be,
+    U32Le,
+    U32Be,
+}
+
+impl IntFormat {
+    pub fn all() -> &'static [IntFormat] {
+        &[IntFormat::U16Le, IntFormat::U16Be, IntFormat::U32Le, IntFormat::U32Be]
+    }
+
+    /// Bytes the value occupies.
+    pub fn width(self) -> usize {
+        match self {
+            IntFormat::U16Le | IntFormat::U16Be => 2,
+            IntFormat::U32Le | IntFormat::U32Be => 4,
+        }
+    }
+
+    /// Short name as accepted on the command line, e.g. `u32be`.
+    pub fn name(self) -> &'static str {
+        match self {
+            IntFormat::U16Le => "u16le",
+            IntFormat::U16Be => "u16be",
+            IntFormat::U32Le => "u32le",
+            IntFormat::U32Be => "u32be",
+        }
+    }
+
+    /// The value stored at `offset`, or `None` if it runs past the end of `bytes`.
+    pub fn read(self, bytes: &[u8], offset: usize) -> Option<u32> {
+        let b = bytes.get(offset..offset.checked_add(self.width())?)?;
+        Some(match self {
+            IntFormat::U16Le => LittleEndian::read_u16(b) as u32,
+            IntFormat::U16Be => BigEndian::read_u16(b) as u32,
+            IntFormat::U32Le => LittleEndian::read_u32(b),
+            IntFormat::U32Be => BigEndian::read_u32(b),
+        })
+    }
+}
+
+/// One-byte XOR of `row`: identical rows always share it, so a changed
+/// cycle stands out when scanning an 18-byte-aligned dump.
+pub fn row_checksum(row: &[u8]) -> u8 {
+    row.iter().fold(0, |acc, b| acc ^ b)
+}
 
 pub fn hex_dump_file<P: AsRef<Path>>(file_path: P, bytes_per_line: usize) -> io::Result<()> {
+    let opts = DumpOptions { bytes_per_line, ..Default::default() };
+    hex_dump_file_with(file_path, &opts)
+}
+
+pub fn hex_dump_file_with<P: AsRef<Path>>(file_path: P, opts: &DumpOptions) -> io::Result<()> {
     let buffer = fs::read(&file_path)?;
     println!("Hex dump of: {:?} ({} bytes)", file_path.as_ref(), buffer.len());
+    hex_dump_with(&mut io::stdout().lock(), &buffer, opts)
+}
 
-    for (i, chunk) in buffer.chunks(bytes_per_line).enumerate() {
+/// Writes the dump lines (without the title) for `buffer`.
+pub fn hex_dump_with<W: Write>(w: &mut W, buffer: &[u8], opts: &DumpOptions) -> io::Result<()> {
+    let bytes_per_line = opts.bytes_per_line;
+    let every = opts.every.max(1);
+    let gaps = bytes_per_line.saturating_sub(1).checked_div(opts.group).unwrap_or(0);
+    let width = bytes_per_line * 3 + gaps;
+
+    for (n, (i, chunk)) in buffer.chunks(bytes_per_line).enumerate().step_by(every).enumerate() {
+        if opts.repeat_header != 0 && n % opts.repeat_header == 0 {
+            writeln!(w, "{}", dump_ruler(opts, width))?;
+        }
         let offset = i * bytes_per_line;
-        let hex = chunk.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+        let (hex, _) = hex_cells(chunk, None, opts);
         let ascii = chunk.iter().map(|b| {
             if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' }
         }).collect::<String>();
 
-        println!("{:06X}  {:<width$}  |{}|", offset, hex, ascii, width = bytes_per_line * 3);
+        let ascii_col = format!("|{}|", ascii);
+        let line = match opts.columns {
+            DumpColumns::Both if opts.rowsum => format!("{:<width$}  {:<pad$}", hex, ascii_col, width = width, pad = bytes_per_line + 2),
+            DumpColumns::Both => format!("{:<width$}  {}", hex, ascii_col, width = width),
+            DumpColumns::HexOnly => format!("{:<width$}", hex, width = width),
+            DumpColumns::AsciiOnly => format!("{:<pad$}", ascii_col, pad = bytes_per_line + 2),
+        };
+        let offset = opts.offset_base.format(offset, 6);
+        if opts.rowsum {
+            writeln!(w, "{}  {}  ^{:02X}", offset, line, row_checksum(chunk))?;
+        } else {
+            writeln!(w, "{}  {}", offset, line.trim_end())?;
+        }
     }
 
     Ok(())
 }
 
+/// Column ruler for [`DumpOptions::repeat_header`]: each byte's position
+/// within the line, in the offset radix, above the hex and ASCII columns.
+fn dump_ruler(opts: &DumpOptions, width: usize) -> String {
+    let mut hex = String::new();
+    for j in 0..opts.bytes_per_line {
+        if j != 0 {
+            hex.push(' ');
+            if opts.group != 0 && j % opts.group == 0 {
+                hex.push(' ');
+            }
+        }
+        let label = opts.offset_base.format(j, 2);
+        hex.push_str(&if opts.uppercase { label } else { label.to_lowercase() });
+    }
+    let radix = match opts.offset_base {
+        OffsetBase::Hex => 16,
+        OffsetBase::Dec => 10,
+    };
+    let ascii: String = (0..opts.bytes_per_line)
+        .map(|j| char::from_digit((j % radix) as u32, radix as u32).unwrap_or('.'))
+        .map(|c| if opts.uppercase { c.to_ascii_uppercase() } else { c })
+        .collect();
+    match opts.columns {
+        DumpColumns::Both => format!("{:<6}  {:<width$}  |{}|", "OFFSET", hex, ascii, width = width),
+        DumpColumns::HexOnly => format!("{:<6}  {}", "OFFSET", hex),
+        DumpColumns::AsciiOnly => format!("{:<6}  |{}|", "OFFSET", ascii),
+    }
+}
+
+/// Hex column for `chunk` and its printed width. Bytes that differ from the
+/// same position in `other` are highlighted.
+fn hex_cells(chunk: &[u8], other: Option<&[u8]>, opts: &DumpOptions) -> (String, usize) {
+    let mut hex = String::with_capacity(chunk.len() * 3);
+    let mut visible = 0;
+    for (j, b) in chunk.iter().enumerate() {
+        if j != 0 {
+            hex.push(' ');
+            visible += 1;
+            if opts.group != 0 && j % opts.group == 0 {
+                hex.push(' ');
+                visible += 1;
+            }
+        }
+        let cell = if opts.uppercase { format!("{:02X}", b) } else { format!("{:02x}", b) };
+        visible += 2;
+        if other.is_some_and(|o| o.get(j) != Some(b)) {
+            hex.push_str(&cell.yellow().bold().to_string());
+        } else {
+            hex.push_str(&cell);
+        }
+    }
+    (hex, visible)
+}
+
+/// Like [`hex_dump_with`] over two buffers, printing only the lines within
+/// `context_lines` lines of a difference. A line that differs is shown as a
+/// `-` (first buffer) / `+` (second buffer) pair with the changed bytes
+/// highlighted; separate hunks are split by `...`. Returns the number of
+/// differing lines.
+pub fn diff_buffers_lines<W: Write, A: AsRef<[u8]>, B: AsRef<[u8]>>(w: &mut W, a: A, b: B, context_lines: usize, opts: &DumpOptions) -> io::Result<usize> {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    let per_line = opts.bytes_per_line.max(1);
+    let lines = a.len().max(b.len()).div_ceil(per_line);
+    let differing: Vec<usize> = (0..lines)
+        .filter(|&i| line_of(a, i, per_line) != line_of(b, i, per_line))
+        .collect();
+
+    // merge each difference's context window with any window it touches
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &d in &differing {
+        let (from, to) = (d.saturating_sub(context_lines), (d + context_lines).min(lines - 1));
+        match hunks.last_mut() {
+            Some((_, end)) if from <= *end + 1 => *end = to,
+            _ => hunks.push((from, to)),
+        }
+    }
+
+    for (n, &(from, to)) in hunks.iter().enumerate() {
+        if n != 0 {
+            writeln!(w, "...")?;
+        }
+        for i in from..=to {
+            let (la, lb) = (line_of(a, i, per_line), line_of(b, i, per_line));
+            let offset = opts.offset_base.format(i * per_line, 6);
+            if la == lb {
+                writeln!(w, "{}", diff_row(&offset, la, None, ' ', opts))?;
+            } else {
+                writeln!(w, "{}", diff_row(&offset, la, Some(lb), '-', opts))?;
+                writeln!(w, "{}", diff_row(&offset, lb, Some(la), '+', opts))?;
+            }
+        }
+    }
+
+    Ok(differing.len())
+}
+
+/// Interleaves dumps of two buffers into one stream, like `diff`'s merged
+/// output: every line of `a` is written, and a line of `b` that differs
+/// follows it marked `+`, with `^^` under each changed byte so the stream
+/// still reads correctly once the colour is stripped. Returns the number of
+/// differing lines.
+pub fn dump_merged<W: Write>(a: &[u8], b: &[u8], bytes_per_line: usize, w: &mut W) -> io::Result<usize> {
+    let opts = DumpOptions { bytes_per_line: bytes_per_line.max(1), ..Default::default() };
+    let per_line = opts.bytes_per_line;
+    let mut differing = 0;
+
+    for i in 0..a.len().max(b.len()).div_ceil(per_line) {
+        let (la, lb) = (line_of(a, i, per_line), line_of(b, i, per_line));
+        let offset = opts.offset_base.format(i * per_line, 6);
+        if la == lb {
+            writeln!(w, "{}", diff_row(&offset, la, None, ' ', &opts))?;
+            continue;
+        }
+        differing += 1;
+        writeln!(w, "{}", diff_row(&offset, la, Some(lb), '-', &opts))?;
+        writeln!(w, "{}", diff_row(&offset, lb, Some(la), '+', &opts))?;
+
+        let marks: Vec<&str> = (0..la.len().max(lb.len()))
+            .map(|j| if la.get(j) != lb.get(j) { "^^" } else { "  " })
+            .collect();
+        writeln!(w, "{}  {}", " ".repeat(offset.len()), marks.join(" ").trim_end())?;
+    }
+
+    Ok(differing)
+}
+
+/// Knobs for [`diff_side_by_side_with`].
+#[derive(Debug, Clone)]
+pub struct SideBySideOptions {
+    pub bytes_per_line: usize,
+    pub offset_base: OffsetBase,
+    /// Replace each run of two or more identical lines with a one-line count.
+    pub collapse: bool,
+    /// Highlight the differing bytes; the marker column shows which lines
+    /// differ either way.
+    pub color: bool,
+}
+
+impl Default for SideBySideOptions {
+    fn default() -> Self {
+        SideBySideOptions { bytes_per_line: 16, offset_base: OffsetBase::Hex, collapse: false, color: true }
+    }
+}
+
+/// `sdiff`-style dump: each line of `a` on the left and the same line of `b`
+/// on the right, with `|` between lines that differ (`<` or `>` where only
+/// one buffer reaches). Returns the number of differing lines.
+pub fn diff_side_by_side<W: Write>(a: &[u8], b: &[u8], bytes_per_line: usize, w: &mut W) -> io::Result<usize> {
+    diff_side_by_side_with(a, b, &SideBySideOptions { bytes_per_line, ..Default::default() }, w)
+}
+
+/// [`diff_side_by_side`] with explicit [`SideBySideOptions`].
+pub fn diff_side_by_side_with<W: Write>(a: &[u8], b: &[u8], opts: &SideBySideOptions, w: &mut W) -> io::Result<usize> {
+    let dump = DumpOptions { bytes_per_line: opts.bytes_per_line.max(1), offset_base: opts.offset_base, ..Default::default() };
+    let per_line = dump.bytes_per_line;
+    let width = per_line * 3 - 1;
+
+    // one buffer's half of a line: hex padded to a full line, then ASCII
+    let side = |chunk: &[u8], other: &[u8]| {
+        if chunk.is_empty() {
+            return " ".repeat(width + per_line + 4);
+        }
+        let (hex, visible) = hex_cells(chunk, opts.color.then_some(other), &dump);
+        let ascii: String = chunk.iter().map(|&c| to_char(c)).collect();
+        format!("{}{}  |{:<per_line$}|", hex, " ".repeat(width - visible), ascii)
+    };
+    let row = |i: usize| {
+        let (la, lb) = (line_of(a, i, per_line), line_of(b, i, per_line));
+        let mark = match (la.is_empty(), lb.is_empty()) {
+            _ if la == lb => ' ',
+            (true, _) => '>',
+            (_, true) => '<',
+            _ => '|',
+        };
+        let line = format!("{}  {}  {}  {}", opts.offset_base.format(i * per_line, 6), side(la, lb), mark, side(lb, la));
+        line.trim_end().to_string()
+    };
+
+    let mut differing = 0;
+    let mut same_from = None;
+    for i in 0..=a.len().max(b.len()).div_ceil(per_line) {
+        let last = i * per_line >= a.len().max(b.len());
+        let same = !last && line_of(a, i, per_line) == line_of(b, i, per_line);
+        if opts.collapse && same {
+            same_from.get_or_insert(i);
+            continue;
+        }
+        match same_from.take() {
+            Some(from) if i - from > 1 => {
+                writeln!(w, "{}  ... {} identical lines", opts.offset_base.format(from * per_line, 6), i - from)?;
+            }
+            Some(from) => writeln!(w, "{}", row(from))?,
+            None => {}
+        }
+        if last {
+            break;
+        }
+        if !same {
+            differing += 1;
+        }
+        writeln!(w, "{}", row(i))?;
+    }
+
+    Ok(differing)
+}
+
+/// Line `i` of `buf` split into `per_line`-byte lines; empty past the end.
+fn line_of(buf: &[u8], i: usize, per_line: usize) -> &[u8] {
+    let start = (i * per_line).min(buf.len());
+    &buf[start..(start + per_line).min(buf.len())]
+}
+
+/// One line of a two-buffer dump: offset, `mark`, hex cells (padded to a full
+/// line, bytes differing from `other` highlighted) and the ASCII column.
+fn diff_row(offset: &str, chunk: &[u8], other: Option<&[u8]>, mark: char, opts: &DumpOptions) -> String {
+    let per_line = opts.bytes_per_line.max(1);
+    let gaps = per_line.saturating_sub(1).checked_div(opts.group).unwrap_or(0);
+    let width = per_line * 3 + gaps;
+    let (hex, visible) = hex_cells(chunk, other, opts);
+    let ascii: String = chunk.iter().map(|&c| to_char(c)).collect();
+    format!("{}{} {}{}  |{}|", offset, mark, hex, " ".repeat(width - visible), ascii)
+}
+
 pub fn diff_files<P: AsRef<Path>>(file1: P, file2: P, context: usize) -> io::Result<()> {
+    diff_files_with(file1, file2, context, OffsetBase::default())
+}
+
+/// [`diff_files`] with byte offsets printed in `base`.
+pub fn diff_files_with<P: AsRef<Path>>(file1: P, file2: P, context: usize, base: OffsetBase) -> io::Result<()> {
     let bytes1 = fs::read(&file1)?;
     let bytes2 = fs::read(&file2)?;
-    let len = usize::max(bytes1.len(), bytes2.len());
 
     println!("Comparing: {:?} vs {:?}", file1.as_ref(), file2.as_ref());
 
+    diff_buffers_with(&bytes1, &bytes2, context, base)
+}
+
+/// In-memory form of [`diff_files`]: reports the first differing byte with
+/// `context` bytes around it.
+pub fn diff_buffers<A: AsRef<[u8]>, B: AsRef<[u8]>>(a: A, b: B, context: usize) -> io::Result<()> {
+    diff_buffers_with(a, b, context, OffsetBase::default())
+}
+
+/// [`diff_buffers`] with byte offsets printed in `base`.
+pub fn diff_buffers_with<A: AsRef<[u8]>, B: AsRef<[u8]>>(a: A, b: B, context: usize, base: OffsetBase) -> io::Result<()> {
+    let bytes1 = a.as_ref();
+    let bytes2 = b.as_ref();
+    let len = usize::max(bytes1.len(), bytes2.len());
+
     for i in 0..len {
         let b1 = *bytes1.get(i).unwrap_or(&0);
         let b2 = *bytes2.get(i).unwrap_or(&0);
 
         if b1 != b2 {
-            println!("\n{}", format!("Difference at byte {}: {:02X} != {:02X}", i, b1, b2).red().bold());
+            println!("\n{}", format!("Difference at byte {}: {:02X} != {:02X}", base.format(i, 0), b1, b2).red().bold());
 
             let start = i.saturating_sub(context);
             let end = usize::min(i + context, len);
@@ -40,56 +656,160 @@ pub fn diff_files<P: AsRef<Path>>(file1: P, file2: P, context: usize) -> io::Res
             for j in start..end {
                 let a = *bytes1.get(j).unwrap_or(&0);
                 let b = *bytes2.get(j).unwrap_or(&0);
-                let mark = if a != b { ">>".yellow().bold().to_string() } else { "  ".to_string() };
-                let line = format!("{} [{:04}] {:02X} vs {:02X}  | {} {}", mark, j, a, b, to_char(a), to_char(b));
-                if a != b {
-                    println!("{}", line.yellow().bold());
-                } else {
-                    println!("{}", line);
+                print_context_line(j, a, b, base);
+            }
+            return Ok(());
+        }
+    }
+
+    println!("{}", "Files are identical.".green().bold());
+    Ok(())
+}
+
+/// Like [`diff_files`], but reads both files through fixed-size buffers so
+/// neither is ever held in memory whole.
+pub fn diff_files_streaming<P: AsRef<Path>>(file1: P, file2: P, context: usize) -> io::Result<()> {
+    diff_files_streaming_with(file1, file2, context, OffsetBase::default())
+}
+
+/// [`diff_files_streaming`] with byte offsets printed in `base`.
+pub fn diff_files_streaming_with<P: AsRef<Path>>(file1: P, file2: P, context: usize, base: OffsetBase) -> io::Result<()> {
+    let a = BufReader::new(File::open(&file1)?);
+    let b = BufReader::new(File::open(&file2)?);
+
+    println!("Comparing: {:?} vs {:?}", file1.as_ref(), file2.as_ref());
+
+    diff_readers(a, b, context, base)
+}
+
+/// Streaming form of [`diff_buffers_with`]. Only the last `context` byte
+/// pairs are retained, in a ring buffer, for the lines before a mismatch.
+pub fn diff_readers<A: BufRead, B: BufRead>(mut a: A, mut b: B, context: usize, base: OffsetBase) -> io::Result<()> {
+    let mut history: VecDeque<(u8, u8)> = VecDeque::with_capacity(context);
+    let mut i = 0;
+
+    loop {
+        let (b1, b2) = match (next_byte(&mut a)?, next_byte(&mut b)?) {
+            (None, None) => break,
+            (x, y) => (x.unwrap_or(0), y.unwrap_or(0)),
+        };
+
+        if b1 != b2 {
+            println!("\n{}", format!("Difference at byte {}: {:02X} != {:02X}", base.format(i, 0), b1, b2).red().bold());
+
+            let start = i - history.len();
+            for (k, &(x, y)) in history.iter().enumerate() {
+                print_context_line(start + k, x, y, base);
+            }
+            if context > 0 {
+                print_context_line(i, b1, b2, base);
+            }
+            for j in i + 1..i + context {
+                match (next_byte(&mut a)?, next_byte(&mut b)?) {
+                    (None, None) => break,
+                    (x, y) => print_context_line(j, x.unwrap_or(0), y.unwrap_or(0), base),
                 }
             }
             return Ok(());
         }
+
+        if context > 0 {
+            if history.len() == context {
+                history.pop_front();
+            }
+            history.push_back((b1, b2));
+        }
+        i += 1;
     }
 
     println!("{}", "Files are identical.".green().bold());
     Ok(())
 }
 
+fn next_byte<R: BufRead>(r: &mut R) -> io::Result<Option<u8>> {
+    let byte = r.fill_buf()?.first().copied();
+    if byte.is_some() {
+        r.consume(1);
+    }
+    Ok(byte)
+}
+
+fn print_context_line(offset: usize, a: u8, b: u8, base: OffsetBase) {
+    let mark = if a != b { ">>".yellow().bold().to_string() } else { "  ".to_string() };
+    let line = format!("{} [{}] {:02X} vs {:02X}  | {} {}", mark, base.format(offset, 4), a, b, to_char(a), to_char(b));
+    if a != b {
+        println!("{}", line.yellow().bold());
+    } else {
+        println!("{}", line);
+    }
+}
+
 pub fn diff_blocks<P: AsRef<Path>>(file1: P, file2: P, block_size: usize, max_blocks: usize) -> io::Result<()> {
+    diff_blocks_with(file1, file2, &BlockDiffOptions { block_size, max_blocks, ..Default::default() })
+}
+
+/// [`diff_blocks`] driven by a [`BlockDiffOptions`].
+pub fn diff_blocks_with<P: AsRef<Path>>(file1: P, file2: P, opts: &BlockDiffOptions) -> io::Result<()> {
     let bytes1 = fs::read(&file1)?;
     let bytes2 = fs::read(&file2)?;
-    let len = usize::max(bytes1.len(), bytes2.len());
+    diff_buffers_blocks_with(&bytes1, &bytes2, opts)
+}
 
-    let total_blocks = len / block_size;
+/// In-memory form of [`diff_blocks`].
+pub fn diff_buffers_blocks<A: AsRef<[u8]>, B: AsRef<[u8]>>(a: A, b: B, block_size: usize, max_blocks: usize) -> io::Result<()> {
+    diff_buffers_blocks_with(a, b, &BlockDiffOptions { block_size, max_blocks, ..Default::default() })
+}
+
+/// In-memory form of [`diff_blocks_with`].
+pub fn diff_buffers_blocks_with<A: AsRef<[u8]>, B: AsRef<[u8]>>(a: A, b: B, opts: &BlockDiffOptions) -> io::Result<()> {
+    let (block_size, max_blocks) = (opts.block_size, opts.max_blocks);
+    let bytes1 = a.as_ref();
+    let bytes2 = b.as_ref();
+
+    let mut report = if opts.pattern_only {
+        diff_pattern_blocks_report(bytes1, bytes2, block_size)
+    } else {
+        diff_blocks_report(bytes1, bytes2, block_size)
+    };
+    if opts.sort_by_changes {
+        report.sort_by(|x, y| y.changed.cmp(&x.changed).then(x.block.cmp(&y.block)));
+    }
     let mut shown = 0;
 
-    for block in 0..total_blocks {
-        let start = block * block_size;
-        let chunk1 = &bytes1.get(start..start + block_size).unwrap_or(&[]);
-        let chunk2 = &bytes2.get(start..start + block_size).unwrap_or(&[]);
-
-        if chunk1 != chunk2 {
-            println!("\nBlock {} ({}–{}):", block, start, start + block_size - 1);
-
-            for i in 0..block_size {
-                let b1 = *chunk1.get(i).unwrap_or(&0);
-                let b2 = *chunk2.get(i).unwrap_or(&0);
-                let mark = if b1 != b2 { ">>".yellow().bold().to_string() } else { "  ".to_string() };
-                let line = format!("{} Byte {:05}: {:02X} vs {:02X} | {} {}", mark, start + i, b1, b2, to_char(b1), to_char(b2));
-                if b1 != b2 {
-                    println!("{}", line.yellow().bold());
-                } else {
-                    println!("{}", line);
-                }
-            }
+    let len = bytes1.len().max(bytes2.len());
+    for &BlockDiff { block, start, changed } in &report {
+        let end = (start + block_size).min(len);
+        let chunk1 = bytes1.get(start..end.min(bytes1.len())).unwrap_or(&[]);
+        let chunk2 = bytes2.get(start..end.min(bytes2.len())).unwrap_or(&[]);
 
-            shown += 1;
-            if shown >= max_blocks {
-                println!("\nMax diff blocks reached.");
-                break;
+        let label = if opts.pattern_only { "Cycle" } else { "Block" };
+        let mut heading = format!("{} {} ({}–{}):", label, block, start, end - 1);
+        if opts.rowsum {
+            heading.push_str(&format!(" ^{:02X} vs ^{:02X}", row_checksum(chunk1), row_checksum(chunk2)));
+        }
+        if opts.sort_by_changes {
+            heading.push_str(&format!(" {} byte(s) differ", changed));
+        }
+        println!("\n{}", heading);
+
+        for i in 0..end - start {
+            let (b1, b2) = (chunk1.get(i).copied(), chunk2.get(i).copied());
+            let hex = |b: Option<u8>| b.map_or_else(|| "--".to_string(), |b| format!("{:02X}", b));
+            let ch = |b: Option<u8>| b.map_or(' ', to_char);
+            let mark = if b1 != b2 { ">>".yellow().bold().to_string() } else { "  ".to_string() };
+            let line = format!("{} Byte {:05}: {} vs {} | {} {}", mark, start + i, hex(b1), hex(b2), ch(b1), ch(b2));
+            if b1 != b2 {
+                println!("{}", line.yellow().bold());
+            } else {
+                println!("{}", line);
             }
         }
+
+        shown += 1;
+        if shown >= max_blocks {
+            println!("\nMax diff blocks reached.");
+            break;
+        }
     }
 
     if shown == 0 {
@@ -99,6 +819,65 @@ pub fn diff_blocks<P: AsRef<Path>>(file1: P, file2: P, block_size: usize, max_bl
     Ok(())
 }
 
+/// Writes a standalone HTML hex dump of `buf_a`. When `buf_b` is given, bytes
+/// that differ from it are highlighted in red, mirroring the TUI diff colouring.
+pub fn hex_dump_html<W: Write>(w: &mut W, title: &str, buf_a: &[u8], buf_b: Option<&[u8]>, bytes_per_line: usize) -> io::Result<()> {
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html><head><meta charset=\"utf-8\"><title>{}</title></head>", html_escape(title))?;
+    writeln!(w, "<body style=\"background:#1e1e1e;color:#d4d4d4\">")?;
+    writeln!(w, "<h3>Hex dump of: {} ({} bytes)</h3>", html_escape(title), buf_a.len())?;
+    writeln!(w, "<pre style=\"font-family:monospace\">")?;
+
+    for (i, chunk) in buf_a.chunks(bytes_per_line).enumerate() {
+        let offset = i * bytes_per_line;
+        let mut hex = String::new();
+        let mut ascii = String::new();
+
+        for (j, &a) in chunk.iter().enumerate() {
+            let diff = buf_b.is_some_and(|b| b.get(offset + j) != Some(&a));
+            if j != 0 {
+                hex.push(' ');
+            }
+            let h = format!("{:02X}", a);
+            let c = html_escape(&to_char(a).to_string());
+            if diff {
+                hex.push_str(&format!("<span style=\"color:#f44747\">{}</span>", h));
+                ascii.push_str(&format!("<span style=\"color:#f44747\">{}</span>", c));
+            } else {
+                hex.push_str(&h);
+                ascii.push_str(&c);
+            }
+        }
+
+        // pad a short final line so the ascii gutter stays aligned
+        let pad = (bytes_per_line - chunk.len()) * 3;
+        writeln!(
+            w,
+            "<span style=\"color:#808080\">{:06X}</span>  {}{}  |{}|",
+            offset, hex, " ".repeat(pad), ascii
+        )?;
+    }
+
+    writeln!(w, "</pre>")?;
+    writeln!(w, "</body></html>")?;
+    Ok(())
+}
+
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
 fn to_char(b: u8) -> char {
     if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }
 }
@@ -113,6 +892,17 @@ mod tests {
     use tempfile::NamedTempFile;
     use std::io::Write;
 
+    #[test]
+    fn int_formats_honour_endianness() {
+        let bytes = [0x12, 0x34, 0x56, 0x78];
+        assert_eq!(IntFormat::U16Le.read(&bytes, 0), Some(0x3412));
+        assert_eq!(IntFormat::U16Be.read(&bytes, 2), Some(0x5678));
+        assert_eq!(IntFormat::U32Le.read(&bytes, 0), Some(0x7856_3412));
+        assert_eq!(IntFormat::U32Be.read(&bytes, 0), Some(0x1234_5678));
+        assert_eq!(IntFormat::U32Be.read(&bytes, 1), None);
+        assert_eq!(IntFormat::U16Le.read(&bytes, usize::MAX), None);
+    }
+
     #[test]
     fn hex_dump_does_not_panic() {
         let mut f = NamedTempFile::new().unwrap();
@@ -131,6 +921,177 @@ mod tests {
         assert!(diff_files(a.path(), b.path(), 2).is_ok());
     }
 
+    #[test]
+    fn diff_buffers_detects_change() {
+        assert!(diff_buffers(b"FOO_BAR", b"FOO-XAR", 2).is_ok());
+        assert!(diff_buffers(vec![1u8, 2], [1u8, 2], 2).is_ok());
+        assert!(diff_buffers_with(b"AB", b"AC", 1, OffsetBase::Dec).is_ok());
+    }
+
+    #[test]
+    fn streaming_diff_handles_mismatch_and_length_difference() {
+        let mut a = NamedTempFile::new().unwrap();
+        let mut b = NamedTempFile::new().unwrap();
+        a.write_all(&[7u8; 5000]).unwrap();
+        b.write_all(&[7u8; 4000]).unwrap();
+        assert!(diff_files_streaming(a.path(), b.path(), 8).is_ok());
+
+        assert!(diff_readers(&b"FOO_BAR"[..], &b"FOO-XAR"[..], 2, OffsetBase::Dec).is_ok());
+        assert!(diff_readers(&b"same"[..], &b"same"[..], 0, OffsetBase::Hex).is_ok());
+
+        let mut empty = &b""[..];
+        assert_eq!(next_byte(&mut empty).unwrap(), None);
+    }
+
+    #[test]
+    fn grouping_and_case_change_only_the_hex_column() {
+        let buf: Vec<u8> = (0xA0..0xA8).collect();
+        let dump = |opts: &DumpOptions| {
+            let mut out = Vec::new();
+            hex_dump_with(&mut out, &buf, opts).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let plain = dump(&DumpOptions { bytes_per_line: 8, ..Default::default() });
+        assert_eq!(plain, "000000  A0 A1 A2 A3 A4 A5 A6 A7   |........|\n");
+
+        let grouped = dump(&DumpOptions { bytes_per_line: 8, group: 4, uppercase: false, ..Default::default() });
+        assert_eq!(grouped, "000000  a0 a1 a2 a3  a4 a5 a6 a7   |........|\n");
+    }
+
+    #[test]
+    fn dump_can_print_one_column() {
+        let dump = |columns, rowsum| {
+            let mut out = Vec::new();
+            let opts = DumpOptions { bytes_per_line: 4, columns, rowsum, repeat_header: 5, ..Default::default() };
+            hex_dump_with(&mut out, b"ABCDEF", &opts).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(dump(DumpColumns::HexOnly, false), "OFFSET  00 01 02 03\n000000  41 42 43 44\n000004  45 46\n");
+        assert_eq!(dump(DumpColumns::AsciiOnly, false), "OFFSET  |0123|\n000000  |ABCD|\n000004  |EF|\n");
+        assert!(dump(DumpColumns::HexOnly, true).ends_with("000004  45 46         ^03\n"));
+        assert!(dump(DumpColumns::AsciiOnly, true).ends_with("000004  |EF|    ^03\n"));
+    }
+
+    #[test]
+    fn repeat_header_reprints_the_ruler() {
+        let mut out = Vec::new();
+        let opts = DumpOptions { bytes_per_line: 4, repeat_header: 2, ..Default::default() };
+        hex_dump_with(&mut out, b"ABCDEFGHIJKL", &opts).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "OFFSET  00 01 02 03   |0123|");
+        assert_eq!(lines[1], "000000  41 42 43 44   |ABCD|");
+        assert_eq!(lines[3], lines[0]);
+
+        let mut out = Vec::new();
+        let opts = DumpOptions { bytes_per_line: 12, offset_base: OffsetBase::Dec, repeat_header: 9, ..Default::default() };
+        hex_dump_with(&mut out, b"ABCDEFGHIJKL", &opts).unwrap();
+        assert!(String::from_utf8(out).unwrap().starts_with("OFFSET  00 01 02 03 04 05 06 07 08 09 10 11   |012345678901|\n"));
+    }
+
+    #[test]
+    fn diff_lines_shows_context_hunks() {
+        let a = vec![0u8; 64];
+        let mut b = a.clone();
+        b[4] = 1;
+        b[60] = 2;
+        let mut out = Vec::new();
+        let opts = DumpOptions { bytes_per_line: 8, ..Default::default() };
+        assert_eq!(diff_buffers_lines(&mut out, &a, &b, 1, &opts).unwrap(), 2);
+
+        let text = String::from_utf8(out).unwrap();
+        let heads: Vec<&str> = text.lines().map(|l| l.get(..7).unwrap_or(l)).collect();
+        assert_eq!(heads, vec!["000000-", "000000+", "000008 ", "...", "000030 ", "000038-", "000038+"]);
+
+        let mut same = Vec::new();
+        assert_eq!(diff_buffers_lines(&mut same, &a, &a, 3, &opts).unwrap(), 0);
+        assert!(same.is_empty());
+    }
+
+    #[test]
+    fn side_by_side_pairs_lines_and_marks_differences() {
+        let a = b"0123456789ABCDEF0123";
+        let b = b"0123456789ABCDEF0X23456789ABCDEF!";
+        let opts = SideBySideOptions { color: false, ..Default::default() };
+        let mut out = Vec::new();
+        assert_eq!(diff_side_by_side_with(a, b, &opts, &mut out).unwrap(), 2);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let half = 6 + 2 + 47 + 2 + 18;
+        assert_eq!(&lines[0][half..half + 5], "     ");
+        assert!(lines[0].ends_with("|0123456789ABCDEF|"));
+        assert_eq!(&lines[1][half..half + 5], "  |  ");
+        assert!(lines[1].contains("|0123            |"));
+        assert!(lines[1].ends_with("|0X23456789ABCDEF|"));
+        assert_eq!(&lines[2][half..half + 5], "  >  ");
+        assert!(lines[2].starts_with("000020  "));
+    }
+
+    #[test]
+    fn side_by_side_collapses_identical_runs() {
+        let mut b = vec![0u8; 64];
+        b[40] = 1;
+        let opts = SideBySideOptions { collapse: true, color: false, ..Default::default() };
+        let mut out = Vec::new();
+        assert_eq!(diff_side_by_side_with(&[0u8; 64], &b, &opts, &mut out).unwrap(), 1);
+
+        let text = String::from_utf8(out).unwrap();
+        let heads: Vec<&str> = text.lines().map(|l| &l[..6]).collect();
+        assert_eq!(heads, ["000000", "000020", "000030"]);
+        assert_eq!(text.lines().next(), Some("000000  ... 2 identical lines"));
+    }
+
+    #[test]
+    fn merged_dump_follows_changed_lines_with_b() {
+        let a = b"0123456789ABCDEF0123";
+        let b = b"0123456789ABCDEF0X23!";
+        let mut out = Vec::new();
+        assert_eq!(dump_merged(a, b, 16, &mut out).unwrap(), 1);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        let heads: Vec<&str> = lines.iter().map(|l| l.get(..7).unwrap_or(l)).collect();
+        assert_eq!(heads, vec!["000000 ", "000010-", "000010+", "       "]);
+        assert_eq!(lines[3], format!("{}^^{}^^", " ".repeat(11), " ".repeat(7)));
+        assert!(lines[2].ends_with("|0X23!|"));
+    }
+
+    #[test]
+    fn rowsum_marks_identical_rows_alike() {
+        assert_eq!(row_checksum(&[]), 0);
+        assert_eq!(row_checksum(&[0x0F, 0xF0, 0x01]), 0xFE);
+
+        let mut buf = vec![1u8; 18];
+        buf.extend([1u8; 17]);
+        buf.push(3);
+        let mut out = Vec::new();
+        let opts = DumpOptions { bytes_per_line: 18, rowsum: true, ..Default::default() };
+        hex_dump_with(&mut out, &buf, &opts).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let sums: Vec<&str> = text.lines().map(|l| l.rsplit(' ').next().unwrap()).collect();
+        assert_eq!(sums, vec!["^00", "^02"]);
+
+        let blocks = BlockDiffOptions { rowsum: true, ..Default::default() };
+        assert!(diff_buffers_blocks_with(&buf, [1u8; 36], &blocks).is_ok());
+    }
+
+    #[test]
+    fn offset_base_formats_both_radixes() {
+        assert_eq!(OffsetBase::Hex.format(255, 6), "0000FF");
+        assert_eq!(OffsetBase::Dec.format(255, 6), "000255");
+        assert_eq!(OffsetBase::Dec.format(7, 0), "7");
+
+        let mut out = Vec::new();
+        let opts = DumpOptions { bytes_per_line: 4, offset_base: OffsetBase::Dec, ..Default::default() };
+        hex_dump_with(&mut out, &[0u8; 12], &opts).unwrap();
+        assert!(String::from_utf8(out).unwrap().lines().nth(2).unwrap().starts_with("000008"));
+    }
+
     #[test]
     fn diff_blocks_detects_change() {
         let mut a = NamedTempFile::new().unwrap();
@@ -146,4 +1107,156 @@ mod tests {
         b.write_all(&data_b).unwrap();
         assert!(diff_blocks(a.path(), b.path(), 4, 5).is_ok());
     }
+
+    #[test]
+    fn equate_header_fields_ignores_only_what_is_asked() {
+        let a = b"True V3   MCLK      \x00\x01";
+        let b = b" true  v3 mclk\0\0\0\0\0\0\x01";
+        let ws = TextNormalize { ignore_ws: true, ..Default::default() };
+        let case = TextNormalize { ignore_case: true, ..Default::default() };
+        let both = TextNormalize { ignore_ws: true, ignore_case: true };
+
+        assert_eq!(equate_header_fields(a, b, 20, 10, both), [&a[..20], &b[20..]].concat());
+        assert_eq!(equate_header_fields(a, b, 20, 10, ws), b.to_vec());
+        assert_eq!(&equate_header_fields(a, b, 20, 10, case)[..10], &b[..10]);
+        // the pattern region is never touched
+        assert_eq!(&equate_header_fields(a, b, 20, 10, both)[20..], &b[20..]);
+    }
+
+    #[test]
+    fn lcs_diff_finds_inserted_and_deleted_runs() {
+        assert_eq!(lcs_diff(b"True V3   MCLK", b"True  V3   MCLK"), [
+            DiffOp::Equal { a: 0, b: 0, len: 5 },
+            DiffOp::Insert { a: 5, b: 5, len: 1 },
+            DiffOp::Equal { a: 5, b: 6, len: 9 },
+        ]);
+        assert_eq!(lcs_diff(b"abcXYdef", b"abcdefZ"), [
+            DiffOp::Equal { a: 0, b: 0, len: 3 },
+            DiffOp::Delete { a: 3, b: 3, len: 2 },
+            DiffOp::Equal { a: 5, b: 3, len: 3 },
+            DiffOp::Insert { a: 8, b: 6, len: 1 },
+        ]);
+        assert_eq!(lcs_diff(b"ab", b"xy"), [
+            DiffOp::Delete { a: 0, b: 0, len: 2 },
+            DiffOp::Insert { a: 2, b: 0, len: 2 },
+        ]);
+        assert_eq!(lcs_diff(b"same", b"same"), [DiffOp::Equal { a: 0, b: 0, len: 4 }]);
+        assert!(lcs_diff(b"", b"").is_empty());
+    }
+
+    #[test]
+    fn diff_summary_counts_every_difference() {
+        let a = [0u8; 10];
+        let mut b = [0u8; 12];
+        b[1] = 1;
+        b[2] = 1;
+        b[9] = 1;
+        let summary = diff_summary(&a, &b, 4);
+        assert_eq!((summary.bytes_compared, summary.bytes_differing, summary.blocks_differing), (12, 5, 2));
+        assert_eq!(summary.to_string(), "5 of 12 bytes differ (41.67%), 2 blocks of 4 bytes");
+
+        let summary = diff_summary(&[0; 918_000], &[0; 918_000], 18);
+        assert_eq!(summary.to_string(), "0 of 918,000 bytes differ (0.00%), 0 blocks of 18 bytes");
+        assert_eq!(with_thousands(1_204), "1,204");
+        assert_eq!(with_thousands(100), "100");
+    }
+
+    #[test]
+    fn diff_blocks_report_counts_changed_bytes() {
+        let a = [0u8; 12];
+        let mut b = a;
+        b[1] = 1;
+        b[8] = 1;
+        b[9] = 1;
+        assert_eq!(diff_blocks_report(&a, &b, 4), vec![
+            BlockDiff { block: 0, start: 0, changed: 1 },
+            BlockDiff { block: 2, start: 8, changed: 2 },
+        ]);
+        assert!(diff_blocks_report(&a, &a, 4).is_empty());
+
+        let opts = BlockDiffOptions { block_size: 4, max_blocks: 1, sort_by_changes: true, ..Default::default() };
+        assert!(diff_buffers_blocks_with(a, b, &opts).is_ok());
+    }
+
+    #[test]
+    fn block_report_and_summary_agree_on_unequal_lengths() {
+        // b is two bytes longer, both zero: only present on one side
+        let a = [0u8; 10];
+        let b = [0u8; 12];
+        let report = diff_blocks_report(&a, &b, 4);
+        assert_eq!(report, vec![BlockDiff { block: 2, start: 8, changed: 2 }]);
+
+        // a 9-byte tail block of which one byte differs
+        let mut c = [0u8; 9];
+        c[8] = 7;
+        let report = diff_blocks_report(&a[..9], &c, 4);
+        assert_eq!(report, vec![BlockDiff { block: 2, start: 8, changed: 1 }]);
+
+        let mut d = [0u8; 13];
+        d[1] = 1;
+        for (x, y) in [(&a[..], &b[..]), (&a[..9], &c[..]), (&a[..], &d[..]), (&d[..], &a[..5])] {
+            let report = diff_blocks_report(x, y, 4);
+            let summary = diff_summary(x, y, 4);
+            assert_eq!(summary.blocks_differing, report.len());
+            assert_eq!(summary.bytes_differing, report.iter().map(|r| r.changed).sum::<usize>());
+        }
+
+        let opts = BlockDiffOptions { block_size: 4, ..Default::default() };
+        assert!(diff_buffers_blocks_with(a, d, &opts).is_ok());
+    }
+
+    #[test]
+    fn pattern_block_report_numbers_cycles() {
+        let a = vec![0u8; header_size() + 4 * 18];
+        let mut b = a.clone();
+        b[3] = 1; // header
+        b[header_size() + 2 * 18 + 5] = 1;
+        assert_eq!(diff_pattern_blocks_report(&a, &b, 18), vec![
+            BlockDiff { block: 2, start: header_size() + 36, changed: 1 },
+        ]);
+        assert!(diff_pattern_blocks_report(&a[..10], &b[..10], 18).is_empty());
+
+        let opts = BlockDiffOptions { pattern_only: true, ..Default::default() };
+        assert!(diff_buffers_blocks_with(&a, &b, &opts).is_ok());
+    }
+
+    #[test]
+    fn diff_buffers_blocks_detects_change() {
+        // create two blocks of 4 bytes
+        let block = vec![1u8,2,3,4];
+        let mut data_a = block.clone();
+        data_a.extend_from_slice(&block);
+        let mut data_b = data_a.clone();
+        // flip one byte in second block
+        data_b[5] ^= 0xFF;
+        assert!(diff_buffers_blocks(&data_a, &data_b, 4, 5).is_ok());
+    }
+
+    #[test]
+    fn hex_dump_every_samples_with_absolute_offsets() {
+        let buf: Vec<u8> = (0..64).collect();
+        let mut out = Vec::new();
+        let opts = DumpOptions { bytes_per_line: 8, every: 3, ..Default::default() };
+        hex_dump_with(&mut out, &buf, &opts).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let offsets: Vec<&str> = text.lines().map(|l| &l[..6]).collect();
+        assert_eq!(offsets, vec!["000000", "000018", "000030"]);
+
+        let mut all = Vec::new();
+        hex_dump_with(&mut all, &buf, &DumpOptions { bytes_per_line: 8, every: 0, ..Default::default() }).unwrap();
+        assert_eq!(String::from_utf8(all).unwrap().lines().count(), 8);
+    }
+
+    #[test]
+    fn hex_dump_html_escapes_and_highlights() {
+        let a = b"<A&B>";
+        let b = b"<A&C>";
+        let mut out = Vec::new();
+        hex_dump_html(&mut out, "a.pcf", a, Some(b), 8).unwrap();
+        let html = String::from_utf8(out).unwrap();
+
+        assert!(html.contains("&lt;A&amp;"));
+        assert!(html.contains("<span style=\"color:#f44747\">42</span>"));
+        assert!(!html.contains("<A&B>"));
+    }
 }
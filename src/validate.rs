@@ -0,0 +1,166 @@
+//! Semantic checks over a parsed [`PatternFileData`].
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+use serde::Serialize;
+
+use crate::layout::NUM_TIMINGS;
+use crate::pattern::PatternFileData;
+use crate::timing::parse_timing;
+
+/// A problem found by [`PatternFileData::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ValidationIssue {
+    /// Two loop regions cover overlapping cycle ranges.
+    LoopOverlap { a: usize, b: usize },
+    /// Every pattern byte is zero.
+    BlankPattern,
+    /// `pulse_time[index]` is longer than `cycle_time[index]`.
+    PulseExceedsCycle { index: usize },
+    /// `vtime_reqd[index]` is longer than `cycle_time[index]`.
+    VtimeExceedsCycle { index: usize },
+    /// `compiled_flag` is set but the file holds no compiled pattern: its
+    /// `pattern_file_length` declares no cycles, or every pattern byte is zero.
+    CompiledFlagInconsistent { detail: &'static str },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::LoopOverlap { a, b } => {
+                write!(f, "loop {} overlaps loop {}", a, b)
+            }
+            ValidationIssue::BlankPattern => {
+                write!(f, "pattern region is entirely zero (uninitialized?)")
+            }
+            ValidationIssue::PulseExceedsCycle { index } => {
+                write!(f, "pulse_time[{}] exceeds cycle_time[{}]", index, index)
+            }
+            ValidationIssue::VtimeExceedsCycle { index } => {
+                write!(f, "vtime_reqd[{}] exceeds cycle_time[{}]", index, index)
+            }
+            ValidationIssue::CompiledFlagInconsistent { detail } => {
+                write!(f, "compiled flag is True but {}", detail)
+            }
+        }
+    }
+}
+
+impl PatternFileData {
+    /// Runs every semantic check and returns the issues found, in a stable order.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        for (a, b) in self.overlapping_loops() {
+            issues.push(ValidationIssue::LoopOverlap { a, b });
+        }
+
+        if self.is_pattern_blank() {
+            issues.push(ValidationIssue::BlankPattern);
+        }
+
+        if self.compiled_flag {
+            let detail = if self.pattern_file_length <= 0 {
+                Some("pattern_file_length declares no cycles")
+            } else if self.is_pattern_blank() {
+                Some("the pattern is blank")
+            } else {
+                None
+            };
+            if let Some(detail) = detail {
+                issues.push(ValidationIssue::CompiledFlagInconsistent { detail });
+            }
+        }
+
+        // Blank, unparseable or unit-mismatched entries are skipped.
+        let exceeds = |a: &str, b: &str| match (parse_timing(a), parse_timing(b)) {
+            (Some(a), Some(b)) => a.compare(&b) == Some(Ordering::Greater),
+            _ => false,
+        };
+        for index in 0..NUM_TIMINGS {
+            if exceeds(&self.pulse_time[index], &self.cycle_time[index]) {
+                issues.push(ValidationIssue::PulseExceedsCycle { index });
+            }
+            if exceeds(&self.vtime_reqd[index], &self.cycle_time[index]) {
+                issues.push(ValidationIssue::VtimeExceedsCycle { index });
+            }
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::NUM_BITS;
+
+    #[test]
+    fn validate_reports_loop_overlap() {
+        let mut data = PatternFileData::default();
+        data.start_addrs[0] = 1;
+        data.end_addrs[0] = 10;
+        data.start_addrs[3] = 10;
+        data.end_addrs[3] = 12;
+
+        let issues = data.validate();
+        assert_eq!(issues, vec![ValidationIssue::LoopOverlap { a: 0, b: 3 }]);
+        assert_eq!(issues[0].to_string(), "loop 0 overlaps loop 3");
+    }
+
+    #[test]
+    fn validate_reports_blank_pattern() {
+        let mut data = PatternFileData::builder().pattern_file_length(4).build().unwrap();
+        assert_eq!(data.validate(), vec![ValidationIssue::BlankPattern]);
+
+        data.pattern_data[3][0] = 1;
+        assert!(data.validate().is_empty());
+    }
+
+    #[test]
+    fn validate_reports_compiled_flag_without_a_pattern() {
+        let mut data = PatternFileData::builder().compiled_flag(true).pattern_file_length(4).build().unwrap();
+        let issues = data.validate();
+        assert_eq!(issues, vec![
+            ValidationIssue::BlankPattern,
+            ValidationIssue::CompiledFlagInconsistent { detail: "the pattern is blank" },
+        ]);
+        assert_eq!(issues[1].to_string(), "compiled flag is True but the pattern is blank");
+
+        data.pattern_data[0][1] = 1;
+        assert!(data.validate().is_empty());
+
+        let data = PatternFileData::builder().compiled_flag(true).columns(&[[1; NUM_BITS]; 20]).build().unwrap();
+        assert_eq!(data.validate(), vec![
+            ValidationIssue::CompiledFlagInconsistent { detail: "pattern_file_length declares no cycles" },
+        ]);
+
+        let data = PatternFileData::builder().pattern_file_length(4).build().unwrap();
+        assert_eq!(data.validate(), vec![ValidationIssue::BlankPattern]);
+    }
+
+    #[test]
+    fn validate_reports_timing_inconsistencies() {
+        let mut data = PatternFileData::default();
+        data.cycle_time[0] = "20".into();
+        data.pulse_time[0] = "30".into();
+        data.cycle_time[1] = "1us".into();
+        data.vtime_reqd[1] = "1500ns".into();
+        data.pulse_time[1] = "500ns".into();
+        data.cycle_time[2] = "10ns".into();
+        data.pulse_time[2] = "40".into(); // no unit: not comparable
+        data.pulse_time[3] = "5".into(); // blank cycle
+
+        assert_eq!(data.validate(), vec![
+            ValidationIssue::PulseExceedsCycle { index: 0 },
+            ValidationIssue::VtimeExceedsCycle { index: 1 },
+        ]);
+    }
+
+    #[test]
+    fn validate_default_is_clean() {
+        assert!(PatternFileData::default().validate().is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+//! Import of a pattern from a Value Change Dump (VCD) file.
+//!
+//! Bit `N` is taken from the one-bit signal whose reference name is `bitN`
+//! (case-insensitive, scope ignored); all eighteen must be declared. Each VCD
+//! time unit is one cycle: the value of cycle `t` is the signal state after
+//! all changes at `#t` (or the last earlier timestamp), and the pattern runs
+//! from cycle 0 to the last timestamp inclusive. `$timescale` is ignored, so
+//! a dump in fine units maps to as many cycles as it has units; such input
+//! is refused past a cycle cap rather than allocated. `x`/`z` values are
+//! stored as 0.
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+use crate::codec::DEFAULT_MAX_PATTERN_BYTES;
+use crate::layout::{NUM_BITS, PATTERN_PAD_COLS};
+use crate::pattern::PatternFileData;
+
+/// Cycle cap used by [`read_vcd`]: the cycles that fit in
+/// [`DEFAULT_MAX_PATTERN_BYTES`] at one byte per bit.
+pub const DEFAULT_MAX_VCD_CYCLES: usize = DEFAULT_MAX_PATTERN_BYTES / NUM_BITS;
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Replaces the pattern of `header` with the waveform in `r`, keeping every
+/// other field. Fewer than [`PATTERN_PAD_COLS`] cycles are padded with the
+/// final state so the result is a valid file. Fails if the last timestamp
+/// would need more than [`DEFAULT_MAX_VCD_CYCLES`] cycles.
+pub fn read_vcd<R: Read>(r: R, header: PatternFileData) -> io::Result<PatternFileData> {
+    read_vcd_limited(r, header, DEFAULT_MAX_VCD_CYCLES)
+}
+
+/// Like [`read_vcd`] with an explicit cap on the number of cycles; the
+/// check is made on each timestamp, before any columns are added for it.
+pub fn read_vcd_limited<R: Read>(mut r: R, mut header: PatternFileData, max_cycles: usize) -> io::Result<PatternFileData> {
+    let mut text = String::new();
+    r.read_to_string(&mut text)?;
+    let mut tokens = text.split_whitespace();
+
+    // identifier code -> bit index
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut seen = [false; NUM_BITS];
+
+    while let Some(tok) = tokens.next() {
+        match tok {
+            "$var" => {
+                let var: Vec<&str> = tokens.by_ref().take_while(|t| *t != "$end").collect();
+                // $var <type> <size> <id> <reference> [range]
+                if let [_, size, id, reference, ..] = var[..] {
+                    let bit = reference
+                        .to_ascii_lowercase()
+                        .strip_prefix("bit")
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|&b| b < NUM_BITS);
+                    if let Some(bit) = bit {
+                        if size != "1" {
+                            return Err(invalid(format!("signal `{}` must be 1 bit wide (got {})", reference, size)));
+                        }
+                        ids.insert(id.to_string(), bit);
+                        seen[bit] = true;
+                    }
+                }
+            }
+            "$enddefinitions" => {
+                tokens.by_ref().find(|t| *t == "$end");
+                break;
+            }
+            _ if tok.starts_with('$') => {
+                tokens.by_ref().find(|t| *t == "$end");
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(missing) = seen.iter().position(|s| !s) {
+        return Err(invalid(format!("no 1-bit signal named `bit{}` in VCD header", missing)));
+    }
+
+    let mut state = [0u8; NUM_BITS];
+    let mut columns: Vec<[u8; NUM_BITS]> = Vec::new();
+    let mut last_t: Option<usize> = None;
+
+    while let Some(tok) = tokens.next() {
+        if let Some(t) = tok.strip_prefix('#') {
+            let t: usize = t.parse().map_err(|_| invalid(format!("bad timestamp `{}`", tok)))?;
+            if last_t.is_some_and(|l| t < l) {
+                return Err(invalid(format!("timestamp #{} goes backwards", t)));
+            }
+            if t >= max_cycles {
+                return Err(invalid(format!(
+                    "timestamp #{} needs {} cycles (limit {}); each time unit is one cycle and $timescale is ignored",
+                    t, t as u128 + 1, max_cycles
+                )));
+            }
+            while columns.len() < t {
+                columns.push(state);
+            }
+            last_t = Some(t);
+        } else if tok.starts_with('$') {
+            // $dumpvars / $end / $comment …: value changes inside are still applied
+            if tok == "$comment" {
+                tokens.by_ref().find(|t| *t == "$end");
+            }
+        } else if let Some(bits) = tok.strip_prefix(['b', 'B']) {
+            let id = tokens.next().unwrap_or_default();
+            if let Some(&bit) = ids.get(id) {
+                state[bit] = u8::from(bits.ends_with('1'));
+            }
+        } else if let Some(value) = tok.chars().next() {
+            let id = &tok[value.len_utf8()..];
+            if let Some(&bit) = ids.get(id) {
+                state[bit] = u8::from(value == '1');
+            }
+        }
+    }
+
+    let end = last_t.map_or(0, |t| t + 1).max(PATTERN_PAD_COLS);
+    while columns.len() < end {
+        columns.push(state);
+    }
+
+    header.set_columns(&columns);
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vcd(signals: usize, body: &str) -> String {
+        let mut s = String::from("$timescale 1ns $end\n$scope module top $end\n");
+        for bit in 0..signals {
+            s.push_str(&format!("$var wire 1 s{} bit{} $end\n", bit, bit));
+        }
+        s.push_str("$upscope $end\n$enddefinitions $end\n");
+        s.push_str(body);
+        s
+    }
+
+    #[test]
+    fn reads_changes_as_cycles() {
+        let text = vcd(NUM_BITS, "#0\n$dumpvars\n1s0\n0s5\n$end\n#3\n1s5\n#25\n0s0\n");
+        let data = read_vcd(text.as_bytes(), PatternFileData::default()).unwrap();
+
+        assert_eq!(data.cols(), 26);
+        assert_eq!(data.pattern_file_length, 6);
+        assert_eq!(data.column(0).unwrap()[0], 1);
+        assert_eq!(data.column(2).unwrap()[5], 0);
+        assert_eq!(data.column(3).unwrap()[5], 1);
+        assert_eq!(data.column(24).unwrap()[0], 1);
+        assert_eq!(data.column(25).unwrap()[0], 0);
+    }
+
+    #[test]
+    fn huge_timestamps_hit_the_cycle_cap() {
+        let text = vcd(NUM_BITS, "#0\n1s0\n#4000000000\n0s0\n");
+        let err = read_vcd(text.as_bytes(), PatternFileData::default()).unwrap_err();
+        assert!(err.to_string().contains("#4000000000 needs 4000000001 cycles"), "{}", err);
+
+        let text = vcd(NUM_BITS, "#0\n#99\n");
+        assert!(read_vcd_limited(text.as_bytes(), PatternFileData::default(), 99).is_err());
+        assert_eq!(read_vcd_limited(text.as_bytes(), PatternFileData::default(), 100).unwrap().cols(), 100);
+    }
+
+    #[test]
+    fn missing_signal_is_an_error() {
+        let text = vcd(17, "#0\n");
+        let err = read_vcd(text.as_bytes(), PatternFileData::default()).unwrap_err();
+        assert!(err.to_string().contains("bit17"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Text waveforms of selected bits, for a quick look at a signal without
+//! leaving the terminal.
+//!
+//! Each cycle takes two characters: a level (`_` low, `‾` high), with the
+//! first replaced by `/` or `\` where the bit rises or falls.
+//!
+//! ```text
+//! cycle   0         5
+//! bit  0  __/‾‾‾\_____
+//! bit  3  ‾‾‾‾\___/‾‾‾
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::layout::NUM_BITS;
+
+/// Width of the `bit NN  ` / `cycle   ` label before each row.
+const LABEL_WIDTH: usize = 8;
+
+/// Cycles between labels on the scale row.
+const SCALE_STEP: usize = 5;
+
+/// Renders `bits` over the cycles in `window` (clamped to the columns
+/// available), one row per bit under a cycle scale. Fails on an empty or
+/// out-of-range bit list or an empty window.
+pub fn render_waveform<I>(columns: I, bits: &[usize], window: Range<usize>) -> Result<String, String>
+where
+    I: IntoIterator<Item = [u8; NUM_BITS]>,
+{
+    if bits.is_empty() {
+        return Err("no bits selected".into());
+    }
+    if let Some(bit) = bits.iter().find(|&&b| b >= NUM_BITS) {
+        return Err(format!("bit {} is out of range (0-{})", bit, NUM_BITS - 1));
+    }
+    let cycles: Vec<[u8; NUM_BITS]> = columns.into_iter().take(window.end).skip(window.start).collect();
+    if cycles.is_empty() {
+        return Err(format!("no cycles in {}..{}", window.start, window.end));
+    }
+
+    let mut scale = alloc::vec![' '; cycles.len() * 2];
+    let mut free_from = 0;
+    for i in 0..cycles.len() {
+        let cycle = window.start + i;
+        if !cycle.is_multiple_of(SCALE_STEP) || 2 * i < free_from {
+            continue;
+        }
+        let label = format!("{}", cycle);
+        if 2 * i + label.len() > scale.len() {
+            break;
+        }
+        scale.splice(2 * i..2 * i + label.len(), label.chars());
+        free_from = 2 * i + label.len() + 1;
+    }
+
+    let mut out = format!("{:<LABEL_WIDTH$}{}\n", "cycle", scale.iter().collect::<String>().trim_end());
+    for &bit in bits {
+        let mut row = format!("{:<LABEL_WIDTH$}", format!("bit {:>2}", bit));
+        let mut prev = None;
+        for col in &cycles {
+            let high = col[bit] != 0;
+            let level = if high { '‾' } else { '_' };
+            row.push(match prev {
+                Some(was) if was != high => if high { '/' } else { '\\' },
+                _ => level,
+            });
+            row.push(level);
+            prev = Some(high);
+        }
+        out.push_str(&row);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(bits: &[usize]) -> [u8; NUM_BITS] {
+        core::array::from_fn(|b| u8::from(bits.contains(&b)))
+    }
+
+    #[test]
+    fn draws_levels_transitions_and_scale() {
+        let cols = [column(&[3]), column(&[3]), column(&[0]), column(&[0, 3]), column(&[]), column(&[]), column(&[0])];
+        let text = render_waveform(cols, &[0, 3], 0..100).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines, [
+            "cycle   0         5",
+            "bit  0  ____/‾‾‾\\___/‾",
+            "bit  3  ‾‾‾‾\\_/‾\\_____",
+        ]);
+
+        let window = render_waveform(cols, &[3], 3..5).unwrap();
+        assert_eq!(window.lines().nth(1), Some("bit  3  ‾‾\\_"));
+    }
+
+    #[test]
+    fn rejects_bad_selections() {
+        let cols = [column(&[]); 4];
+        assert!(render_waveform(cols, &[], 0..4).is_err());
+        assert!(render_waveform(cols, &[18], 0..4).is_err());
+        assert!(render_waveform(cols, &[1], 4..8).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Golden-file checks against a real PCF written by the original tooling,
+//! so format regressions show up even when encoder and decoder agree.
+#![cfg(feature = "std")]
+
+use pcf_parser::{encode_to_vec, layout::header_size, parse_pcf_file, parse_pcf_reader, PatternEncoding, PatternFileData};
+
+const FIXTURE: &[u8] = include_bytes!("fixtures/test1.pcf");
+
+fn fixture() -> PatternFileData {
+    parse_pcf_reader(FIXTURE).expect("fixture parses")
+}
+
+#[test]
+fn fixture_layout_is_as_recorded() {
+    assert_eq!(FIXTURE.len(), 1764);
+    assert_eq!(&FIXTURE[..10], b"True V3   ");
+    // the vtime table stores entry 8 first
+    assert_eq!(&FIXTURE[100..110], b"100us     ");
+    assert_eq!(&FIXTURE[110..120], b"488.28ns  ");
+    assert_eq!(FIXTURE.len() - header_size(), 28 * 18);
+}
+
+#[test]
+fn parses_known_header_values() {
+    let data = fixture();
+    assert!(data.compiled_flag());
+    assert_eq!(data.version(), "V3");
+    assert_eq!(data.source_combo_index(), 0);
+    assert_eq!(*data.pclk_source_indices(), [0, 1, 2, 0, 0, 0, 0, 0]);
+    assert_eq!(data.vtime_reqd()[0], "488.28ns");
+    assert_eq!(data.vtime_reqd()[8], "100us");
+    assert_eq!(*data.cycle_time(), ["20", "4", "16", "", "", "", "", "", "4000"]);
+    assert_eq!(*data.pulse_time(), ["10", "1", "1", "", "", "", "", "", "2000"]);
+    assert_eq!(data.clk_sources().len(), 65);
+    assert_eq!(data.clk_sources()[0], "");
+    assert!(data.clk_sources()[1..].iter().all(|s| s == "0"));
+    assert_eq!((*data.start_addrs(), *data.end_addrs(), *data.loop_counts()), ([0; 8], [0; 8], [0; 8]));
+    assert_eq!(data.pattern_file_length(), 8);
+    assert_eq!(data.encoding(), PatternEncoding::BytesPerBit);
+}
+
+#[test]
+fn parses_known_pattern_values() {
+    let data = fixture();
+    assert_eq!(data.cols(), 28);
+    assert_eq!(data.column(0), Some([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]));
+    assert_eq!(data.column(1), Some([255, 255, 255, 255, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]));
+    assert_eq!(data.row_major(17).unwrap()[..9], [1, 1, 1, 1, 1, 1, 1, 1, 0]);
+    assert!(data.columns().skip(8).all(|col| col == [0; 18]));
+}
+
+#[test]
+fn round_trips_byte_for_byte() {
+    let data = fixture();
+    assert_eq!(encode_to_vec(&data).unwrap(), FIXTURE);
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/test1.pcf");
+    assert_eq!(parse_pcf_file(path).unwrap(), data);
+}